@@ -12,7 +12,27 @@ Either way, the output will not only be deduplicated, it will also be sorted.
 
 A duplicate count will also be sent to <stderr>.
 
-For examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_dedup.rs.
+With --fuzzy, near-duplicates are detected instead. Records are compared with a
+similarity metric on the selected columns, and records that are similar enough are
+clustered together (transitively, so if A matches B and B matches C, all three are in
+the same cluster). One survivor per cluster is kept, chosen with --survivor, or every
+record is kept with a cluster id column with --cluster-col. Fuzzy dedup doesn't sort,
+the output is in input order.
+
+As every record is compared to every other record, use --block to only compare records
+that have the same exact values in the blocking columns (e.g. a zip code when deduping
+addresses). This is much faster on large files.
+
+Examples:
+
+  # remove the near-duplicate names within each zip code, keeping the most complete record
+  $ qsv dedup --fuzzy jaro-winkler --threshold 0.92 -s name --block zip \
+      --survivor most-complete people.csv
+
+  # add a cluster id to names that sound alike
+  $ qsv dedup --fuzzy eudex -s name --cluster-col cluster_id people.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_dedup.rs.
 
 Usage:
     qsv dedup [options] [<input>]
@@ -25,6 +45,12 @@ dedup options:
                                See 'qsv select --help' for the format details.
     -N, --numeric              Compare according to string numerical value
     -i, --ignore-case          Compare strings disregarding case.
+    --collate <locale>         Compare strings with the Unicode Collation Algorithm,
+                               using the collation rules of <locale> (e.g. en, de, sv).
+                               Strings that collate equally are duplicates.
+                               See 'qsv sort --help' for details.
+    --accent-insensitive       Compare strings disregarding accents (e.g. "é" = "e").
+                               Uses the root collation if --collate is not set.
     --sorted                   The input is already sorted. Do not load the CSV into
                                memory to sort it first. Meant to be used in tandem and
                                after an extsort.
@@ -37,6 +63,26 @@ dedup options:
                                Does not work with --sorted option as its not
                                multithreaded.
 
+fuzzy dedup options:
+    --fuzzy <metric>           Detect near-duplicates, comparing the selected columns
+                               with <metric>. Valid metrics are:
+                                 jaro-winkler, levenshtein, damerau-levenshtein,
+                                 sorensen-dice and eudex (sounds alike).
+                               The similarity of two records is the mean of the
+                               similarities of their selected columns.
+    --threshold <arg>          The minimum similarity (0.0 to 1.0) for two records
+                               to be near-duplicates. [default: 0.9]
+    --block <arg>              Only compare records with the same values in these
+                               columns. See 'qsv select --help' for the format details.
+    --cluster-col <name>       Keep all the records, and add a column with this name
+                               with the 1-based cluster id of each record.
+    --survivor <rule>          The record kept for each cluster. Valid rules are:
+                                 first - the first record of the cluster
+                                 last - the last record of the cluster
+                                 most-complete - the record with the most non-empty fields
+                                 longest - the record with the most data
+                               Ties are resolved with the first record. [default: first]
+
 Common options:
     -h, --help                 Display this message
     -o, --output <file>        Write output to <file> instead of stdout.
@@ -51,47 +97,153 @@ Common options:
                                CSV into memory using CONSERVATIVE heuristics.
 "#;
 
-use std::cmp;
+use std::{cmp, io};
 
+use ahash::AHashMap;
 use csv::ByteRecord;
+use icu_collator::Collator;
 use rayon::slice::ParallelSliceMut;
 use serde::Deserialize;
 use simdutf8::basic::from_utf8;
 
 use crate::{
-    cmd::sort::{iter_cmp, iter_cmp_num},
+    cmd::sort::{iter_cmp, iter_cmp_collate, iter_cmp_num, new_collator},
     config::{Config, Delimiter},
-    select::SelectColumns,
+    select::{SelectColumns, Selection},
     util, CliResult,
 };
 #[derive(Deserialize)]
 struct Args {
-    arg_input:           Option<String>,
-    flag_select:         SelectColumns,
-    flag_numeric:        bool,
-    flag_ignore_case:    bool,
-    flag_sorted:         bool,
-    flag_dupes_output:   Option<String>,
-    flag_output:         Option<String>,
-    flag_no_headers:     bool,
-    flag_delimiter:      Option<Delimiter>,
-    flag_human_readable: bool,
-    flag_jobs:           Option<usize>,
-    flag_quiet:          bool,
-    flag_memcheck:       bool,
+    arg_input:               Option<String>,
+    flag_select:             SelectColumns,
+    flag_numeric:            bool,
+    flag_ignore_case:        bool,
+    flag_collate:            Option<String>,
+    flag_accent_insensitive: bool,
+    flag_sorted:             bool,
+    flag_dupes_output:       Option<String>,
+    flag_output:             Option<String>,
+    flag_no_headers:         bool,
+    flag_delimiter:          Option<Delimiter>,
+    flag_human_readable:     bool,
+    flag_jobs:               Option<usize>,
+    flag_quiet:              bool,
+    flag_memcheck:           bool,
+    flag_fuzzy:              Option<String>,
+    flag_threshold:          f64,
+    flag_block:              Option<SelectColumns>,
+    flag_cluster_col:        Option<String>,
+    flag_survivor:           String,
 }
 
 enum ComparisonMode {
     Numeric,
     IgnoreCase,
+    Collate(Box<Collator>),
     Normal,
 }
 
+/// A string similarity metric, also used by `join --fuzzy`
+#[derive(Clone, Copy)]
+pub enum FuzzyMetric {
+    JaroWinkler,
+    Levenshtein,
+    DamerauLevenshtein,
+    SorensenDice,
+    Eudex,
+}
+
+impl FuzzyMetric {
+    pub fn from_name(name: &str) -> CliResult<Self> {
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "jaro-winkler" => FuzzyMetric::JaroWinkler,
+            "levenshtein" => FuzzyMetric::Levenshtein,
+            "damerau-levenshtein" => FuzzyMetric::DamerauLevenshtein,
+            "sorensen-dice" => FuzzyMetric::SorensenDice,
+            "eudex" => FuzzyMetric::Eudex,
+            _ => return fail_incorrectusage_clierror!("Invalid --fuzzy metric: {name}"),
+        })
+    }
+
+    /// The similarity of two values, from 0.0 (different) to 1.0 (equal)
+    pub fn similarity(self, a: &str, b: &str) -> f64 {
+        match self {
+            FuzzyMetric::JaroWinkler => strsim::jaro_winkler(a, b),
+            FuzzyMetric::Levenshtein => strsim::normalized_levenshtein(a, b),
+            FuzzyMetric::DamerauLevenshtein => strsim::normalized_damerau_levenshtein(a, b),
+            FuzzyMetric::SorensenDice => strsim::sorensen_dice(a, b),
+            FuzzyMetric::Eudex => {
+                if (eudex::Hash::new(a) - eudex::Hash::new(b)).similar() {
+                    1.0
+                } else {
+                    0.0
+                }
+            },
+        }
+    }
+
+    /// The mean similarity of the values of two keys with the same number of values
+    pub fn mean_similarity(self, a: &[String], b: &[String]) -> f64 {
+        if a.is_empty() {
+            return 1.0;
+        }
+        let total: f64 = a.iter().zip(b).map(|(x, y)| self.similarity(x, y)).sum();
+        total / a.len() as f64
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Survivor {
+    First,
+    Last,
+    MostComplete,
+    Longest,
+}
+
+impl Survivor {
+    fn from_name(name: &str) -> CliResult<Self> {
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "first" => Survivor::First,
+            "last" => Survivor::Last,
+            "most-complete" => Survivor::MostComplete,
+            "longest" => Survivor::Longest,
+            _ => return fail_incorrectusage_clierror!("Invalid --survivor rule: {name}"),
+        })
+    }
+
+    /// Whether `candidate` should replace `current` as the survivor of their cluster.
+    /// `candidate` always comes after `current` in the input.
+    fn prefers(self, current: &ByteRecord, candidate: &ByteRecord) -> bool {
+        match self {
+            Survivor::First => false,
+            Survivor::Last => true,
+            Survivor::MostComplete => {
+                let non_empty = |r: &ByteRecord| r.iter().filter(|f| !f.is_empty()).count();
+                non_empty(candidate) > non_empty(current)
+            },
+            Survivor::Longest => candidate.as_slice().len() > current.as_slice().len(),
+        }
+    }
+}
+
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
 
+    let collator = new_collator(
+        args.flag_collate.as_deref(),
+        args.flag_ignore_case,
+        args.flag_accent_insensitive,
+    )?;
+    if collator.is_some() && (args.flag_numeric || args.flag_fuzzy.is_some()) {
+        return fail_incorrectusage_clierror!(
+            "--collate and --accent-insensitive cannot be used with --numeric or --fuzzy."
+        );
+    }
+
     let compare_mode = if args.flag_numeric {
         ComparisonMode::Numeric
+    } else if let Some(collator) = collator {
+        ComparisonMode::Collate(Box::new(collator))
     } else if args.flag_ignore_case {
         ComparisonMode::IgnoreCase
     } else {
@@ -103,6 +255,29 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         .no_headers(args.flag_no_headers)
         .select(args.flag_select);
 
+    let fuzzy = match args.flag_fuzzy {
+        Some(ref metric) => {
+            if args.flag_sorted || args.flag_numeric {
+                return fail_incorrectusage_clierror!(
+                    "--fuzzy cannot be used with --sorted or --numeric."
+                );
+            }
+            if args.flag_cluster_col.is_some() && args.flag_dupes_output.is_some() {
+                return fail_incorrectusage_clierror!(
+                    "--cluster-col cannot be used with --dupes-output."
+                );
+            }
+            if !(0.0..=1.0).contains(&args.flag_threshold) {
+                return fail_incorrectusage_clierror!("--threshold must be between 0.0 and 1.0.");
+            }
+            Some((
+                FuzzyMetric::from_name(metric)?,
+                Survivor::from_name(&args.flag_survivor)?,
+            ))
+        },
+        None => None,
+    };
+
     let mut rdr = rconfig.reader()?;
     let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
     let dupes_output = args.flag_dupes_output.is_some();
@@ -114,10 +289,52 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     }
     let sel = rconfig.selection(headers)?;
 
-    rconfig.write_headers(&mut rdr, &mut wtr)?;
     let mut dupe_count = 0_usize;
 
-    if args.flag_sorted {
+    if let Some((metric, survivor)) = fuzzy {
+        if let Some(path) = rconfig.path.clone() {
+            util::mem_file_check(&path, false, args.flag_memcheck)?;
+        }
+        let headers = headers.clone();
+        let block_sel = match args.flag_block {
+            Some(block) => Some(block.selection(&headers, !args.flag_no_headers)?),
+            None => None,
+        };
+        let all = rdr.byte_records().collect::<Result<Vec<_>, _>>()?;
+
+        let clusterer = FuzzyClusterer {
+            metric,
+            threshold: args.flag_threshold,
+            ignore_case: args.flag_ignore_case,
+        };
+        let clusters = clusterer.cluster(&all, &sel, block_sel.as_ref());
+        let cluster_count = clusters.iter().max().map_or(0, |&max| max + 1);
+        dupe_count = all.len() - cluster_count;
+
+        if let Some(ref cluster_col) = args.flag_cluster_col {
+            if !args.flag_no_headers {
+                let mut headers = headers;
+                headers.push_field(cluster_col.as_bytes());
+                wtr.write_byte_record(&headers)?;
+            }
+            let mut itoa_buf = itoa::Buffer::new();
+            for (mut record, cluster) in all.into_iter().zip(clusters) {
+                record.push_field(itoa_buf.format(cluster + 1).as_bytes());
+                wtr.write_byte_record(&record)?;
+            }
+        } else {
+            rconfig.write_headers(&mut rdr, &mut wtr)?;
+            write_survivors(
+                &all,
+                &clusters,
+                cluster_count,
+                survivor,
+                &mut wtr,
+                &mut dupewtr,
+            )?;
+        }
+    } else if args.flag_sorted {
+        rconfig.write_headers(&mut rdr, &mut wtr)?;
         let mut record = ByteRecord::new();
         let mut next_record = ByteRecord::new();
 
@@ -134,6 +351,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
                 ComparisonMode::Normal => iter_cmp(a, b),
                 ComparisonMode::Numeric => iter_cmp_num(a, b),
                 ComparisonMode::IgnoreCase => iter_cmp_ignore_case(a, b),
+                ComparisonMode::Collate(ref collator) => iter_cmp_collate(collator, a, b),
             };
             match comparison {
                 cmp::Ordering::Equal => {
@@ -154,6 +372,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
             }
         }
     } else {
+        rconfig.write_headers(&mut rdr, &mut wtr)?;
         // we're loading the entire file into memory, we need to check avail mem
         if let Some(path) = rconfig.path.clone() {
             util::mem_file_check(&path, false, args.flag_memcheck)?;
@@ -184,6 +403,13 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
                     iter_cmp_ignore_case(a, b)
                 });
             },
+            ComparisonMode::Collate(ref collator) => {
+                all.par_sort_by(|r1, r2| {
+                    let a = sel.select(r1);
+                    let b = sel.select(r2);
+                    iter_cmp_collate(collator, a, b)
+                });
+            },
         }
 
         for (current, current_record) in all.iter().enumerate() {
@@ -221,6 +447,16 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
                             wtr.write_byte_record(current_record)?;
                         }
                     },
+                    ComparisonMode::Collate(ref collator) => {
+                        if iter_cmp_collate(collator, a, b) == cmp::Ordering::Equal {
+                            dupe_count += 1;
+                            if dupes_output {
+                                dupewtr.write_byte_record(current_record)?;
+                            }
+                        } else {
+                            wtr.write_byte_record(current_record)?;
+                        }
+                    },
                 }
             } else {
                 wtr.write_byte_record(current_record)?;
@@ -246,6 +482,116 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     Ok(())
 }
 
+/// Clusters near-duplicate records.
+struct FuzzyClusterer {
+    metric:      FuzzyMetric,
+    threshold:   f64,
+    ignore_case: bool,
+}
+
+impl FuzzyClusterer {
+    /// Returns the 0-based cluster id of every record. Cluster ids are numbered
+    /// in the order of their first record.
+    fn cluster(
+        &self,
+        records: &[ByteRecord],
+        sel: &Selection,
+        block_sel: Option<&Selection>,
+    ) -> Vec<usize> {
+        let values: Vec<Vec<String>> = records
+            .iter()
+            .map(|record| {
+                sel.select(record)
+                    .map(|field| {
+                        let value = String::from_utf8_lossy(field);
+                        if self.ignore_case {
+                            value.to_lowercase()
+                        } else {
+                            value.into_owned()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut blocks: AHashMap<Vec<&[u8]>, Vec<usize>> = AHashMap::new();
+        for (i, record) in records.iter().enumerate() {
+            let block_key = block_sel.map_or_else(Vec::new, |bsel| bsel.select(record).collect());
+            blocks.entry(block_key).or_default().push(i);
+        }
+
+        // union-find, where the root of a cluster is its first record
+        let mut parent: Vec<usize> = (0..records.len()).collect();
+        for block in blocks.values() {
+            for (n, &i) in block.iter().enumerate() {
+                for &j in &block[n + 1..] {
+                    let (root_i, root_j) = (find_root(&mut parent, i), find_root(&mut parent, j));
+                    if root_i != root_j && self.is_match(&values[i], &values[j]) {
+                        parent[root_i.max(root_j)] = root_i.min(root_j);
+                    }
+                }
+            }
+        }
+
+        let mut cluster_ids = vec![0; records.len()];
+        let mut next_id = 0;
+        for i in 0..records.len() {
+            let root = find_root(&mut parent, i);
+            if root == i {
+                cluster_ids[i] = next_id;
+                next_id += 1;
+            } else {
+                // the root comes before i, so it already has its id
+                cluster_ids[i] = cluster_ids[root];
+            }
+        }
+        cluster_ids
+    }
+
+    fn is_match(&self, a: &[String], b: &[String]) -> bool {
+        self.metric.mean_similarity(a, b) >= self.threshold
+    }
+}
+
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Write the survivor of every cluster in input order, and the other records
+/// of the clusters to `dupewtr`.
+fn write_survivors<W: io::Write, D: io::Write>(
+    records: &[ByteRecord],
+    clusters: &[usize],
+    cluster_count: usize,
+    survivor: Survivor,
+    wtr: &mut csv::Writer<W>,
+    dupewtr: &mut csv::Writer<D>,
+) -> CliResult<()> {
+    let mut survivors: Vec<Option<usize>> = vec![None; cluster_count];
+    for (i, &cluster) in clusters.iter().enumerate() {
+        match survivors[cluster] {
+            Some(current) if !survivor.prefers(&records[current], &records[i]) => {},
+            _ => survivors[cluster] = Some(i),
+        }
+    }
+    // a later record can survive its cluster, so sort the survivors back in input order
+    let mut survivor_indices: Vec<usize> = survivors.iter().flatten().copied().collect();
+    survivor_indices.sort_unstable();
+    for i in survivor_indices {
+        wtr.write_byte_record(&records[i])?;
+    }
+    for (i, &cluster) in clusters.iter().enumerate() {
+        if survivors[cluster] != Some(i) {
+            dupewtr.write_byte_record(&records[i])?;
+        }
+    }
+    Ok(())
+}
+
 /// Try comparing `a` and `b` ignoring the case
 #[inline]
 pub fn iter_cmp_ignore_case<'a, L, R>(mut a: L, mut b: R) -> cmp::Ordering
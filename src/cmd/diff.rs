@@ -35,6 +35,22 @@ in the result (equal field values are replaced with the empty string). Key
 field values _will_ appear in the output:
     qsv diff --drop-equal-fields left.csv right.csv
 
+Find the difference between two CSVs keyed by the id column, treating prices within
+a cent and names that only differ in case or whitespace as equal, and ignoring the
+updated_at column:
+    qsv diff -k id --tolerance price=0.01 --ignore-case name --ignore-whitespace name \
+      --ignore-columns updated_at left.csv right.csv
+
+Summarize the difference between two CSVs as JSON, with the number of added, deleted
+and modified rows and the number of modified rows per column:
+    qsv diff -k id --summary left.csv right.csv
+
+Find the difference between two CSVs that are larger than memory, in bounded memory.
+Both CSVs are already sorted by the key column (e.g. with `qsv extsort -s id`):
+    qsv diff -k id --sorted left.csv right.csv
+    # or let diff sort them into temporary files first
+    qsv diff -k id --extsort --tmp-dir /tmp left.csv right.csv
+
 Find the difference between two CSVs, but do not output headers in the result:
     qsv diff --no-headers-output left.csv right.csv
 
@@ -69,10 +85,8 @@ diff options:
     -k, --key <arg...>          The column indices that uniquely identify a record
                                 as a comma separated list of indices, e.g. 0,1,2
                                 or column names, e.g. name,age.
-                                Note that when selecting columns by name, only the 
-                                left CSV's headers are used to match the column names
-                                and it is assumed that the right CSV has the same
-                                selected column names in the same order as the left CSV.
+                                When selecting columns by name, the right CSV must have
+                                the selected columns at the same positions as the left CSV.
                                 (default: 0)
     --sort-columns <arg...>     The column indices by which the diff result should be
                                 sorted as a comma separated list of indices, e.g. 0,1,2
@@ -82,13 +96,51 @@ diff options:
                                 but have different content) will always be kept together
                                 in the sorted diff result and so won't be sorted
                                 independently from each other.
-                                Note that when selecting columns by name, only the 
-                                left CSV's headers are used to match the column names
-                                and it is assumed that the right CSV has the same
-                                selected column names in the same order as the left CSV.
+                                When selecting columns by name, the right CSV must have
+                                the selected columns at the same positions as the left CSV.
     --drop-equal-fields         Drop values of equal fields in modified rows of the CSV
                                 diff result (and replace them with the empty string).
                                 Key field values will not be dropped.
+    --ignore-columns <arg>      The columns whose differences are ignored, as a comma
+                                separated list of indices or names, or * for all columns.
+                                Rows that only differ in these columns are not modified.
+    --ignore-case <arg>         The columns to compare case-insensitively, as a comma
+                                separated list of indices or names, or * for all columns.
+    --ignore-whitespace <arg>   The columns to compare ignoring leading & trailing
+                                whitespace and treating runs of whitespace as a single
+                                space, as a comma separated list of indices or names,
+                                or * for all columns.
+    --tolerance <arg>           The numeric tolerance of columns, as a comma separated
+                                list of <column>=<tolerance>, where <column> is an index,
+                                a name or * for all columns. The tolerance is absolute,
+                                e.g. price=0.01, or relative to the larger value when
+                                followed by %, e.g. weight=0.5%. Numbers within the
+                                tolerance are equal. Non-numeric values are compared
+                                as is.
+                                The comparison options only apply to non-key columns,
+                                as records are matched by their exact key values.
+    --summary                   Instead of the diff result, output a JSON summary with
+                                the number of added, deleted and modified rows, and the
+                                number of modified rows per column.
+
+                                STREAMING DIFF OPTIONS:
+    --sorted                    Both CSVs are sorted by the key columns (compared as bytes,
+                                in key column order, as `qsv sort` and `qsv extsort` do).
+                                Instead of loading both CSVs in memory, they're diffed with
+                                a streaming sort-merge in bounded memory, so CSVs larger than
+                                memory can be diffed. Fails if a CSV is not sorted.
+                                The diff result is in key order, and rows with duplicate
+                                keys are paired in order.
+    --extsort                   Like --sorted, but the CSVs are first sorted by the key
+                                columns into temporary files with an external merge sort.
+    --memory-limit <arg>        The maximum amount of memory to buffer the --extsort
+                                external merge sort. If less than 50, this is a percentage
+                                of total memory. If more than 50, this is the memory in MB
+                                to allocate, capped at 90 percent of total memory.
+                                [default: 10]
+    --tmp-dir <arg>             The directory for the --extsort temporary files.
+                                [default: ./]
+
     -j, --jobs <arg>            The number of jobs to run in parallel.
                                 When not set, the number of jobs is set to the number
                                 of CPUs detected.
@@ -98,16 +150,22 @@ Common options:
     -o, --output <file>         Write output to <file> instead of stdout.
 "#;
 
-use std::io::{self, Write};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    io::{self, Write},
+    path::Path,
+};
 
 use csv::ByteRecord;
 use csv_diff::{
     csv_diff::CsvByteDiffBuilder, csv_headers::Headers, diff_result::DiffByteRecords,
     diff_row::DiffByteRecord,
 };
+use ext_sort::{buffer::mem::MemoryLimitedBufferBuilder, ExternalSorter, ExternalSorterBuilder};
 use serde::Deserialize;
 
-use super::rename::rename_headers_all_generic;
+use super::{extdedup::calculate_memory_limit, rename::rename_headers_all_generic};
 use crate::{
     clitypes::CliError,
     config::{Config, Delimiter},
@@ -129,8 +187,19 @@ struct Args {
     flag_key:               Option<String>,
     flag_sort_columns:      Option<String>,
     flag_drop_equal_fields: bool,
+    flag_ignore_columns:    Option<String>,
+    flag_ignore_case:       Option<String>,
+    flag_ignore_whitespace: Option<String>,
+    flag_tolerance:         Option<String>,
+    flag_summary:           bool,
+    flag_sorted:            bool,
+    flag_extsort:           bool,
+    flag_memory_limit:      Option<u64>,
+    flag_tmp_dir:           Option<String>,
 }
 
+const RW_BUFFER_CAPACITY: usize = 1_000_000; // 1 MB
+
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
 
@@ -151,104 +220,143 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let mut csv_rdr_left = rconfig_left.reader()?;
     let mut csv_rdr_right = rconfig_right.reader()?;
 
-    let headers_left = csv_rdr_left.byte_headers()?;
-    let headers_right = csv_rdr_right.byte_headers()?;
+    let headers_left = csv_rdr_left.byte_headers()?.clone();
+    let headers_right = csv_rdr_right.byte_headers()?.clone();
 
+    let columns = ColumnResolver {
+        headers_left:  &headers_left,
+        headers_right: &headers_right,
+        check_right:   !args.flag_no_headers_right,
+    };
     let primary_key_cols: Vec<usize> = match args.flag_key {
         None => vec![0],
-        Some(s) => {
-            // check if the key is a comma separated list of numbers
-            if s.chars().all(|c: char| c.is_numeric() || c == ',') {
-                s.split(',')
-                    .map(str::parse::<usize>)
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|err| CliError::Other(err.to_string()))?
-            } else {
-                // check if the key is a comma separated list of column names
-                let left_key_indices = s
-                    .split(',')
-                    .enumerate()
-                    .map(|(index, col_name)| {
-                        headers_left
-                            .iter()
-                            .position(|h| h == col_name.as_bytes())
-                            .ok_or_else(|| {
-                                CliError::Other(format!(
-                                    "Column name '{col_name}' not found on left CSV"
-                                ))
-                            })
-                            .map(|pos| pos + index + 1)
-                    })
-                    .collect::<Result<Vec<usize>, _>>()?;
-
-                // now check if the right CSV has the same selected colnames in the same locations
-                let right_key_indices = s
-                    .split(',')
-                    .enumerate()
-                    .map(|(index, col_name)| {
-                        headers_right
-                            .iter()
-                            .position(|h| h == col_name.as_bytes())
-                            .ok_or_else(|| {
-                                CliError::Other(format!(
-                                    "Column name '{col_name}' not found on right CSV"
-                                ))
-                            })
-                            .map(|pos| pos + index + 1)
-                    })
-                    .collect::<Result<Vec<usize>, _>>()?;
-
-                if left_key_indices != right_key_indices {
-                    return fail_incorrectusage_clierror!(
-                        "Column names on left and right CSVs do not match.\nUse `qsv select` to \
-                         reorder the columns on the right CSV to match the order of the left \
-                         CSV.\nThe key column indices on the left CSV are in index \
-                         locations:\n{left_key_indices:?}\nand on the right CSV \
-                         are:\n{right_key_indices:?}",
-                    );
-                }
-                left_key_indices
+        Some(ref s) => columns.resolve_list(s)?,
+    };
+    let sort_cols = args
+        .flag_sort_columns
+        .as_deref()
+        .map(|s| columns.resolve_list(s))
+        .transpose()?;
+
+    let num_columns = headers_left.len().max(headers_right.len());
+    let mut rules = vec![ColumnRule::default(); num_columns];
+    if let Some(ref s) = args.flag_ignore_columns {
+        for col in columns.resolve_list_or_all(s, num_columns)? {
+            rules[col].ignore = true;
+        }
+    }
+    if let Some(ref s) = args.flag_ignore_case {
+        for col in columns.resolve_list_or_all(s, num_columns)? {
+            rules[col].ignore_case = true;
+        }
+    }
+    if let Some(ref s) = args.flag_ignore_whitespace {
+        for col in columns.resolve_list_or_all(s, num_columns)? {
+            rules[col].ignore_whitespace = true;
+        }
+    }
+    if let Some(ref s) = args.flag_tolerance {
+        for spec in s.split(',') {
+            let Some((col, tolerance)) = spec.rsplit_once('=') else {
+                return fail_incorrectusage_clierror!(
+                    "Invalid --tolerance '{spec}'. Expected <column>=<number>[%]."
+                );
+            };
+            let tolerance = Tolerance::parse(tolerance)?;
+            for col in columns.resolve_list_or_all(col, num_columns)? {
+                rules[col].tolerance = Some(tolerance);
             }
+        }
+    }
+    let comparator = Comparator {
+        rules: if rules.iter().all(ColumnRule::is_exact) {
+            // no rules, so the byte-for-byte comparison of csv_diff stands
+            Vec::new()
+        } else {
+            rules
         },
     };
 
-    let sort_cols = args
-        .flag_sort_columns
-        .map(|s| {
-            // check if the sort columns are a comma separated list of numbers
-            if s.chars().all(|c: char| c.is_numeric() || c == ',') {
-                s.split(',')
-                    .map(str::parse::<usize>)
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|err| CliError::Other(err.to_string()))
-            } else {
-                // check if the sort columns is a comma separated list of column names
-                let left_sort_indices = s
-                    .split(',')
-                    .enumerate()
-                    .map(|(index, col_name)| {
-                        headers_left
-                            .iter()
-                            .position(|h| h == col_name.as_bytes())
-                            .ok_or_else(|| {
-                                CliError::Other(format!(
-                                    "Column name '{col_name}' not found on left CSV"
-                                ))
-                            })
-                            .map(|pos| pos + index + 1)
-                    })
-                    .collect::<Result<Vec<usize>, _>>()?;
-
-                Ok(left_sort_indices)
+    // the column names for the summary, before the readers are consumed
+    let summary_names: Vec<String> = if args.flag_no_headers_left && args.flag_no_headers_right {
+        (1..=num_columns).map(|i| format!("_col_{i}")).collect()
+    } else {
+        let headers = if args.flag_no_headers_left {
+            &headers_right
+        } else {
+            &headers_left
+        };
+        headers
+            .iter()
+            .map(|h| String::from_utf8_lossy(h).to_string())
+            .collect()
+    };
+
+    let njobs = util::njobs(args.flag_jobs);
+
+    if args.flag_sorted || args.flag_extsort {
+        if sort_cols.is_some() {
+            return fail_incorrectusage_clierror!(
+                "--sort-columns cannot be used with --sorted or --extsort, as the diff result is \
+                 in key order."
+            );
+        }
+        let sorter = if args.flag_extsort {
+            let tmp_dir = args.flag_tmp_dir.as_deref().unwrap_or("./");
+            if !Path::new(tmp_dir).exists() {
+                return fail_clierror!("tmp-dir '{tmp_dir}' does not exist");
             }
-        })
-        .transpose()?;
+            let mem_limited_buffer_bytes = calculate_memory_limit(args.flag_memory_limit);
+            log::info!("{mem_limited_buffer_bytes} bytes used for in memory mergesort buffer...");
+            match ExternalSorterBuilder::new()
+                .with_tmp_dir(Path::new(tmp_dir))
+                .with_buffer(MemoryLimitedBufferBuilder::new(mem_limited_buffer_bytes))
+                .with_rw_buf_size(RW_BUFFER_CAPACITY)
+                .with_threads_number(njobs)
+                .build()
+            {
+                Ok(sorter) => Some(sorter),
+                Err(e) => {
+                    return fail_clierror!("cannot create external sorter: {e}");
+                },
+            }
+        } else {
+            None
+        };
+        let (left, right) = match sorter {
+            Some(ref sorter) => (
+                KeyedRecords::extsorted(csv_rdr_left, &primary_key_cols, "left", sorter)?,
+                KeyedRecords::extsorted(csv_rdr_right, &primary_key_cols, "right", sorter)?,
+            ),
+            None => (
+                KeyedRecords::sorted(csv_rdr_left, &primary_key_cols, "left"),
+                KeyedRecords::sorted(csv_rdr_right, &primary_key_cols, "right"),
+            ),
+        };
 
-    let wtr = Config::new(args.flag_output.as_ref())
-        .delimiter(args.flag_delimiter_output)
-        .writer()?;
+        if args.flag_summary {
+            let mut summary = DiffSummary::default();
+            merge_diff(left, right, &comparator, &mut summary)?;
+            return write_summary(&summary, &summary_names, args.flag_output.as_ref());
+        }
 
-    util::njobs(args.flag_jobs);
+        let wtr = Config::new(args.flag_output.as_ref())
+            .delimiter(args.flag_delimiter_output)
+            .writer()?;
+        let mut csv_diff_writer = CsvDiffWriter::new(
+            wtr,
+            args.flag_no_headers_output,
+            args.flag_drop_equal_fields,
+            primary_key_cols,
+        );
+        csv_diff_writer.write_headers(
+            (!args.flag_no_headers_left).then_some(&headers_left),
+            (!args.flag_no_headers_right).then_some(&headers_right),
+            Some(&num_columns),
+        )?;
+        merge_diff(left, right, &comparator, &mut csv_diff_writer)?;
+        return Ok(csv_diff_writer.csv_writer.flush()?);
+    }
 
     let Ok(csv_diff) = CsvByteDiffBuilder::new()
         .primary_key_columns(primary_key_cols.clone())
@@ -272,13 +380,522 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         },
     }
 
+    if args.flag_summary {
+        let mut summary = DiffSummary::default();
+        comparator.send_diff_byte_records(&mut summary, diff_byte_records)?;
+        return write_summary(&summary, &summary_names, args.flag_output.as_ref());
+    }
+
+    let wtr = Config::new(args.flag_output.as_ref())
+        .delimiter(args.flag_delimiter_output)
+        .writer()?;
     let mut csv_diff_writer = CsvDiffWriter::new(
         wtr,
         args.flag_no_headers_output,
         args.flag_drop_equal_fields,
         primary_key_cols,
     );
-    Ok(csv_diff_writer.write_diff_byte_records(diff_byte_records)?)
+    Ok(csv_diff_writer.write_diff_byte_records(diff_byte_records, &comparator)?)
+}
+
+/// The records of one side of a streaming diff, in key order.
+struct KeyedRecords {
+    records:  Box<dyn Iterator<Item = CliResult<ByteRecord>>>,
+    key_cols: Vec<usize>,
+    side:     &'static str,
+    current:  Option<ByteRecord>,
+    previous: Option<ByteRecord>,
+}
+
+impl KeyedRecords {
+    fn new(
+        records: Box<dyn Iterator<Item = CliResult<ByteRecord>>>,
+        key_cols: &[usize],
+        side: &'static str,
+    ) -> Self {
+        Self {
+            records,
+            key_cols: key_cols.to_vec(),
+            side,
+            current: None,
+            previous: None,
+        }
+    }
+
+    /// The records of a CSV that is already sorted by the key columns.
+    fn sorted(
+        rdr: csv::Reader<Box<dyn io::Read + Send + 'static>>,
+        key_cols: &[usize],
+        side: &'static str,
+    ) -> Self {
+        let records = rdr
+            .into_byte_records()
+            .map(|record| record.map_err(CliError::from));
+        Self::new(Box::new(records), key_cols, side)
+    }
+
+    /// The records of a CSV, sorted by the key columns with an external merge sort.
+    fn extsorted(
+        rdr: csv::Reader<Box<dyn io::Read + Send + 'static>>,
+        key_cols: &[usize],
+        side: &'static str,
+        sorter: &ExternalSorter<Vec<u8>, io::Error, MemoryLimitedBufferBuilder>,
+    ) -> CliResult<Self> {
+        let items = rdr.into_byte_records().map(|record| {
+            record
+                .map(|record| {
+                    let key = key_cols.iter().map(|&i| record.get(i).unwrap_or_default());
+                    encode_sort_item(key, &record)
+                })
+                .map_err(io::Error::from)
+        });
+        let sorted = match sorter.sort_by(items, |a: &Vec<u8>, b: &Vec<u8>| a.cmp(b)) {
+            Ok(sorted) => sorted,
+            Err(e) => {
+                return fail!(format!("cannot do external sort of {side} CSV: {e:?}"));
+            },
+        };
+        let num_key_cols = key_cols.len();
+        let records = sorted.map(move |item| -> CliResult<ByteRecord> {
+            match item {
+                Ok(item) => Ok(decode_sort_item(&item, num_key_cols)),
+                Err(e) => fail!(format!("cannot do external sort of {side} CSV: {e:?}")),
+            }
+        });
+        Ok(Self::new(Box::new(records), key_cols, side))
+    }
+
+    /// Move to the next record, checking that the records are in key order.
+    fn advance(&mut self) -> CliResult<()> {
+        let next = self.records.next().transpose()?;
+        self.previous = std::mem::replace(&mut self.current, next);
+        if let (Some(previous), Some(current)) = (&self.previous, &self.current) {
+            if cmp_keys(previous, current, &self.key_cols) == Ordering::Greater {
+                return fail_incorrectusage_clierror!(
+                    "The {} CSV is not sorted by the key columns. Sort it with `qsv sort` or `qsv \
+                     extsort`, or use --extsort.",
+                    self.side
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare the key values of two records.
+fn cmp_keys(a: &ByteRecord, b: &ByteRecord, key_cols: &[usize]) -> Ordering {
+    for &i in key_cols {
+        let ord = a
+            .get(i)
+            .unwrap_or_default()
+            .cmp(b.get(i).unwrap_or_default());
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Diff two inputs sorted by key with a streaming sort-merge, in bounded memory.
+/// Records with the same key are paired in order.
+fn merge_diff(
+    mut left: KeyedRecords,
+    mut right: KeyedRecords,
+    comparator: &Comparator,
+    sink: &mut impl DiffSink,
+) -> CliResult<()> {
+    left.advance()?;
+    right.advance()?;
+    let mut field_indices = Vec::new();
+    loop {
+        let (advance_left, advance_right) = match (&left.current, &right.current) {
+            (None, None) => break,
+            (Some(delete), None) => {
+                sink.delete(delete)?;
+                (true, false)
+            },
+            (None, Some(add)) => {
+                sink.add(add)?;
+                (false, true)
+            },
+            (Some(delete), Some(add)) => match cmp_keys(delete, add, &left.key_cols) {
+                Ordering::Less => {
+                    sink.delete(delete)?;
+                    (true, false)
+                },
+                Ordering::Greater => {
+                    sink.add(add)?;
+                    (false, true)
+                },
+                Ordering::Equal => {
+                    field_indices.clear();
+                    field_indices.extend(
+                        (0..delete.len().max(add.len())).filter(|&i| delete.get(i) != add.get(i)),
+                    );
+                    if !field_indices.is_empty() {
+                        comparator.send_modify(sink, delete, add, &field_indices)?;
+                    }
+                    (true, true)
+                },
+            },
+        };
+        if advance_left {
+            left.advance()?;
+        }
+        if advance_right {
+            right.advance()?;
+        }
+    }
+    Ok(())
+}
+
+/// Encode a record for an external sort, as its key values followed by all its values,
+/// so that the items sort by key. The values are escaped so they don't contain
+/// NUL bytes, which terminate each value and sort before any other byte.
+/// Also used by the sort-merge join of `qsv join`.
+pub fn encode_sort_item<'a>(
+    key: impl Iterator<Item = &'a [u8]>,
+    record: &'a ByteRecord,
+) -> Vec<u8> {
+    let mut item = Vec::with_capacity(record.as_slice().len() * 2);
+    for field in key.chain(record.iter()) {
+        for &byte in field {
+            match byte {
+                0 => item.extend_from_slice(&[1, 2]),
+                1 => item.extend_from_slice(&[1, 3]),
+                byte => item.push(byte),
+            }
+        }
+        item.push(0);
+    }
+    item
+}
+
+/// Decode the record of an external sort item, skipping its key values.
+pub fn decode_sort_item(item: &[u8], num_key_cols: usize) -> ByteRecord {
+    let mut record = ByteRecord::new();
+    let mut field = Vec::new();
+    let mut escaped = false;
+    let mut skip = num_key_cols;
+    for &byte in item {
+        if escaped {
+            field.push(byte - 2);
+            escaped = false;
+        } else if byte == 1 {
+            escaped = true;
+        } else if byte == 0 {
+            if skip > 0 {
+                skip -= 1;
+            } else {
+                record.push_field(&field);
+            }
+            field.clear();
+        } else {
+            field.push(byte);
+        }
+    }
+    record
+}
+
+fn write_summary(
+    summary: &DiffSummary,
+    names: &[String],
+    output: Option<&String>,
+) -> CliResult<()> {
+    let mut wtr = Config::new(output).io_writer()?;
+    serde_json::to_writer_pretty(&mut wtr, &summary.to_json(names))?;
+    writeln!(wtr)?;
+    Ok(wtr.flush()?)
+}
+
+/// Resolves the column selections of the diff options to column indices.
+pub(crate) struct ColumnResolver<'a> {
+    pub(crate) headers_left:  &'a ByteRecord,
+    pub(crate) headers_right: &'a ByteRecord,
+    pub(crate) check_right:   bool,
+}
+
+impl ColumnResolver<'_> {
+    /// Resolve a comma separated list of column indices or names.
+    /// Names are looked up in the left CSV's headers, and must be at the
+    /// same position in the right CSV's headers, if it has headers.
+    pub(crate) fn resolve_list(&self, s: &str) -> CliResult<Vec<usize>> {
+        // check if the columns are a comma separated list of numbers
+        if s.chars().all(|c: char| c.is_numeric() || c == ',') {
+            return s
+                .split(',')
+                .map(str::parse::<usize>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| CliError::Other(err.to_string()));
+        }
+
+        let mut indices = Vec::new();
+        for col_name in s.split(',') {
+            let Some(pos) = self
+                .headers_left
+                .iter()
+                .position(|h| h == col_name.as_bytes())
+            else {
+                return fail_incorrectusage_clierror!(
+                    "Column name '{col_name}' not found on left CSV"
+                );
+            };
+            if self.check_right && self.headers_right.get(pos) != Some(col_name.as_bytes()) {
+                return match self
+                    .headers_right
+                    .iter()
+                    .position(|h| h == col_name.as_bytes())
+                {
+                    Some(right_pos) => fail_incorrectusage_clierror!(
+                        "Column names on left and right CSVs do not match.\nUse `qsv select` to \
+                         reorder the columns on the right CSV to match the order of the left \
+                         CSV.\nColumn '{col_name}' is in index location {pos} on the left CSV and \
+                         {right_pos} on the right CSV."
+                    ),
+                    None => fail_incorrectusage_clierror!(
+                        "Column name '{col_name}' not found on right CSV"
+                    ),
+                };
+            }
+            indices.push(pos);
+        }
+        Ok(indices)
+    }
+
+    /// Like `resolve_list`, but `*` selects all the columns.
+    fn resolve_list_or_all(&self, s: &str, num_columns: usize) -> CliResult<Vec<usize>> {
+        let indices = if s == "*" {
+            (0..num_columns).collect()
+        } else {
+            self.resolve_list(s)?
+        };
+        if let Some(col) = indices.iter().find(|&&col| col >= num_columns) {
+            return fail_incorrectusage_clierror!(
+                "Column index {col} is out of range. The CSVs have {num_columns} columns."
+            );
+        }
+        Ok(indices)
+    }
+}
+
+/// The numeric tolerance of a column.
+#[derive(Clone, Copy)]
+enum Tolerance {
+    Absolute(f64),
+    /// a fraction of the larger absolute value
+    Relative(f64),
+}
+
+impl Tolerance {
+    fn parse(s: &str) -> CliResult<Self> {
+        let s = s.trim();
+        let (num, relative) = match s.strip_suffix('%') {
+            Some(num) => (num, true),
+            None => (s, false),
+        };
+        match num.trim().parse::<f64>() {
+            Ok(n) if n >= 0.0 && n.is_finite() => Ok(if relative {
+                Tolerance::Relative(n / 100.0)
+            } else {
+                Tolerance::Absolute(n)
+            }),
+            _ => fail_incorrectusage_clierror!("Invalid tolerance '{s}'."),
+        }
+    }
+
+    fn within(self, a: f64, b: f64) -> bool {
+        let diff = (a - b).abs();
+        match self {
+            Tolerance::Absolute(t) => diff <= t,
+            Tolerance::Relative(t) => diff <= t * a.abs().max(b.abs()),
+        }
+    }
+}
+
+/// How the values of a column are compared.
+#[derive(Clone, Copy, Default)]
+struct ColumnRule {
+    ignore:            bool,
+    ignore_case:       bool,
+    ignore_whitespace: bool,
+    tolerance:         Option<Tolerance>,
+}
+
+impl ColumnRule {
+    const fn is_exact(&self) -> bool {
+        !self.ignore && !self.ignore_case && !self.ignore_whitespace && self.tolerance.is_none()
+    }
+
+    fn normalize<'a>(&self, value: &'a [u8]) -> Cow<'a, str> {
+        let mut value = String::from_utf8_lossy(value);
+        if self.ignore_whitespace {
+            value = Cow::Owned(value.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        if self.ignore_case {
+            value = Cow::Owned(value.to_lowercase());
+        }
+        value
+    }
+
+    fn equal(&self, left: &[u8], right: &[u8]) -> bool {
+        if self.ignore {
+            return true;
+        }
+        let (left, right) = (self.normalize(left), self.normalize(right));
+        if let Some(tolerance) = self.tolerance {
+            if let (Ok(l), Ok(r)) = (left.trim().parse::<f64>(), right.trim().parse::<f64>()) {
+                return tolerance.within(l, r);
+            }
+        }
+        left == right
+    }
+}
+
+/// Applies the per-column comparison rules to the fields csv_diff found modified.
+struct Comparator {
+    /// the rule of each column, empty when all the columns are compared exactly
+    rules: Vec<ColumnRule>,
+}
+
+impl Comparator {
+    /// The indices of the fields that are still modified under the column rules.
+    /// If empty, the records are considered equal.
+    fn modified_fields(
+        &self,
+        delete: &ByteRecord,
+        add: &ByteRecord,
+        field_indices: &[usize],
+    ) -> Vec<usize> {
+        if self.rules.is_empty() {
+            return field_indices.to_vec();
+        }
+        field_indices
+            .iter()
+            .copied()
+            .filter(|&i| {
+                let rule = self.rules.get(i).copied().unwrap_or_default();
+                !rule.equal(
+                    delete.get(i).unwrap_or_default(),
+                    add.get(i).unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    /// Send a modified row to `sink`, unless the records are equal under the column rules.
+    fn send_modify(
+        &self,
+        sink: &mut impl DiffSink,
+        delete: &ByteRecord,
+        add: &ByteRecord,
+        field_indices: &[usize],
+    ) -> io::Result<()> {
+        let field_indices = self.modified_fields(delete, add, field_indices);
+        if field_indices.is_empty() {
+            return Ok(());
+        }
+        sink.modify(delete, add, &field_indices)
+    }
+
+    /// Send all the records of a csv_diff result to `sink`.
+    fn send_diff_byte_records(
+        &self,
+        sink: &mut impl DiffSink,
+        diff_byte_records: DiffByteRecords,
+    ) -> io::Result<()> {
+        for dbr in diff_byte_records {
+            match dbr {
+                DiffByteRecord::Add(add) => sink.add(add.byte_record())?,
+                DiffByteRecord::Delete(delete) => sink.delete(delete.byte_record())?,
+                DiffByteRecord::Modify {
+                    delete,
+                    add,
+                    field_indices,
+                } => {
+                    self.send_modify(
+                        sink,
+                        delete.byte_record(),
+                        add.byte_record(),
+                        &field_indices,
+                    )?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The destination of the rows of a diff result.
+trait DiffSink {
+    fn add(&mut self, add: &ByteRecord) -> io::Result<()>;
+    fn delete(&mut self, delete: &ByteRecord) -> io::Result<()>;
+    /// `field_indices` are the indices of the modified fields.
+    fn modify(
+        &mut self,
+        delete: &ByteRecord,
+        add: &ByteRecord,
+        field_indices: &[usize],
+    ) -> io::Result<()>;
+}
+
+/// Counts the added, deleted and modified rows of a diff result.
+#[derive(Default)]
+struct DiffSummary {
+    added:         u64,
+    deleted:       u64,
+    modified:      u64,
+    /// the number of modified rows per column
+    column_counts: Vec<u64>,
+}
+
+impl DiffSummary {
+    /// The JSON summary, with the modified row counts of the columns with modifications.
+    fn to_json(&self, names: &[String]) -> serde_json::Value {
+        let mut modified_columns = serde_json::Map::new();
+        for (i, &count) in self.column_counts.iter().enumerate() {
+            if count > 0 {
+                let name = names
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(|| format!("_col_{}", i + 1));
+                modified_columns.insert(name, count.into());
+            }
+        }
+        serde_json::json!({
+            "added": self.added,
+            "deleted": self.deleted,
+            "modified": self.modified,
+            "modified_columns": modified_columns,
+        })
+    }
+}
+
+impl DiffSink for DiffSummary {
+    fn add(&mut self, _add: &ByteRecord) -> io::Result<()> {
+        self.added += 1;
+        Ok(())
+    }
+
+    fn delete(&mut self, _delete: &ByteRecord) -> io::Result<()> {
+        self.deleted += 1;
+        Ok(())
+    }
+
+    fn modify(
+        &mut self,
+        _delete: &ByteRecord,
+        _add: &ByteRecord,
+        field_indices: &[usize],
+    ) -> io::Result<()> {
+        self.modified += 1;
+        for &i in field_indices {
+            if i >= self.column_counts.len() {
+                self.column_counts.resize(i + 1, 0);
+            }
+            self.column_counts[i] += 1;
+        }
+        Ok(())
+    }
 }
 
 struct CsvDiffWriter<W: Write> {
@@ -303,8 +920,13 @@ impl<W: Write> CsvDiffWriter<W> {
         }
     }
 
-    fn write_headers(&mut self, headers: &Headers, num_columns: Option<&usize>) -> csv::Result<()> {
-        match (headers.headers_left(), headers.headers_right()) {
+    fn write_headers(
+        &mut self,
+        headers_left: Option<&ByteRecord>,
+        headers_right: Option<&ByteRecord>,
+        num_columns: Option<&usize>,
+    ) -> csv::Result<()> {
+        match (headers_left, headers_right) {
             (Some(lbh), Some(_rbh)) => {
                 // currently, `diff` can only handle two CSVs that have the same
                 // headers ordering, so in this case we can either choose the left
@@ -332,69 +954,22 @@ impl<W: Write> CsvDiffWriter<W> {
         Ok(())
     }
 
-    fn write_diff_byte_records(&mut self, diff_byte_records: DiffByteRecords) -> io::Result<()> {
+    fn write_diff_byte_records(
+        &mut self,
+        diff_byte_records: DiffByteRecords,
+        comparator: &Comparator,
+    ) -> io::Result<()> {
+        let headers: &Headers = diff_byte_records.headers();
         self.write_headers(
-            diff_byte_records.headers(),
+            headers.headers_left(),
+            headers.headers_right(),
             diff_byte_records.num_columns().as_ref(),
         )?;
-        for dbr in diff_byte_records {
-            self.write_diff_byte_record(&dbr)?;
-        }
+        comparator.send_diff_byte_records(self, diff_byte_records)?;
         self.csv_writer.flush()?;
         Ok(())
     }
 
-    fn write_diff_byte_record(&mut self, diff_byte_record: &DiffByteRecord) -> csv::Result<()> {
-        let add_sign: &[u8] = &b"+"[..];
-        let remove_sign: &[u8] = &b"-"[..];
-
-        match diff_byte_record {
-            DiffByteRecord::Add(add) => {
-                let mut vec = vec![add_sign];
-                vec.extend(add.byte_record());
-                self.csv_writer.write_record(vec)
-            },
-            DiffByteRecord::Modify {
-                delete,
-                add,
-                field_indices,
-            } => {
-                let vec_del = if self.drop_equal_fields {
-                    self.fill_modified_and_drop_equal_fields(
-                        remove_sign,
-                        delete.byte_record(),
-                        field_indices.as_slice(),
-                    )
-                } else {
-                    let mut tmp = vec![remove_sign];
-                    tmp.extend(delete.byte_record());
-                    tmp
-                };
-
-                self.csv_writer.write_record(vec_del)?;
-
-                let vec_add = if self.drop_equal_fields {
-                    self.fill_modified_and_drop_equal_fields(
-                        add_sign,
-                        add.byte_record(),
-                        field_indices.as_slice(),
-                    )
-                } else {
-                    let mut tmp = vec![add_sign];
-                    tmp.extend(add.byte_record());
-                    tmp
-                };
-
-                self.csv_writer.write_record(vec_add)
-            },
-            DiffByteRecord::Delete(del) => {
-                let mut vec = vec![remove_sign];
-                vec.extend(del.byte_record());
-                self.csv_writer.write_record(vec)
-            },
-        }
-    }
-
     fn fill_modified_and_drop_equal_fields<'a>(
         &self,
         prefix: &'a [u8],
@@ -411,13 +986,59 @@ impl<W: Write> CsvDiffWriter<W> {
         // key field values and modified field values should appear in the output
         for &key_field in self.key_fields.iter().chain(modified_field_indices) {
             // + 1 here, because of the prefix value (see above)
-            vec_to_fill[key_field + 1] = &byte_record[key_field];
+            if let Some(field) = byte_record.get(key_field) {
+                vec_to_fill[key_field + 1] = field;
+            }
         }
 
         vec_to_fill
     }
 }
 
+impl<W: Write> DiffSink for CsvDiffWriter<W> {
+    fn add(&mut self, add: &ByteRecord) -> io::Result<()> {
+        let mut vec = vec![ADD_SIGN];
+        vec.extend(add);
+        Ok(self.csv_writer.write_record(vec)?)
+    }
+
+    fn delete(&mut self, delete: &ByteRecord) -> io::Result<()> {
+        let mut vec = vec![REMOVE_SIGN];
+        vec.extend(delete);
+        Ok(self.csv_writer.write_record(vec)?)
+    }
+
+    fn modify(
+        &mut self,
+        delete: &ByteRecord,
+        add: &ByteRecord,
+        field_indices: &[usize],
+    ) -> io::Result<()> {
+        let vec_del = if self.drop_equal_fields {
+            self.fill_modified_and_drop_equal_fields(REMOVE_SIGN, delete, field_indices)
+        } else {
+            let mut tmp = vec![REMOVE_SIGN];
+            tmp.extend(delete);
+            tmp
+        };
+
+        self.csv_writer.write_record(vec_del)?;
+
+        let vec_add = if self.drop_equal_fields {
+            self.fill_modified_and_drop_equal_fields(ADD_SIGN, add, field_indices)
+        } else {
+            let mut tmp = vec![ADD_SIGN];
+            tmp.extend(add);
+            tmp
+        };
+
+        Ok(self.csv_writer.write_record(vec_add)?)
+    }
+}
+
+const ADD_SIGN: &[u8] = b"+";
+const REMOVE_SIGN: &[u8] = b"-";
+
 trait WriteDiffResultHeader {
     fn write_diffresult_header<W: Write>(&self, csv_writer: &mut csv::Writer<W>)
         -> csv::Result<()>;
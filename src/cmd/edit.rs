@@ -20,8 +20,29 @@ flashlight,gray
 You may also choose to specify the column name by its index (in this case 1).
 Specifying a column as a number is prioritized by index rather than name.
 
+To apply many corrections in a single pass, list them in a corrections CSV with
+"column" & "value" columns, and either a "row" column with the row indices of the cells,
+or with --key, the key columns identifying their rows (with the same names as in the input).
+For instance, with the following corrections.csv:
+
+item,column,value
+shoes,color,green
+flashlight,item,torch
+
+Running `qsv edit --corrections corrections.csv --key item items.csv` returns:
+
+item,color
+shoes,green
+torch,gray
+
+A key correction applies to all the rows with its key. Corrections of the same cell are
+applied in order. With --audit, the old and new values of every edited cell are written
+to a CSV. Corrections whose row, key or column is not in the input are not applied,
+and are reported in an error after the output is written.
+
 Usage:
     qsv edit [options] <input> <column> <row> <value>
+    qsv edit [options] --corrections <file> <input>
     qsv edit --help
 
 edit arguments:
@@ -32,34 +53,92 @@ edit arguments:
     row                    The cell's row index. Indices start from the first non-header row as 0.
     value                  The new value to replace the old cell content with.
 
+edit options:
+    --corrections <file>   The CSV of corrections to apply. See above.
+    -k, --key <cols>       With --corrections, the key columns identifying the rows
+                           to edit, instead of row indices.
+                           See 'qsv select --help' for the format details.
+    --audit <file>         With --corrections, write the row, column, old value and
+                           new value of every edited cell to <file>.
+
 Common options:
     -h, --help             Display this message
     -o, --output <file>    Write output to <file> instead of stdout.
     -n, --no-headers       Start row indices from the header row as 0 (allows editing the header row).
 "#;
 
+use ahash::AHashMap;
+use csv::{ByteRecord, StringRecord};
 use serde::Deserialize;
 
-use crate::{config::Config, util, CliResult};
+use crate::{
+    config::Config,
+    select::{SelectColumns, Selection},
+    util, CliResult,
+};
 
 #[allow(dead_code)]
 #[derive(Deserialize)]
 struct Args {
-    arg_input:       Option<String>,
-    arg_column:      String,
-    arg_row:         usize,
-    arg_value:       String,
-    flag_output:     Option<String>,
-    flag_no_headers: bool,
+    arg_input:        Option<String>,
+    arg_column:       Option<String>,
+    arg_row:          Option<usize>,
+    arg_value:        Option<String>,
+    flag_corrections: Option<String>,
+    flag_key:         Option<SelectColumns>,
+    flag_audit:       Option<String>,
+    flag_output:      Option<String>,
+    flag_no_headers:  bool,
+}
+
+/// A cell correction of the --corrections file
+struct Correction {
+    /// the line of the correction in the corrections file, for error messages
+    line:         u64,
+    target:       String,
+    column:       String,
+    column_index: usize,
+    value:        String,
+    applied:      bool,
+}
+
+/// The corrections to apply to each row, as indices of the corrections
+enum Targets {
+    Rows(AHashMap<usize, Vec<usize>>),
+    Keys(Selection, AHashMap<Vec<Vec<u8>>, Vec<usize>>),
+}
+
+/// The index of a column given by name, by index, or as "_" for the last column
+fn column_index(headers: &StringRecord, column: &str) -> Option<usize> {
+    if column == "_" {
+        headers.len().checked_sub(1)
+    } else if let Ok(c) = column.parse::<usize>() {
+        Some(c)
+    } else {
+        headers.iter().position(|header| header == column)
+    }
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
 
+    if let Some(ref corrections) = args.flag_corrections {
+        if args.arg_column.is_some() {
+            return fail_incorrectusage_clierror!(
+                "Cannot use --corrections with <column>, <row> and <value>."
+            );
+        }
+        return edit_corrections(&args, corrections);
+    }
+    if args.flag_key.is_some() || args.flag_audit.is_some() {
+        return fail_incorrectusage_clierror!("--key and --audit require --corrections.");
+    }
+
     let input = args.arg_input;
-    let column = args.arg_column;
-    let row = args.arg_row;
-    let value = args.arg_value;
+    // safety: <column>, <row> and <value> are required without --corrections
+    let column = args.arg_column.unwrap();
+    let row = args.arg_row.unwrap();
+    let value = args.arg_value.unwrap();
     let no_headers = args.flag_no_headers;
 
     // Build the CSV reader and iterate over each record.
@@ -68,19 +147,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
 
     let headers = rdr.headers()?;
-    let mut column_index: Option<usize> = None;
-    if column == "_" {
-        column_index = Some(headers.len() - 1);
-    } else if let Ok(c) = column.parse::<usize>() {
-        column_index = Some(c);
-    } else {
-        for (i, header) in headers.iter().enumerate() {
-            if column.as_str() == header {
-                column_index = Some(i);
-                break;
-            }
-        }
-    }
+    let column_index = column_index(headers, &column);
     if column_index.is_none() {
         return fail_clierror!("Invalid column selected.");
     }
@@ -106,3 +173,166 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     Ok(wtr.flush()?)
 }
+
+/// Apply the corrections of the --corrections file in a single pass over the input
+fn edit_corrections(args: &Args, corrections_path: &str) -> CliResult<()> {
+    let conf = Config::new(args.arg_input.as_ref()).no_headers(true);
+    let mut rdr = conf.reader()?;
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+    let headers = rdr.headers()?.clone();
+
+    let key_sel = match args.flag_key {
+        Some(_) if args.flag_no_headers => {
+            return fail_incorrectusage_clierror!("--key cannot be used with --no-headers.");
+        },
+        Some(ref key) => Some(key.selection(rdr.byte_headers()?, true)?),
+        None => None,
+    };
+
+    let mut corrections_rdr = Config::new(Some(corrections_path.to_string()).as_ref()).reader()?;
+    let corrections_headers = corrections_rdr.headers()?.clone();
+    let find_column = |name: &str| -> CliResult<usize> {
+        match corrections_headers.iter().position(|h| h == name) {
+            Some(i) => Ok(i),
+            None => fail_incorrectusage_clierror!("The corrections file has no \"{name}\" column."),
+        }
+    };
+    let column_col = find_column("column")?;
+    let value_col = find_column("value")?;
+    let target_cols = match key_sel {
+        Some(ref sel) => sel
+            .iter()
+            .map(|&i| find_column(&headers[i]))
+            .collect::<CliResult<Vec<usize>>>()?,
+        None => vec![find_column("row")?],
+    };
+
+    let mut targets = match key_sel {
+        Some(sel) => Targets::Keys(sel, AHashMap::new()),
+        None => Targets::Rows(AHashMap::new()),
+    };
+    let mut corrections = Vec::new();
+    let mut errors = Vec::new();
+    let mut correction_record = StringRecord::new();
+    while corrections_rdr.read_record(&mut correction_record)? {
+        let line = correction_record.position().map_or(0, csv::Position::line);
+        let target = target_cols
+            .iter()
+            .map(|&i| &correction_record[i])
+            .collect::<Vec<&str>>()
+            .join(",");
+        let column = &correction_record[column_col];
+        let Some(col) = column_index(&headers, column).filter(|&col| col < headers.len()) else {
+            errors.push(format!("line {line}: column \"{column}\" not found"));
+            continue;
+        };
+
+        let correction_index = corrections.len();
+        match targets {
+            Targets::Rows(ref mut rows) => {
+                let Ok(row) = correction_record[target_cols[0]].parse::<usize>() else {
+                    return fail_incorrectusage_clierror!(
+                        "Invalid row \"{target}\" on line {line} of the corrections file."
+                    );
+                };
+                rows.entry(row).or_default().push(correction_index);
+            },
+            Targets::Keys(_, ref mut keys) => {
+                let key = target_cols
+                    .iter()
+                    .map(|&i| correction_record[i].as_bytes().to_vec())
+                    .collect();
+                keys.entry(key).or_default().push(correction_index);
+            },
+        }
+        corrections.push(Correction {
+            line,
+            target,
+            column: column.to_string(),
+            column_index: col,
+            value: correction_record[value_col].to_string(),
+            applied: false,
+        });
+    }
+
+    let mut audit_wtr = match args.flag_audit {
+        Some(ref audit_path) => {
+            let mut audit_wtr = Config::new(Some(audit_path)).writer()?;
+            audit_wtr.write_record(["row", "column", "old_value", "new_value"])?;
+            Some(audit_wtr)
+        },
+        None => None,
+    };
+
+    let mut record = ByteRecord::new();
+    let mut fields: Vec<Vec<u8>> = Vec::new();
+    let mut itoa_buf = itoa::Buffer::new();
+    let mut records_read: usize = 0;
+    while rdr.read_byte_record(&mut record)? {
+        // with --no-headers, the header row is row 0. Otherwise, it can't be edited.
+        let row = if args.flag_no_headers {
+            Some(records_read)
+        } else {
+            records_read.checked_sub(1)
+        };
+        records_read += 1;
+
+        let edits = match (row, &targets) {
+            (None, _) => None,
+            (Some(row), Targets::Rows(rows)) => rows.get(&row),
+            (Some(_), Targets::Keys(sel, keys)) => {
+                let key: Vec<Vec<u8>> = sel.select(&record).map(<[u8]>::to_vec).collect();
+                keys.get(&key)
+            },
+        };
+        let (Some(row), Some(edits)) = (row, edits) else {
+            wtr.write_byte_record(&record)?;
+            continue;
+        };
+
+        fields.clear();
+        fields.extend(record.iter().map(<[u8]>::to_vec));
+        for &i in edits {
+            let correction = &mut corrections[i];
+            let Some(field) = fields.get_mut(correction.column_index) else {
+                continue;
+            };
+            if let Some(ref mut audit_wtr) = audit_wtr {
+                audit_wtr.write_record([
+                    itoa_buf.format(row).as_bytes(),
+                    headers[correction.column_index].as_bytes(),
+                    field,
+                    correction.value.as_bytes(),
+                ])?;
+            }
+            correction.value.as_bytes().clone_into(field);
+            correction.applied = true;
+        }
+        wtr.write_record(&fields)?;
+    }
+    wtr.flush()?;
+    if let Some(mut audit_wtr) = audit_wtr {
+        audit_wtr.flush()?;
+    }
+
+    let target_name = if matches!(targets, Targets::Rows(_)) {
+        "row"
+    } else {
+        "key"
+    };
+    errors.extend(corrections.iter().filter(|c| !c.applied).map(|c| {
+        format!(
+            "line {}: {target_name} \"{}\" not found for column \"{}\"",
+            c.line, c.target, c.column
+        )
+    }));
+    if !errors.is_empty() {
+        return fail_clierror!(
+            "{} correction(s) could not be applied:\n{}",
+            errors.len(),
+            errors.join("\n")
+        );
+    }
+
+    Ok(())
+}
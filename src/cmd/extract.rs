@@ -0,0 +1,188 @@
+static USAGE: &str = r#"
+Extracts the named capture groups of a regex applied to a column into new columns,
+e.g. to parse codes, ids or amounts out of free text.
+
+Every named capture group - (?P<name>...) or (?<name>...) - of the regex becomes a new
+column, appended after the existing columns and named after the group. Unnamed groups
+are not extracted. Groups that don't participate in a match are empty.
+
+For instance, extracting '(?P<code>[A-Z]{3})-(?P<num>\d+)' from the "notes" column of:
+
+id,notes
+1,see ABC-12 and XYZ-7
+2,no code
+
+gives:
+
+id,notes,code,num
+1,see ABC-12 and XYZ-7,ABC,12
+2,no code,,
+
+With --all, every match is extracted into its own row, repeating the other columns:
+
+id,notes,code,num
+1,see ABC-12 and XYZ-7,ABC,12
+1,see ABC-12 and XYZ-7,XYZ,7
+2,no code,,
+
+Examples:
+
+  # extract the area code and number of phone numbers
+  $ qsv extract '\((?P<area>\d{3})\) (?P<number>\d{3}-\d{4})' phone data.csv
+
+  # extract all the hashtags of tweets, with a row per hashtag
+  $ qsv extract '#(?P<hashtag>\w+)' text --all --matches-only tweets.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_extract.rs.
+
+Usage:
+    qsv extract [options] <regex> <column> [<input>]
+    qsv extract --help
+
+extract arguments:
+    <regex>                Regular expression with named capture groups. Uses Rust regex syntax.
+                           See https://docs.rs/regex/latest/regex/index.html#syntax
+                           or https://regex101.com with the Rust flavor for more info.
+    <column>               The column to apply the regex to.
+                           See 'qsv select --help' for the format details.
+    <input>                The CSV file to read. If not given, reads from stdin.
+
+extract options:
+    -a, --all              Extract all the matches of the regex, with a row per match.
+                           Otherwise, only the first match is extracted.
+    --matches-only         Only output the rows where the regex matches. Otherwise,
+                           rows without a match are output with empty new columns.
+    --prefix <arg>         Prefix the names of the new columns with <arg>, e.g. to
+                           avoid clashes with existing columns.
+    -i, --ignore-case      Case insensitive search. This is equivalent to
+                           prefixing the regex with '(?i)'.
+    -u, --unicode          Enable unicode support. When enabled, character classes
+                           will match all unicode word characters instead of only
+                           ASCII word characters. Decreases performance.
+    --size-limit <mb>      Set the approximate size limit (MB) of the compiled
+                           regular expression. If the compiled expression exceeds this
+                           number, then a compilation error is returned.
+                           [default: 50]
+    --dfa-size-limit <mb>  Set the approximate size of the cache (MB) used by the regular
+                           expression engine's Discrete Finite Automata.
+                           [default: 10]
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. The new columns are then not named.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+"#;
+
+use csv::ByteRecord;
+use regex::bytes::RegexBuilder;
+use serde::Deserialize;
+
+use crate::{
+    config::{Config, Delimiter},
+    select::SelectColumns,
+    util, CliResult,
+};
+
+#[derive(Deserialize)]
+struct Args {
+    arg_regex:           String,
+    arg_column:          SelectColumns,
+    arg_input:           Option<String>,
+    flag_all:            bool,
+    flag_matches_only:   bool,
+    flag_prefix:         Option<String>,
+    flag_ignore_case:    bool,
+    flag_unicode:        bool,
+    flag_size_limit:     usize,
+    flag_dfa_size_limit: usize,
+    flag_output:         Option<String>,
+    flag_no_headers:     bool,
+    flag_delimiter:      Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+
+    let regex_unicode = if util::get_envvar_flag("QSV_REGEX_UNICODE") {
+        true
+    } else {
+        args.flag_unicode
+    };
+    let pattern = RegexBuilder::new(&args.arg_regex)
+        .case_insensitive(args.flag_ignore_case)
+        .unicode(regex_unicode)
+        .size_limit(args.flag_size_limit * (1 << 20))
+        .dfa_size_limit(args.flag_dfa_size_limit * (1 << 20))
+        .build()?;
+
+    // the indices & names of the named capture groups, in the order of the regex
+    let groups: Vec<(usize, &str)> = pattern
+        .capture_names()
+        .enumerate()
+        .filter_map(|(i, name)| name.map(|name| (i, name)))
+        .collect();
+    if groups.is_empty() {
+        return fail_incorrectusage_clierror!(
+            "The regex has no named capture groups, e.g. (?P<name>...), to extract."
+        );
+    }
+
+    let rconfig = Config::new(args.arg_input.as_ref())
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers)
+        .select(args.arg_column);
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+
+    let mut headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+    if sel.len() != 1 {
+        return fail_incorrectusage_clierror!("Only one column can be selected to extract from.");
+    }
+    let column_index = sel[0];
+
+    if !rconfig.no_headers {
+        let prefix = args.flag_prefix.as_deref().unwrap_or_default();
+        for (_, name) in &groups {
+            let column_name = format!("{prefix}{name}");
+            if headers.iter().any(|h| h == column_name.as_bytes()) {
+                return fail_incorrectusage_clierror!(
+                    "The \"{column_name}\" column already exists. Use --prefix to rename the \
+                     extracted columns."
+                );
+            }
+            headers.push_field(column_name.as_bytes());
+        }
+        wtr.write_byte_record(&headers)?;
+    }
+
+    let mut record = ByteRecord::new();
+    let mut output = ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        let mut matched = false;
+        for caps in pattern.captures_iter(&record[column_index]) {
+            matched = true;
+            output.clone_from(&record);
+            for (i, _) in &groups {
+                output.push_field(caps.get(*i).map_or(&b""[..], |m| m.as_bytes()));
+            }
+            wtr.write_byte_record(&output)?;
+            if !args.flag_all {
+                break;
+            }
+        }
+        if !matched && !args.flag_matches_only {
+            output.clone_from(&record);
+            for _ in &groups {
+                output.push_field(b"");
+            }
+            wtr.write_byte_record(&output)?;
+        }
+    }
+
+    Ok(wtr.flush()?)
+}
@@ -4,10 +4,12 @@ Sort an arbitrarily large CSV/text file using a multithreaded external sort algo
 This command has TWO modes of operation.
 
  * CSV MODE
-   when --select is set, it sorts based on the given column/s. Requires an index.
+   when --select or --keys is set, it sorts based on the given column/s. Requires an index.
    See `qsv select --help` for select syntax details.
+   The index is used to sample the sort keys and split the file into key ranges that are
+   sorted in parallel, so both the run generation and the merge phases use all the cores.
  * LINE MODE
-   when --select is NOT set, it sorts any input text file (not just CSVs) on a
+   when neither --select nor --keys is set, it sorts any input text file (not just CSVs) on a
    line-by-line basis. If sorting a non-CSV file, be sure to set --no-headers, 
    otherwise, the first line will not be included in the external sort.
 
@@ -20,7 +22,22 @@ External sort option:
                            Note that the outputs will remain at the full width of the CSV.
                            If --select is NOT set, extsort will work in LINE MODE, sorting
                            the input as a text file on a line-by-line basis.
+    -k, --keys <spec>      Sort using per-column key specifications (CSV MODE),
+                           e.g. -k date:desc:date,amount:asc:num,name:asc:natural
+                           See 'qsv sort --help' for the format details.
+                           Takes precedence over --select and cannot be combined
+                           with --numeric, --collate or --accent-insensitive.
+    -N, --numeric          Compare the selected columns according to their numerical
+                           value (CSV MODE).
     -R, --reverse          Reverse order
+    -u, --unique           Only keep the first record (CSV MODE) or line (LINE MODE) of
+                           every group of records with the same sort key.
+                           In CSV MODE, the first record is the earliest in the input.
+    --collate <locale>     Compare strings with the Unicode Collation Algorithm,
+                           using the collation rules of <locale> (e.g. en, de, sv)
+                           instead of byte order. See 'qsv sort --help' for details.
+    --accent-insensitive   Compare strings disregarding accents (e.g. "é" = "e").
+                           Uses the root collation if --collate is not set.
     --memory-limit <arg>   The maximum amount of memory to buffer the external merge sort.
                            If less than 50, this is a percentage of total memory.
                            If more than 50, this is the memory in MB to allocate, capped
@@ -45,36 +62,53 @@ Common options:
 "#;
 
 use std::{
-    fs,
+    cmp, fs,
     io::{self, stdin, stdout, BufRead, Write},
     path,
 };
 
 use ext_sort::{buffer::mem::MemoryLimitedBufferBuilder, ExternalSorter, ExternalSorterBuilder};
+use icu_collator::Collator;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use serde::Deserialize;
 
 use crate::{
-    cmd::extdedup::calculate_memory_limit,
+    cmd::{
+        extdedup::calculate_memory_limit,
+        sort::{encode_sort_keys, iter_cmp_collate, new_collator, parse_sort_keys, SortKey},
+    },
     config,
     config::{Config, Delimiter},
-    select::SelectColumns,
+    select::{SelectColumns, Selection},
     util, CliResult,
 };
 
 #[derive(Deserialize)]
 struct Args {
-    arg_input:         Option<String>,
-    arg_output:        Option<String>,
-    flag_select:       Option<SelectColumns>,
-    flag_reverse:      bool,
-    flag_delimiter:    Option<Delimiter>,
-    flag_jobs:         Option<usize>,
-    flag_memory_limit: Option<u64>,
-    flag_tmp_dir:      Option<String>,
-    flag_no_headers:   bool,
+    arg_input:               Option<String>,
+    arg_output:              Option<String>,
+    flag_select:             Option<SelectColumns>,
+    flag_keys:               Option<String>,
+    flag_numeric:            bool,
+    flag_reverse:            bool,
+    flag_unique:             bool,
+    flag_collate:            Option<String>,
+    flag_accent_insensitive: bool,
+    flag_delimiter:          Option<Delimiter>,
+    flag_jobs:               Option<usize>,
+    flag_memory_limit:       Option<u64>,
+    flag_tmp_dir:            Option<String>,
+    flag_no_headers:         bool,
 }
 
 const RW_BUFFER_CAPACITY: usize = 1_000_000; // 1 MB
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+// separates the selected fields of a sort key when collating, so they're compared one by one
+const FIELD_SEPARATOR: char = '\u{1f}';
+// the number of sort keys sampled per partition to pick the partition boundaries
+const SAMPLES_PER_PARTITION: u64 = 1_000;
+
+type Sorter = ExternalSorter<String, io::Error, MemoryLimitedBufferBuilder>;
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
@@ -95,36 +129,115 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let mem_limited_buffer_bytes = calculate_memory_limit(args.flag_memory_limit);
     log::info!("{mem_limited_buffer_bytes} bytes used for in memory mergesort buffer...");
 
-    let sorter: ExternalSorter<String, io::Error, MemoryLimitedBufferBuilder> =
-        match ExternalSorterBuilder::new()
-            .with_tmp_dir(path::Path::new(&tmp_dir))
-            .with_buffer(MemoryLimitedBufferBuilder::new(mem_limited_buffer_bytes))
-            .with_rw_buf_size(RW_BUFFER_CAPACITY)
-            .with_threads_number(util::njobs(args.flag_jobs))
-            .build()
-        {
-            Ok(sorter) => sorter,
-            Err(e) => {
-                return fail_clierror!("cannot create external sorter: {e}");
-            },
-        };
+    let collator = new_collator(
+        args.flag_collate.as_deref(),
+        false,
+        args.flag_accent_insensitive,
+    )?;
+    if args.flag_keys.is_some() && (args.flag_numeric || collator.is_some()) {
+        return fail_incorrectusage_clierror!(
+            "--keys cannot be combined with --numeric, --collate or --accent-insensitive."
+        );
+    }
+    if args.flag_numeric && collator.is_some() {
+        return fail_incorrectusage_clierror!(
+            "--numeric cannot be combined with --collate or --accent-insensitive."
+        );
+    }
 
-    if args.flag_select.is_some() {
-        sort_csv(&args, &tmp_dir, &sorter)
+    let jobs = util::njobs(args.flag_jobs);
+    if args.flag_select.is_some() || args.flag_keys.is_some() {
+        sort_csv(
+            &args,
+            &tmp_dir,
+            mem_limited_buffer_bytes,
+            jobs,
+            collator.as_ref(),
+        )
     } else {
-        sort_lines(&args, &sorter)
+        if args.flag_numeric {
+            return fail_incorrectusage_clierror!("--numeric requires --select.");
+        }
+        let sorter = new_sorter(&tmp_dir, mem_limited_buffer_bytes, jobs)?;
+        sort_lines(&args, &sorter, collator.as_ref())
+    }
+}
+
+fn new_sorter(tmp_dir: &str, buffer_bytes: u64, threads: usize) -> CliResult<Sorter> {
+    match ExternalSorterBuilder::new()
+        .with_tmp_dir(path::Path::new(tmp_dir))
+        .with_buffer(MemoryLimitedBufferBuilder::new(buffer_bytes))
+        .with_rw_buf_size(RW_BUFFER_CAPACITY)
+        .with_threads_number(threads)
+        .build()
+    {
+        Ok(sorter) => Ok(sorter),
+        Err(e) => fail_clierror!("cannot create external sorter: {e}"),
+    }
+}
+
+/// Builds the lines that are externally sorted in CSV MODE - the sort key of a record
+/// followed by "|" and its position, padded with leading zeroes to always be the same width.
+struct SortLineBuilder {
+    keys:    Option<Vec<SortKey>>,
+    sel:     Option<Selection>,
+    collate: bool,
+    width:   usize,
+    key_buf: Vec<u8>,
+}
+
+impl SortLineBuilder {
+    fn build(&mut self, row: &csv::ByteRecord, position: u64, line: &mut String) {
+        line.clear();
+        if let Some(ref keys) = self.keys {
+            // the encoded keys are binary, hex-encode them so they still sort as text lines
+            encode_sort_keys(keys, row, &mut self.key_buf);
+            for b in &self.key_buf {
+                line.push(HEX_DIGITS[(b >> 4) as usize] as char);
+                line.push(HEX_DIGITS[(b & 0x0f) as usize] as char);
+            }
+        } else if let Some(ref sel) = self.sel {
+            for (i, field) in sel.select(row).enumerate() {
+                if self.collate && i > 0 {
+                    line.push(FIELD_SEPARATOR);
+                }
+                if let Ok(s_utf8) = simdutf8::basic::from_utf8(field) {
+                    line.push_str(s_utf8);
+                } else {
+                    line.push_str(&String::from_utf8_lossy(field));
+                }
+            }
+        }
+        line.push_str(&format!("|{position:0width$}", width = self.width));
+    }
+}
+
+/// Compare the sort keys of two sort lines, without their position
+#[inline]
+fn compare_keys(a: &str, b: &str, collator: Option<&Collator>) -> cmp::Ordering {
+    match collator {
+        Some(collator) => iter_cmp_collate(
+            collator,
+            a.split(FIELD_SEPARATOR).map(str::as_bytes),
+            b.split(FIELD_SEPARATOR).map(str::as_bytes),
+        ),
+        None => a.cmp(b),
     }
 }
 
 fn sort_csv(
     args: &Args,
     tmp_dir: &str,
-    sorter: &ExternalSorter<String, io::Error, MemoryLimitedBufferBuilder>,
-) -> Result<(), crate::clitypes::CliError> {
-    let rconfig = Config::new(args.arg_input.as_ref())
+    buffer_bytes: u64,
+    jobs: usize,
+    collator: Option<&Collator>,
+) -> CliResult<()> {
+    let mut rconfig = Config::new(args.arg_input.as_ref())
         .delimiter(args.flag_delimiter)
-        .no_headers(args.flag_no_headers)
-        .select(args.flag_select.clone().unwrap());
+        .no_headers(args.flag_no_headers);
+    if let Some(ref select) = args.flag_select {
+        rconfig = rconfig.select(select.clone());
+    }
 
     let mut idxfile = if let Ok(idx) = rconfig.indexed() {
         if idx.is_none() {
@@ -137,118 +250,184 @@ fn sort_csv(
 
     let mut input_rdr = rconfig.reader()?;
 
-    let linewtr_tfile = tempfile::NamedTempFile::new_in(tmp_dir)?;
-    let mut line_wtr = io::BufWriter::with_capacity(RW_BUFFER_CAPACITY, linewtr_tfile.as_file());
-
     let headers = input_rdr.byte_headers()?.clone();
-    let sel = rconfig.selection(&headers)?;
-
-    let mut sort_key = String::with_capacity(20);
-    let mut utf8_string = String::with_capacity(20);
-    let mut curr_row = csv::ByteRecord::new();
+    let keys = if let Some(ref spec) = args.flag_keys {
+        Some(parse_sort_keys(
+            spec,
+            &headers,
+            !args.flag_no_headers,
+            rconfig.get_dmy_preference(),
+        )?)
+    } else if args.flag_numeric {
+        Some(vec![SortKey::numeric(rconfig.selection(&headers)?)])
+    } else {
+        None
+    };
+    let sel = if keys.is_none() {
+        Some(rconfig.selection(&headers)?)
+    } else {
+        None
+    };
 
     let rowcount = idxfile.count();
-    let width = rowcount.to_string().len();
-
-    // first pass. get the selected columns, and the record position
-    // then write them to a temp text file with the selected columns and the position
-    // separated by "|". Pad the position with leading zeroes, so it will always be the same width
-    for row in input_rdr.byte_records() {
-        curr_row.clone_from(&row?);
-        sort_key.clear();
-        for field in sel.select(&curr_row) {
-            if let Ok(s_utf8) = simdutf8::basic::from_utf8(field) {
-                sort_key.push_str(s_utf8);
-            } else {
-                utf8_string.clear();
-                utf8_string.push_str(&String::from_utf8_lossy(field));
-                sort_key.push_str(&utf8_string);
-            }
-        }
-        let idx_position = curr_row.position().unwrap();
-
-        sort_key.push_str(&format!("|{:01$}", idx_position.line(), width));
-
-        writeln!(line_wtr, "{sort_key}")?;
-    }
-    line_wtr.flush()?;
-
-    let line_rdr = io::BufReader::with_capacity(
-        RW_BUFFER_CAPACITY,
-        std::fs::File::open(linewtr_tfile.path())?,
-    );
+    let position_delta: u64 = if args.flag_no_headers { 1 } else { 2 };
+    // the width of the largest position (i.e. the line number of the last record)
+    let width = (rowcount + position_delta - 1).to_string().len();
+    let mut line_builder = SortLineBuilder {
+        keys,
+        sel,
+        collate: collator.is_some(),
+        width,
+        key_buf: Vec::with_capacity(20),
+    };
 
     let reverse_flag = args.flag_reverse;
     let compare = |a: &String, b: &String| {
-        if reverse_flag {
-            a.cmp(b).reverse()
+        let (a_key, a_pos) = a.split_at(a.len() - width - 1);
+        let (b_key, b_pos) = b.split_at(b.len() - width - 1);
+        let ordering = compare_keys(a_key, b_key, collator);
+        let ordering = if reverse_flag {
+            ordering.reverse()
         } else {
-            a.cmp(b)
-        }
-    };
-
-    // Now sort the temp text file
-    let sorted = match sorter.sort_by(line_rdr.lines(), compare) {
-        Ok(sorted) => sorted,
-        Err(e) => {
-            return fail!(format!("cannot do external sort: {e:?}"));
-        },
+            ordering
+        };
+        // ties are broken by the position, so the sort is stable
+        ordering.then_with(|| a_pos.cmp(b_pos))
     };
 
-    let sorted_tfile = tempfile::NamedTempFile::new_in(tmp_dir)?;
-    let mut sorted_line_wtr =
-        io::BufWriter::with_capacity(RW_BUFFER_CAPACITY, sorted_tfile.as_file());
-
-    for item in sorted.map(Result::unwrap) {
-        sorted_line_wtr.write_all(format!("{item}\n").as_bytes())?;
+    // Rather than k-way merging all the sorted runs on a single thread, split the input
+    // into key ranges that are externally sorted in parallel and then simply concatenated.
+    // The range boundaries are picked from a sample of the sort keys, read with the index.
+    let num_partitions = (jobs as u64).min(rowcount / SAMPLES_PER_PARTITION).max(1) as usize;
+    let mut boundaries: Vec<String> = Vec::with_capacity(num_partitions - 1);
+    if num_partitions > 1 {
+        let sample_size = SAMPLES_PER_PARTITION * num_partitions as u64;
+        let stride = rowcount / sample_size;
+        let mut samples = Vec::with_capacity(sample_size as usize);
+        let mut sample_row = csv::ByteRecord::new();
+        for i in 0..sample_size {
+            idxfile.seek(i * stride)?;
+            idxfile.read_byte_record(&mut sample_row)?;
+            let mut sample = String::new();
+            line_builder.build(&sample_row, i * stride + position_delta, &mut sample);
+            samples.push(sample);
+        }
+        samples.sort_unstable_by(compare);
+        for p in 1..num_partitions {
+            boundaries.push(samples[p * samples.len() / num_partitions].clone());
+        }
     }
-    sorted_line_wtr.flush()?;
-    // Delete the temporary file containing unsorted lines
-    drop(line_wtr);
-    linewtr_tfile.close()?;
-
-    // now write the sorted CSV file by reading the sorted_line temp file
-    // and extracting the position from each line
-    // and then using that to seek the input file to retrieve the record
-    // and then write the record to the final sorted CSV
-    let sorted_lines = std::fs::File::open(sorted_tfile.path())?;
-    let sorted_line_rdr = io::BufReader::with_capacity(RW_BUFFER_CAPACITY, sorted_lines);
-
+    log::info!("sorting {rowcount} records in {num_partitions} partition/s...");
+
+    // first pass. build the sort line of every record
+    // and write it to the temp file of its partition
+    let partition_tfiles = (0..num_partitions)
+        .map(|_| tempfile::NamedTempFile::new_in(tmp_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut partition_wtrs: Vec<_> = partition_tfiles
+        .iter()
+        .map(|tfile| io::BufWriter::with_capacity(RW_BUFFER_CAPACITY, tfile.as_file()))
+        .collect();
+
+    let mut sort_line = String::with_capacity(20);
+    let mut curr_row = csv::ByteRecord::new();
+    while input_rdr.read_byte_record(&mut curr_row)? {
+        let position = curr_row.position().unwrap().line();
+        line_builder.build(&curr_row, position, &mut sort_line);
+        let partition =
+            boundaries.partition_point(|boundary| compare(boundary, &sort_line).is_lt());
+        writeln!(partition_wtrs[partition], "{sort_line}")?;
+    }
+    for wtr in &mut partition_wtrs {
+        wtr.flush()?;
+    }
+    drop(partition_wtrs);
+
+    // now sort the partitions in parallel, splitting the threads & memory between them
+    let partition_buffer_bytes = buffer_bytes / num_partitions as u64;
+    let sorter_threads = (jobs / num_partitions).max(1);
+    let sorted_tfiles = partition_tfiles
+        .into_par_iter()
+        .map(|partition_tfile| -> CliResult<tempfile::NamedTempFile> {
+            let sorter = new_sorter(tmp_dir, partition_buffer_bytes, sorter_threads)?;
+            let line_rdr = io::BufReader::with_capacity(
+                RW_BUFFER_CAPACITY,
+                fs::File::open(partition_tfile.path())?,
+            );
+            let sorted = match sorter.sort_by(line_rdr.lines(), compare) {
+                Ok(sorted) => sorted,
+                Err(e) => {
+                    return fail!(format!("cannot do external sort: {e:?}"));
+                },
+            };
+
+            let sorted_tfile = tempfile::NamedTempFile::new_in(tmp_dir)?;
+            let mut sorted_line_wtr =
+                io::BufWriter::with_capacity(RW_BUFFER_CAPACITY, sorted_tfile.as_file());
+            for item in sorted {
+                match item {
+                    Ok(item) => writeln!(sorted_line_wtr, "{item}")?,
+                    Err(e) => {
+                        return fail!(format!("cannot do external sort: {e:?}"));
+                    },
+                }
+            }
+            sorted_line_wtr.flush()?;
+            drop(sorted_line_wtr);
+            // Delete the temporary file containing the unsorted lines
+            partition_tfile.close()?;
+            Ok(sorted_tfile)
+        })
+        .collect::<CliResult<Vec<_>>>()?;
+
+    // now write the sorted CSV file by reading the sorted partitions in order,
+    // extracting the position from each line and using that to seek the input file
+    // to retrieve the record and then write the record to the final sorted CSV
     let mut sorted_csv_wtr = Config::new(args.arg_output.as_ref()).writer()?;
-
-    let position_delta: u64 = if args.flag_no_headers {
-        1
-    } else {
+    if !args.flag_no_headers {
         // Write the header row if --no-headers is false
         sorted_csv_wtr.write_byte_record(&headers)?;
-        2
-    };
+    }
 
     // amortize allocations
     let mut record_wrk = csv::ByteRecord::new();
     let mut line = String::new();
+    let mut prev_key = String::new();
+    let mut is_first = true;
+
+    for sorted_tfile in sorted_tfiles {
+        let sorted_line_rdr =
+            io::BufReader::with_capacity(RW_BUFFER_CAPACITY, fs::File::open(sorted_tfile.path())?);
+        for l in sorted_line_rdr.lines() {
+            line.clone_from(&l?);
+            let key = &line[..line.len() - width - 1];
+            if args.flag_unique {
+                // the sort is stable, so the first record of a group is the earliest one
+                if !is_first && compare_keys(&prev_key, key, collator).is_eq() {
+                    continue;
+                }
+                prev_key.clear();
+                prev_key.push_str(key);
+                is_first = false;
+            }
 
-    for l in sorted_line_rdr.lines() {
-        line.clone_from(&l?);
-        let Ok(position) = atoi_simd::parse::<u64>(line[line.len() - width..].as_bytes()) else {
-            return fail!("Failed to retrieve position: invalid integer");
-        };
+            let Ok(position) = atoi_simd::parse::<u64>(line[line.len() - width..].as_bytes())
+            else {
+                return fail!("Failed to retrieve position: invalid integer");
+            };
 
-        idxfile.seek(position - position_delta)?;
-        idxfile.read_byte_record(&mut record_wrk)?;
-        sorted_csv_wtr.write_byte_record(&record_wrk)?;
+            idxfile.seek(position - position_delta)?;
+            idxfile.read_byte_record(&mut record_wrk)?;
+            sorted_csv_wtr.write_byte_record(&record_wrk)?;
+        }
+        sorted_tfile.close()?;
     }
     sorted_csv_wtr.flush()?;
-    drop(sorted_line_wtr);
-    sorted_tfile.close()?;
 
     Ok(())
 }
 
-fn sort_lines(
-    args: &Args,
-    sorter: &ExternalSorter<String, io::Error, MemoryLimitedBufferBuilder>,
-) -> Result<(), crate::clitypes::CliError> {
+fn sort_lines(args: &Args, sorter: &Sorter, collator: Option<&Collator>) -> CliResult<()> {
     let mut input_rdr: Box<dyn BufRead> = match &args.arg_input {
         Some(input_path) => {
             if input_path.to_lowercase().ends_with(".sz") {
@@ -291,10 +470,14 @@ fn sort_lines(
 
     let reverse_flag = args.flag_reverse;
     let compare = |a: &String, b: &String| {
+        let ordering = match collator {
+            Some(collator) => collator.compare(a, b),
+            None => a.cmp(b),
+        };
         if reverse_flag {
-            a.cmp(b).reverse()
+            ordering.reverse()
         } else {
-            a.cmp(b)
+            ordering
         }
     };
 
@@ -309,8 +492,20 @@ fn sort_lines(
         output_wtr.write_all(format!("{}\n", header.trim_end()).as_bytes())?;
     }
 
+    let mut prev_line: Option<String> = None;
     for item in sorted.map(Result::unwrap) {
-        output_wtr.write_all(format!("{item}\n").as_bytes())?;
+        if args.flag_unique {
+            if prev_line
+                .as_ref()
+                .is_some_and(|prev| compare(prev, &item).is_eq())
+            {
+                continue;
+            }
+            output_wtr.write_all(format!("{item}\n").as_bytes())?;
+            prev_line = Some(item);
+        } else {
+            output_wtr.write_all(format!("{item}\n").as_bytes())?;
+        }
     }
     output_wtr.flush()?;
     Ok(())
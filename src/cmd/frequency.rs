@@ -37,6 +37,22 @@ STATS_MODE "none" NOTES:
     This is useful when you want to apply limits only to columns with a large number
     of unique items and not to columns with a small number of unique items.
 
+APPROXIMATE MODE:
+
+    When only the top N values of very large columns are needed, exact counting is
+    overkill. With --approx, frequencies are estimated using the SpaceSaving algorithm,
+    which monitors at most --approx-size distinct values per column, regardless of
+    cardinality. Memory usage is therefore bounded, and with an index, per-chunk summaries
+    are computed in parallel and merged.
+
+    In approximate mode, an additional "error" column is returned. The reported count is
+    an upper bound of the true count, and the true count is guaranteed to be in the
+    range [count - error, count]. Percentages are computed using the reported count.
+    The "Other" category is not reported in approximate mode.
+
+    Any value whose true count is more than N/approx-size (where N is the number of rows)
+    is guaranteed to be monitored and reported (subject to --limit).
+
 For examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_frequency.rs.
 
 Usage:
@@ -99,6 +115,15 @@ frequency options:
                             [default: auto]
    --all-unique-text <arg>  The text to use for the "<ALL_UNIQUE>" category.
                             [default: <ALL_UNIQUE>]
+    --approx                Compute approximate frequencies for the most frequent values
+                            using the SpaceSaving algorithm with bounded memory.
+                            Adds an "error" column with the maximum overcount per value.
+                            See APPROXIMATE MODE above.
+    --approx-size <arg>     The number of counters to maintain per column in approximate
+                            mode. Larger values are more accurate but use more memory.
+                            When set to 0, uses 100 times the absolute value of --limit,
+                            with a minimum of 10,000 counters.
+                            [default: 0]
     -j, --jobs <arg>        The number of jobs to run in parallel.
                             This works much faster when the given CSV data has
                             an index already created. Note that a file handle
@@ -119,8 +144,9 @@ Common options:
                            CSV into memory using CONSERVATIVE heuristics.
 "#;
 
-use std::{fs, io, sync::OnceLock};
+use std::{cmp::Reverse, collections::BinaryHeap, fs, io, sync::OnceLock};
 
+use ahash::AHashMap;
 use crossbeam_channel;
 use indicatif::HumanCount;
 use rust_decimal::prelude::*;
@@ -154,6 +180,8 @@ pub struct Args {
     pub flag_ignore_case:     bool,
     pub flag_stats_mode:      String,
     pub flag_all_unique_text: String,
+    pub flag_approx:          bool,
+    pub flag_approx_size:     usize,
     pub flag_jobs:            Option<usize>,
     pub flag_output:          Option<String>,
     pub flag_no_headers:      bool,
@@ -171,6 +199,11 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
     let rconfig = args.rconfig();
 
+    // approximate mode uses bounded memory, so there's no need to check avail mem
+    if args.flag_approx {
+        return args.run_approx(&rconfig);
+    }
+
     // we're loading the entire file into memory, we need to check avail mem
     if let Some(path) = rconfig.path.clone() {
         util::mem_file_check(&path, false, args.flag_memcheck)?;
@@ -185,12 +218,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     #[allow(unused_assignments)]
     let mut header_vec: Vec<u8> = Vec::with_capacity(tables.len());
     let mut itoa_buffer = itoa::Buffer::new();
-    let mut pct_decimal: Decimal;
-    let mut final_pct_decimal: Decimal;
     let mut pct_string: String;
-    let mut pct_scale: u32;
-    let mut current_scale: u32;
-    let abs_dec_places = args.flag_pct_dec_places.unsigned_abs() as u32;
     let mut row: Vec<&[u8]>;
     let mut all_unique_header: bool;
 
@@ -231,31 +259,7 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         };
 
         for (value, count, percentage) in sorted_counts {
-            pct_decimal = Decimal::from_f64(percentage).unwrap_or_default();
-            pct_scale = if args.flag_pct_dec_places < 0 {
-                current_scale = pct_decimal.scale();
-                if current_scale > abs_dec_places {
-                    current_scale
-                } else {
-                    abs_dec_places
-                }
-            } else {
-                abs_dec_places
-            };
-            final_pct_decimal = pct_decimal
-                .round_dp_with_strategy(
-                    pct_scale,
-                    rust_decimal::RoundingStrategy::MidpointAwayFromZero,
-                )
-                .normalize();
-            pct_string = if final_pct_decimal.fract().to_string().len() > abs_dec_places as usize {
-                final_pct_decimal
-                    .round_dp_with_strategy(abs_dec_places, RoundingStrategy::MidpointAwayFromZero)
-                    .normalize()
-                    .to_string()
-            } else {
-                final_pct_decimal.to_string()
-            };
+            pct_string = args.format_pct(percentage);
             row = vec![
                 &*header_vec,
                 &*value,
@@ -280,6 +284,225 @@ impl Args {
             .select(self.flag_select.clone())
     }
 
+    /// compute approximate frequencies with bounded memory using SpaceSaving summaries
+    fn run_approx(&self, rconfig: &Config) -> CliResult<()> {
+        let mut wtr = Config::new(self.flag_output.as_ref()).writer()?;
+        let (headers, summaries) = match rconfig.indexed()? {
+            Some(ref mut idx) if util::njobs(self.flag_jobs) > 1 => self.parallel_summaries(idx),
+            _ => self.sequential_summaries(),
+        }?;
+
+        // safety: UNIQUE_COLUMNS has been previously set by sel_headers
+        let all_unique_headers = UNIQUE_COLUMNS.get().unwrap();
+        let row_count = *FREQ_ROW_COUNT.get().unwrap_or(&0);
+        let abs_limit = self.flag_limit.unsigned_abs();
+        let mut count_buffer = itoa::Buffer::new();
+        let mut error_buffer = itoa::Buffer::new();
+        let mut header_vec: Vec<u8>;
+        let mut pct_string: String;
+
+        wtr.write_record(vec!["field", "value", "count", "percentage", "error"])?;
+        for (i, (header, summary)) in headers.iter().zip(summaries).enumerate() {
+            header_vec = if rconfig.no_headers {
+                (i + 1).to_string().into_bytes()
+            } else {
+                header.to_vec()
+            };
+
+            if all_unique_headers.contains(&i) {
+                pct_string = self.format_pct(100.0_f64);
+                wtr.write_record(vec![
+                    &*header_vec,
+                    self.flag_all_unique_text.as_bytes(),
+                    count_buffer.format(row_count).as_bytes(),
+                    pct_string.as_bytes(),
+                    &b"0"[..],
+                ])?;
+                continue;
+            }
+
+            let total_count = summary.total;
+            #[allow(clippy::cast_precision_loss)]
+            let pct_factor = if total_count > 0 {
+                100.0_f64 / total_count as f64
+            } else {
+                0.0_f64
+            };
+
+            let mut counters = summary.into_sorted_vec(self.flag_asc);
+            if self.flag_limit > 0 {
+                counters.truncate(abs_limit);
+            } else if self.flag_limit < 0 {
+                let count_limit = abs_limit as u64;
+                counters.retain(|(_, count, _)| *count >= count_limit);
+            }
+
+            for (value, count, error) in counters {
+                #[allow(clippy::cast_precision_loss)]
+                let percentage = count as f64 * pct_factor;
+                pct_string = self.format_pct(percentage);
+                wtr.write_record(vec![
+                    &*header_vec,
+                    if value.is_empty() { NULL_VAL } else { &*value },
+                    count_buffer.format(count).as_bytes(),
+                    pct_string.as_bytes(),
+                    error_buffer.format(error).as_bytes(),
+                ])?;
+            }
+        }
+        Ok(wtr.flush()?)
+    }
+
+    /// the number of SpaceSaving counters to maintain per column
+    fn approx_capacity(&self) -> usize {
+        if self.flag_approx_size > 0 {
+            self.flag_approx_size
+        } else {
+            (self.flag_limit.unsigned_abs() * 100).max(10_000)
+        }
+    }
+
+    fn sequential_summaries(&self) -> CliResult<(Headers, Vec<SpaceSaving>)> {
+        let mut rdr = self.rconfig().reader()?;
+        let (headers, sel) = self.sel_headers(&mut rdr)?;
+        Ok((headers, self.summaries(&sel, rdr.byte_records())))
+    }
+
+    fn parallel_summaries(
+        &self,
+        idx: &Indexed<fs::File, fs::File>,
+    ) -> CliResult<(Headers, Vec<SpaceSaving>)> {
+        let mut rdr = self.rconfig().reader()?;
+        let (headers, sel) = self.sel_headers(&mut rdr)?;
+
+        let idx_count = idx.count() as usize;
+        if idx_count == 0 {
+            return Ok((headers, vec![]));
+        }
+
+        let njobs = util::njobs(self.flag_jobs);
+        let chunk_size = util::chunk_size(idx_count, njobs);
+        let nchunks = util::num_of_chunks(idx_count, chunk_size);
+
+        let pool = ThreadPool::new(njobs);
+        let (send, recv) = crossbeam_channel::bounded(0);
+        for i in 0..nchunks {
+            let (send, args, sel) = (send.clone(), self.clone(), sel.clone());
+            pool.execute(move || {
+                // safety: we know the file is indexed and seekable
+                let mut idx = args.rconfig().indexed().unwrap().unwrap();
+                idx.seek((i * chunk_size) as u64).unwrap();
+                let it = idx.byte_records().take(chunk_size);
+                send.send((i, args.summaries(&sel, it))).unwrap();
+            });
+        }
+        drop(send);
+
+        // merge the per-chunk summaries column by column, in chunk order, as merging
+        // truncates the summaries to their capacity, which depends on the merge order
+        let mut chunks: Vec<(usize, Vec<SpaceSaving>)> = recv.iter().collect();
+        chunks.sort_unstable_by_key(|(i, _)| *i);
+        let merged = chunks
+            .into_iter()
+            .map(|(_, chunk)| chunk)
+            .reduce(|acc: Vec<SpaceSaving>, chunk| {
+                acc.into_iter()
+                    .zip(chunk)
+                    .map(|(a, b)| a.merge(b))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok((headers, merged))
+    }
+
+    fn summaries<I>(&self, sel: &Selection, it: I) -> Vec<SpaceSaving>
+    where
+        I: Iterator<Item = csv::Result<csv::ByteRecord>>,
+    {
+        let nsel = sel.normal();
+        let nsel_len = nsel.len();
+        let capacity = self.approx_capacity();
+        let mut summaries: Vec<_> = (0..nsel_len).map(|_| SpaceSaving::new(capacity)).collect();
+
+        let all_unique_headers = UNIQUE_COLUMNS.get().unwrap();
+        let all_unique_flag_vec: Vec<bool> = (0..nsel_len)
+            .map(|i| all_unique_headers.contains(&i))
+            .collect();
+
+        // amortize allocations
+        let mut row_buffer: csv::ByteRecord = csv::ByteRecord::with_capacity(200, nsel_len);
+        let mut field_buffer: Vec<u8> = Vec::with_capacity(100);
+        let mut lower_buffer = String::new();
+
+        for row in it {
+            // safety: we know the row is not empty
+            row_buffer.clone_from(&row.unwrap());
+            for (i, field) in nsel.select(row_buffer.into_iter()).enumerate() {
+                if all_unique_flag_vec[i] {
+                    continue;
+                }
+                if field.is_empty() {
+                    if !self.flag_no_nulls {
+                        summaries[i].add(b"");
+                    }
+                    continue;
+                }
+                self.normalize_into(field, &mut field_buffer, &mut lower_buffer);
+                summaries[i].add(&field_buffer);
+            }
+        }
+        summaries
+    }
+
+    /// apply the --ignore-case and --no-trim options to a field, writing it into buf
+    #[inline]
+    fn normalize_into(&self, field: &[u8], buf: &mut Vec<u8>, lower_buf: &mut String) {
+        buf.clear();
+        match simdutf8::basic::from_utf8(field) {
+            Ok(s) => {
+                let s = if self.flag_no_trim { s } else { s.trim() };
+                if self.flag_ignore_case {
+                    util::to_lowercase_into(s, lower_buf);
+                    buf.extend_from_slice(lower_buf.as_bytes());
+                } else {
+                    buf.extend_from_slice(s.as_bytes());
+                }
+            },
+            Err(_) if self.flag_no_trim => buf.extend_from_slice(field),
+            Err(_) => buf.extend_from_slice(util::trim_bs_whitespace(field)),
+        }
+    }
+
+    /// round the percentage to the number of decimal places specified by --pct-dec-places
+    fn format_pct(&self, percentage: f64) -> String {
+        let abs_dec_places = self.flag_pct_dec_places.unsigned_abs() as u32;
+        let pct_decimal = Decimal::from_f64(percentage).unwrap_or_default();
+        let pct_scale = if self.flag_pct_dec_places < 0 {
+            let current_scale = pct_decimal.scale();
+            if current_scale > abs_dec_places {
+                current_scale
+            } else {
+                abs_dec_places
+            }
+        } else {
+            abs_dec_places
+        };
+        let final_pct_decimal = pct_decimal
+            .round_dp_with_strategy(
+                pct_scale,
+                rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+            )
+            .normalize();
+        if final_pct_decimal.fract().to_string().len() > abs_dec_places as usize {
+            final_pct_decimal
+                .round_dp_with_strategy(abs_dec_places, RoundingStrategy::MidpointAwayFromZero)
+                .normalize()
+                .to_string()
+        } else {
+            final_pct_decimal.to_string()
+        }
+    }
+
     #[inline]
     fn counts(&self, ftab: &FTable) -> Vec<(ByteString, u64, f64)> {
         let (mut counts, total_count) = if self.flag_asc {
@@ -655,3 +878,151 @@ impl Args {
         Ok((sel.select(headers).map(<[u8]>::to_vec).collect(), sel))
     }
 }
+
+/// A SpaceSaving summary (Metwally, Agrawal & El Abbadi, 2005) of the most frequent
+/// values of a column, using at most `capacity` counters.
+///
+/// Each counter holds an overestimated count and its maximum overestimation (error),
+/// so the true count of a monitored value is always within [count - error, count].
+/// Summaries are mergeable (Agarwal et al., 2012) so per-chunk summaries can be
+/// computed in parallel and combined while preserving the error guarantees.
+#[derive(Clone, Default)]
+struct SpaceSaving {
+    capacity:   usize,
+    /// the monitored values, indexed by slot
+    values:     Vec<Vec<u8>>,
+    counts:     Vec<u64>,
+    errors:     Vec<u64>,
+    slots:      AHashMap<Vec<u8>, usize>,
+    /// min-heap of (count, slot) with exactly one entry per slot.
+    /// Entries are lazily refreshed as counts only ever increase.
+    heap:       BinaryHeap<Reverse<(u64, usize)>>,
+    /// upper bound on the count of unmonitored values while no counter has been evicted
+    base_floor: u64,
+    evicted:    bool,
+    /// the number of values added to the summary
+    total:      u64,
+}
+
+impl SpaceSaving {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    fn add(&mut self, value: &[u8]) {
+        self.total += 1;
+        if let Some(&slot) = self.slots.get(value) {
+            self.counts[slot] += 1;
+        } else if self.values.len() < self.capacity {
+            let slot = self.values.len();
+            self.values.push(value.to_vec());
+            self.counts.push(self.base_floor + 1);
+            self.errors.push(self.base_floor);
+            self.heap.push(Reverse((self.base_floor + 1, slot)));
+            self.slots.insert(value.to_vec(), slot);
+        } else if self.capacity > 0 {
+            // replace the value with the smallest count
+            let (min_count, slot) = self.pop_min();
+            self.slots.remove(&self.values[slot]);
+            self.values[slot].clear();
+            self.values[slot].extend_from_slice(value);
+            self.counts[slot] = min_count + 1;
+            self.errors[slot] = min_count;
+            self.heap.push(Reverse((min_count + 1, slot)));
+            self.slots.insert(value.to_vec(), slot);
+            self.evicted = true;
+        }
+    }
+
+    /// pop the heap entry of the counter with the smallest count
+    fn pop_min(&mut self) -> (u64, usize) {
+        loop {
+            // safety: the heap has an entry for every slot, and it is only called when full
+            let Reverse((count, slot)) = self.heap.pop().unwrap();
+            let current = self.counts[slot];
+            if current == count {
+                return (count, slot);
+            }
+            // stale entry, reinsert it with its current count
+            self.heap.push(Reverse((current, slot)));
+        }
+    }
+
+    /// the maximum possible count of any value that is not monitored
+    fn floor(&self) -> u64 {
+        if self.evicted {
+            self.counts.iter().min().copied().unwrap_or_default()
+        } else {
+            self.base_floor
+        }
+    }
+
+    /// merge two summaries. Values missing from one summary are assumed to have
+    /// the maximum count they could have had in that summary.
+    fn merge(self, other: Self) -> Self {
+        let capacity = self.capacity.max(other.capacity);
+        let total = self.total + other.total;
+        let (floor_a, floor_b) = (self.floor(), other.floor());
+
+        let mut merged: AHashMap<Vec<u8>, (u64, u64)> =
+            AHashMap::with_capacity(self.values.len() + other.values.len());
+        for ((value, count), error) in self.values.into_iter().zip(self.counts).zip(self.errors) {
+            merged.insert(value, (count + floor_b, error + floor_b));
+        }
+        for ((value, count), error) in other.values.into_iter().zip(other.counts).zip(other.errors)
+        {
+            merged
+                .entry(value)
+                .and_modify(|(c, e)| {
+                    *c = *c - floor_b + count;
+                    *e = *e - floor_b + error;
+                })
+                .or_insert((count + floor_a, error + floor_a));
+        }
+
+        let mut counters: Vec<(Vec<u8>, u64, u64)> = merged
+            .into_iter()
+            .map(|(value, (count, error))| (value, count, error))
+            .collect();
+        let truncated = counters.len() > capacity;
+        if truncated {
+            counters.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            counters.truncate(capacity);
+        }
+
+        let mut summary = Self::new(capacity);
+        summary.total = total;
+        summary.base_floor = floor_a + floor_b;
+        summary.evicted = truncated;
+        for (slot, (value, count, error)) in counters.into_iter().enumerate() {
+            summary.slots.insert(value.clone(), slot);
+            summary.values.push(value);
+            summary.counts.push(count);
+            summary.errors.push(error);
+            summary.heap.push(Reverse((count, slot)));
+        }
+        summary
+    }
+
+    /// return the (value, count, error) of the monitored values sorted by count.
+    /// Ties are broken by value so the output is deterministic.
+    fn into_sorted_vec(self, ascending: bool) -> Vec<(Vec<u8>, u64, u64)> {
+        let mut counters: Vec<(Vec<u8>, u64, u64)> = self
+            .values
+            .into_iter()
+            .zip(self.counts)
+            .zip(self.errors)
+            .map(|((value, count), error)| (value, count, error))
+            .collect();
+        if ascending {
+            counters.sort_unstable_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        } else {
+            counters.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        }
+        counters
+    }
+}
@@ -0,0 +1,856 @@
+static USAGE: &str = r#"
+Group CSV data by one or more key columns and compute aggregates for each group.
+
+Unlike `sqlp`, groupby does not infer column types. Values are streamed as raw bytes,
+so it works on "dirty" CSVs where type inference would trip. Numeric aggregates
+(sum, mean and numeric min/max) are computed using exact decimal arithmetic, so
+summing 0.1 and 0.2 results in exactly 0.3. Values that are not numbers are ignored
+by numeric aggregates. Empty values are ignored by all aggregates except `first`,
+`last` and `count` without a column.
+
+The following aggregate functions are supported:
+
+    count                 The number of rows in the group.
+    count:<column>        The number of non-empty values of <column> in the group.
+    count_distinct:<col>  The number of distinct non-empty values of <col> in the group.
+    sum:<column>          The sum of the numeric values of <column>.
+    mean:<column>         The mean of the numeric values of <column>.
+    min:<column>          The minimum value of <column>. If all values are numeric, the
+                          numeric minimum is returned. Otherwise, the lexicographic minimum.
+    max:<column>          The maximum value of <column>, with the same rules as min.
+    first:<column>        The value of <column> in the first row of the group.
+    last:<column>         The value of <column> in the last row of the group.
+
+The output has one row per group, sorted by the key columns, with the key columns
+followed by one column per aggregate, named <function>_<column> (or "count").
+
+If the number of groups held in memory exceeds --max-groups, partial aggregates are
+spilled to sorted temporary files in --tmp-dir, which are merged at the end. This
+allows groupby to aggregate datasets with more groups than can fit in memory.
+
+If the CSV is indexed, rows are aggregated in parallel chunks and the partial
+aggregates are merged.
+
+Examples:
+
+  # count the number of rows per borough
+  $ qsv groupby Borough nyc311.csv
+
+  # total and average fare per vendor and payment type
+  $ qsv groupby VendorID,payment_type --agg "count,sum:fare_amount,mean:fare_amount" taxi.csv
+
+  # distinct customers, first and last order date per region
+  $ qsv groupby region -a "count_distinct:customer_id,first:order_date,last:order_date" orders.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_groupby.rs.
+
+Usage:
+    qsv groupby [options] <key> [<input>]
+    qsv groupby --help
+
+groupby arguments:
+    <key>                  The column/s to group by.
+                           See 'qsv select --help' for the format details.
+    <input>                The CSV file to read from. If not specified, then
+                           the input will be read from stdin.
+
+groupby options:
+    -a, --agg <aggs>       A comma-separated list of aggregates to compute for each
+                           group, in the form <function>[:<column>].
+                           The column can be a column name or a 1-based index.
+                           [default: count]
+    --round <places>       Round the mean to <places> decimal places.
+                           [default: 4]
+    --max-groups <arg>     The maximum number of groups to hold in memory before
+                           spilling partial aggregates to disk.
+                           [default: 1000000]
+    --tmp-dir <arg>        The directory to use for spill files.
+                           [default: ./]
+    -j, --jobs <arg>       The number of jobs to run in parallel when the CSV is indexed.
+                           When not set, the number of jobs is set to the
+                           number of CPUs detected.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. Columns are then referred to by their
+                           1-based index and no header row is written.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+"#;
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use ahash::{AHashMap, AHashSet};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use rayon::slice::ParallelSliceMut;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tempfile::NamedTempFile;
+use threadpool::ThreadPool;
+
+use crate::{
+    config::{Config, Delimiter},
+    select::{SelectColumns, Selection},
+    util, CliError, CliResult,
+};
+
+#[derive(Clone, Deserialize)]
+struct Args {
+    arg_key:         SelectColumns,
+    arg_input:       Option<String>,
+    flag_agg:        String,
+    flag_round:      u32,
+    flag_max_groups: usize,
+    flag_tmp_dir:    String,
+    flag_jobs:       Option<usize>,
+    flag_output:     Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter:  Option<Delimiter>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AggFunc {
+    Count,
+    CountDistinct,
+    Sum,
+    Mean,
+    Min,
+    Max,
+    First,
+    Last,
+}
+
+#[derive(Clone)]
+pub(crate) struct AggSpec {
+    pub(crate) func: AggFunc,
+    pub(crate) col:  Option<usize>,
+    pub(crate) name: String,
+}
+
+/// the smallest or largest value seen so far. Numeric values are compared numerically,
+/// but once a non-numeric value is seen, the lexicographic extreme is used instead.
+#[derive(Clone, Default)]
+pub(crate) struct Extreme {
+    num:         Option<(Decimal, Vec<u8>)>,
+    text:        Option<Vec<u8>>,
+    non_numeric: bool,
+}
+
+/// the partial aggregate of one aggregate function for one group
+#[derive(Clone)]
+pub(crate) enum AggState {
+    Count(u64),
+    CountDistinct(AHashSet<Vec<u8>>),
+    Sum(Option<Decimal>),
+    Mean(Decimal, u64),
+    Min(Extreme),
+    Max(Extreme),
+    /// (row number, value)
+    First(Option<(u64, Vec<u8>)>),
+    Last(Option<(u64, Vec<u8>)>),
+}
+
+type GroupKey = Vec<Vec<u8>>;
+type Groups = AHashMap<GroupKey, Vec<AggState>>;
+
+const RW_BUFFER_CAPACITY: usize = 1_000_000; // 1 MB
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+
+    if !Path::new(&args.flag_tmp_dir).exists() {
+        return fail_clierror!("tmp-dir '{}' does not exist", args.flag_tmp_dir);
+    }
+    if args.flag_max_groups == 0 {
+        return fail_incorrectusage_clierror!("--max-groups must be greater than 0");
+    }
+
+    let rconfig = args.rconfig();
+    let mut rdr = rconfig.reader()?;
+    let headers = rdr.byte_headers()?.clone();
+    let key_sel = rconfig.selection(&headers)?;
+    let specs = parse_aggs(&args.flag_agg, &headers, !args.flag_no_headers)?;
+
+    let aggregator = match rconfig.indexed()? {
+        Some(idx) if util::njobs(args.flag_jobs) > 1 => {
+            args.parallel_aggregate(idx.count() as usize, &key_sel, &specs)?
+        },
+        _ => {
+            let mut aggregator = Aggregator::new(specs.clone(), args.flag_max_groups, &args);
+            let mut record = csv::ByteRecord::new();
+            let mut rowno = 0_u64;
+            while rdr.read_byte_record(&mut record)? {
+                aggregator.add(&key_sel, &record, rowno)?;
+                rowno += 1;
+            }
+            aggregator
+        },
+    };
+
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+    if !args.flag_no_headers {
+        let mut out_headers: csv::ByteRecord = key_sel.select(&headers).collect();
+        for spec in &specs {
+            out_headers.push_field(spec.name.as_bytes());
+        }
+        wtr.write_byte_record(&out_headers)?;
+    }
+
+    let round = args.flag_round;
+    let mut out_record = csv::ByteRecord::new();
+    aggregator.finish(|key, states| {
+        out_record.clear();
+        for field in &key {
+            out_record.push_field(field);
+        }
+        for state in &states {
+            out_record.push_field(&state.output(round));
+        }
+        wtr.write_byte_record(&out_record)?;
+        Ok(())
+    })?;
+
+    Ok(wtr.flush()?)
+}
+
+impl Args {
+    fn rconfig(&self) -> Config {
+        Config::new(self.arg_input.as_ref())
+            .delimiter(self.flag_delimiter)
+            .no_headers(self.flag_no_headers)
+            .select(self.arg_key.clone())
+    }
+
+    fn parallel_aggregate(
+        &self,
+        idx_count: usize,
+        key_sel: &Selection,
+        specs: &[AggSpec],
+    ) -> CliResult<Aggregator> {
+        let njobs = util::njobs(self.flag_jobs);
+        if idx_count == 0 {
+            return Ok(Aggregator::new(specs.to_vec(), self.flag_max_groups, self));
+        }
+        let chunk_size = util::chunk_size(idx_count, njobs);
+        let nchunks = util::num_of_chunks(idx_count, chunk_size);
+        // each job gets its share of the in-memory group budget
+        let chunk_max_groups = (self.flag_max_groups / njobs).max(1);
+
+        let pool = ThreadPool::new(njobs);
+        let (send, recv) = crossbeam_channel::bounded(0);
+        for i in 0..nchunks {
+            let (send, args, key_sel, specs) =
+                (send.clone(), self.clone(), key_sel.clone(), specs.to_vec());
+            pool.execute(move || {
+                let result = (|| -> CliResult<Aggregator> {
+                    let mut aggregator = Aggregator::new(specs, chunk_max_groups, &args);
+                    // safety: we know the file is indexed and seekable
+                    let mut idx = args.rconfig().indexed()?.unwrap();
+                    let start = (i * chunk_size) as u64;
+                    idx.seek(start)?;
+                    for (offset, record) in idx.byte_records().take(chunk_size).enumerate() {
+                        aggregator.add(&key_sel, &record?, start + offset as u64)?;
+                    }
+                    Ok(aggregator)
+                })();
+                send.send(result).unwrap();
+            });
+        }
+        drop(send);
+
+        let mut merged = Aggregator::new(specs.to_vec(), self.flag_max_groups, self);
+        for partial in recv {
+            merged.absorb(partial?)?;
+        }
+        Ok(merged)
+    }
+}
+
+/// parse the --agg option into aggregate specifications
+pub(crate) fn parse_aggs(
+    aggs: &str,
+    headers: &csv::ByteRecord,
+    use_names: bool,
+) -> CliResult<Vec<AggSpec>> {
+    let mut specs = Vec::new();
+    for agg in aggs.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (func_name, column) = match agg.split_once(':') {
+            Some((func_name, column)) => (func_name.trim(), Some(column.trim())),
+            None => (agg, None),
+        };
+        let func = match func_name.to_lowercase().as_str() {
+            "count" => AggFunc::Count,
+            "count_distinct" => AggFunc::CountDistinct,
+            "sum" => AggFunc::Sum,
+            "mean" | "avg" => AggFunc::Mean,
+            "min" => AggFunc::Min,
+            "max" => AggFunc::Max,
+            "first" => AggFunc::First,
+            "last" => AggFunc::Last,
+            _ => return fail_incorrectusage_clierror!("Unknown aggregate function: {func_name}"),
+        };
+        let Some(column) = column else {
+            if func == AggFunc::Count {
+                specs.push(AggSpec {
+                    func,
+                    col: None,
+                    name: "count".to_string(),
+                });
+                continue;
+            }
+            return fail_incorrectusage_clierror!(
+                "Aggregate function {func_name} requires a column"
+            );
+        };
+        let sel = SelectColumns::parse(column)?.selection(headers, use_names)?;
+        if sel.len() != 1 {
+            return fail_incorrectusage_clierror!(
+                "Aggregate {agg} must select exactly one column."
+            );
+        }
+        let col = sel[0];
+        let col_name = if use_names {
+            String::from_utf8_lossy(&headers[col]).to_string()
+        } else {
+            (col + 1).to_string()
+        };
+        specs.push(AggSpec {
+            func,
+            col: Some(col),
+            name: format!("{}_{col_name}", func_name.to_lowercase()),
+        });
+    }
+    if specs.is_empty() {
+        return fail_incorrectusage_clierror!("No aggregates specified.");
+    }
+    Ok(specs)
+}
+
+/// parse a field as an exact decimal, ignoring surrounding whitespace
+#[inline]
+pub(crate) fn parse_decimal(field: &[u8]) -> Option<Decimal> {
+    let s = simdutf8::basic::from_utf8(field).ok()?.trim();
+    if s.is_empty() {
+        return None;
+    }
+    Decimal::from_str(s)
+        .or_else(|_| Decimal::from_scientific(s))
+        .ok()
+}
+
+impl Extreme {
+    #[inline]
+    fn update(&mut self, value: &[u8], num: Option<Decimal>, is_min: bool) {
+        let better = |a: &[u8], b: &[u8]| if is_min { a < b } else { a > b };
+        if self
+            .text
+            .as_ref()
+            .map_or(true, |t| better(value, t.as_slice()))
+        {
+            self.text = Some(value.to_vec());
+        }
+        match num {
+            Some(n) => {
+                let replace = self.num.as_ref().map_or(true, |(current, _)| {
+                    if is_min {
+                        n < *current
+                    } else {
+                        n > *current
+                    }
+                });
+                if replace {
+                    self.num = Some((n, value.to_vec()));
+                }
+            },
+            None => self.non_numeric = true,
+        }
+    }
+
+    fn merge(&mut self, other: Self, is_min: bool) {
+        self.non_numeric |= other.non_numeric;
+        if let Some(text) = other.text {
+            if self
+                .text
+                .as_ref()
+                .map_or(true, |t| if is_min { text < *t } else { text > *t })
+            {
+                self.text = Some(text);
+            }
+        }
+        if let Some((n, raw)) = other.num {
+            if self.num.as_ref().map_or(
+                true,
+                |(current, _)| {
+                    if is_min {
+                        n < *current
+                    } else {
+                        n > *current
+                    }
+                },
+            ) {
+                self.num = Some((n, raw));
+            }
+        }
+    }
+
+    fn output(&self) -> Vec<u8> {
+        if self.non_numeric {
+            self.text.clone().unwrap_or_default()
+        } else {
+            self.num
+                .as_ref()
+                .map(|(_, raw)| raw.clone())
+                .unwrap_or_default()
+        }
+    }
+}
+
+impl AggState {
+    pub(crate) fn new(func: AggFunc) -> Self {
+        match func {
+            AggFunc::Count => AggState::Count(0),
+            AggFunc::CountDistinct => AggState::CountDistinct(AHashSet::new()),
+            AggFunc::Sum => AggState::Sum(None),
+            AggFunc::Mean => AggState::Mean(Decimal::ZERO, 0),
+            AggFunc::Min => AggState::Min(Extreme::default()),
+            AggFunc::Max => AggState::Max(Extreme::default()),
+            AggFunc::First => AggState::First(None),
+            AggFunc::Last => AggState::Last(None),
+        }
+    }
+
+    /// update the state with the value of the aggregated column.
+    /// value is None for aggregates without a column (i.e. count)
+    #[inline]
+    pub(crate) fn update(&mut self, value: Option<&[u8]>, rowno: u64) -> CliResult<()> {
+        match (self, value) {
+            (AggState::Count(n), None) => *n += 1,
+            (AggState::First(first), Some(v)) => {
+                if first.is_none() {
+                    *first = Some((rowno, v.to_vec()));
+                }
+            },
+            (AggState::Last(last), Some(v)) => *last = Some((rowno, v.to_vec())),
+            (_, Some(v)) if v.is_empty() => {},
+            (AggState::Count(n), Some(_)) => *n += 1,
+            (AggState::CountDistinct(set), Some(v)) => {
+                if !set.contains(v) {
+                    set.insert(v.to_vec());
+                }
+            },
+            (AggState::Sum(sum), Some(v)) => {
+                if let Some(n) = parse_decimal(v) {
+                    *sum = Some(checked_add(sum.unwrap_or_default(), n)?);
+                }
+            },
+            (AggState::Mean(sum, count), Some(v)) => {
+                if let Some(n) = parse_decimal(v) {
+                    *sum = checked_add(*sum, n)?;
+                    *count += 1;
+                }
+            },
+            (AggState::Min(extreme), Some(v)) => extreme.update(v, parse_decimal(v), true),
+            (AggState::Max(extreme), Some(v)) => extreme.update(v, parse_decimal(v), false),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// merge the partial aggregate of another chunk or spill file into this one
+    fn merge(&mut self, other: Self) -> CliResult<()> {
+        match (self, other) {
+            (AggState::Count(a), AggState::Count(b)) => *a += b,
+            (AggState::CountDistinct(a), AggState::CountDistinct(b)) => a.extend(b),
+            (AggState::Sum(a), AggState::Sum(b)) => {
+                if let Some(b) = b {
+                    *a = Some(checked_add(a.unwrap_or_default(), b)?);
+                }
+            },
+            (AggState::Mean(sum_a, count_a), AggState::Mean(sum_b, count_b)) => {
+                *sum_a = checked_add(*sum_a, sum_b)?;
+                *count_a += count_b;
+            },
+            (AggState::Min(a), AggState::Min(b)) => a.merge(b, true),
+            (AggState::Max(a), AggState::Max(b)) => a.merge(b, false),
+            (AggState::First(a), AggState::First(b)) => {
+                if b.as_ref()
+                    .is_some_and(|(row_b, _)| a.as_ref().map_or(true, |(row_a, _)| row_b < row_a))
+                {
+                    *a = b;
+                }
+            },
+            (AggState::Last(a), AggState::Last(b)) => {
+                if b.as_ref()
+                    .is_some_and(|(row_b, _)| a.as_ref().map_or(true, |(row_a, _)| row_b > row_a))
+                {
+                    *a = b;
+                }
+            },
+            _ => return fail!("Cannot merge different aggregate states"),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn output(&self, round: u32) -> Vec<u8> {
+        match self {
+            AggState::Count(n) => itoa::Buffer::new().format(*n).as_bytes().to_vec(),
+            AggState::CountDistinct(set) => {
+                itoa::Buffer::new().format(set.len()).as_bytes().to_vec()
+            },
+            AggState::Sum(sum) => sum
+                .map(|s| s.normalize().to_string().into_bytes())
+                .unwrap_or_default(),
+            AggState::Mean(sum, count) => {
+                if *count == 0 {
+                    return Vec::new();
+                }
+                (*sum / Decimal::from(*count))
+                    .round_dp(round)
+                    .normalize()
+                    .to_string()
+                    .into_bytes()
+            },
+            AggState::Min(extreme) | AggState::Max(extreme) => extreme.output(),
+            AggState::First(v) | AggState::Last(v) => {
+                v.as_ref().map(|(_, v)| v.clone()).unwrap_or_default()
+            },
+        }
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        match self {
+            AggState::Count(n) => {
+                w.write_u8(0)?;
+                w.write_u64::<LittleEndian>(*n)?;
+            },
+            AggState::CountDistinct(set) => {
+                w.write_u8(1)?;
+                w.write_u64::<LittleEndian>(set.len() as u64)?;
+                for v in set {
+                    write_bytes(w, v)?;
+                }
+            },
+            AggState::Sum(sum) => {
+                w.write_u8(2)?;
+                write_opt_decimal(w, *sum)?;
+            },
+            AggState::Mean(sum, count) => {
+                w.write_u8(3)?;
+                w.write_all(&sum.serialize())?;
+                w.write_u64::<LittleEndian>(*count)?;
+            },
+            AggState::Min(extreme) | AggState::Max(extreme) => {
+                w.write_u8(if matches!(self, AggState::Min(_)) {
+                    4
+                } else {
+                    5
+                })?;
+                w.write_u8(u8::from(extreme.non_numeric))?;
+                write_opt_decimal(w, extreme.num.as_ref().map(|(n, _)| *n))?;
+                if let Some((_, raw)) = &extreme.num {
+                    write_bytes(w, raw)?;
+                }
+                w.write_u8(u8::from(extreme.text.is_some()))?;
+                if let Some(text) = &extreme.text {
+                    write_bytes(w, text)?;
+                }
+            },
+            AggState::First(v) | AggState::Last(v) => {
+                w.write_u8(if matches!(self, AggState::First(_)) {
+                    6
+                } else {
+                    7
+                })?;
+                w.write_u8(u8::from(v.is_some()))?;
+                if let Some((rowno, value)) = v {
+                    w.write_u64::<LittleEndian>(*rowno)?;
+                    write_bytes(w, value)?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let state = match r.read_u8()? {
+            0 => AggState::Count(r.read_u64::<LittleEndian>()?),
+            1 => {
+                let len = r.read_u64::<LittleEndian>()?;
+                let mut set = AHashSet::with_capacity(len as usize);
+                for _ in 0..len {
+                    set.insert(read_bytes(r)?);
+                }
+                AggState::CountDistinct(set)
+            },
+            2 => AggState::Sum(read_opt_decimal(r)?),
+            3 => {
+                let sum = read_decimal(r)?;
+                AggState::Mean(sum, r.read_u64::<LittleEndian>()?)
+            },
+            tag @ (4 | 5) => {
+                let non_numeric = r.read_u8()? == 1;
+                let num = match read_opt_decimal(r)? {
+                    Some(n) => Some((n, read_bytes(r)?)),
+                    None => None,
+                };
+                let text = if r.read_u8()? == 1 {
+                    Some(read_bytes(r)?)
+                } else {
+                    None
+                };
+                let extreme = Extreme {
+                    num,
+                    text,
+                    non_numeric,
+                };
+                if tag == 4 {
+                    AggState::Min(extreme)
+                } else {
+                    AggState::Max(extreme)
+                }
+            },
+            tag @ (6 | 7) => {
+                let v = if r.read_u8()? == 1 {
+                    let rowno = r.read_u64::<LittleEndian>()?;
+                    Some((rowno, read_bytes(r)?))
+                } else {
+                    None
+                };
+                if tag == 6 {
+                    AggState::First(v)
+                } else {
+                    AggState::Last(v)
+                }
+            },
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid aggregate state tag in spill file: {tag}"),
+                ))
+            },
+        };
+        Ok(state)
+    }
+}
+
+#[inline]
+fn checked_add(a: Decimal, b: Decimal) -> CliResult<Decimal> {
+    a.checked_add(b)
+        .ok_or_else(|| CliError::Other(format!("Decimal overflow when adding {a} and {b}")))
+}
+
+fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    w.write_u64::<LittleEndian>(bytes.len() as u64)?;
+    w.write_all(bytes)
+}
+
+fn read_bytes<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
+    let len = r.read_u64::<LittleEndian>()? as usize;
+    let mut buf = vec![0_u8; len];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn write_opt_decimal<W: Write>(w: &mut W, d: Option<Decimal>) -> io::Result<()> {
+    match d {
+        Some(d) => {
+            w.write_u8(1)?;
+            w.write_all(&d.serialize())
+        },
+        None => w.write_u8(0),
+    }
+}
+
+fn read_decimal<R: Read>(r: &mut R) -> io::Result<Decimal> {
+    let mut buf = [0_u8; 16];
+    r.read_exact(&mut buf)?;
+    Ok(Decimal::deserialize(buf))
+}
+
+fn read_opt_decimal<R: Read>(r: &mut R) -> io::Result<Option<Decimal>> {
+    if r.read_u8()? == 1 {
+        Ok(Some(read_decimal(r)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Hash aggregation of groups, spilling partial aggregates to sorted
+/// temporary files (runs) when the number of groups exceeds max_groups.
+struct Aggregator {
+    specs:      Vec<AggSpec>,
+    groups:     Groups,
+    runs:       Vec<NamedTempFile>,
+    max_groups: usize,
+    tmp_dir:    String,
+    key_buf:    GroupKey,
+}
+
+impl Aggregator {
+    fn new(specs: Vec<AggSpec>, max_groups: usize, args: &Args) -> Self {
+        Self {
+            specs,
+            groups: Groups::new(),
+            runs: Vec::new(),
+            max_groups,
+            tmp_dir: args.flag_tmp_dir.clone(),
+            key_buf: GroupKey::new(),
+        }
+    }
+
+    #[inline]
+    fn add(&mut self, key_sel: &Selection, record: &csv::ByteRecord, rowno: u64) -> CliResult<()> {
+        // reuse the key buffer to avoid allocating for groups that already exist
+        self.key_buf.resize_with(key_sel.len(), Vec::new);
+        for (buf, &i) in self.key_buf.iter_mut().zip(key_sel.iter()) {
+            buf.clear();
+            buf.extend_from_slice(record.get(i).unwrap_or_default());
+        }
+
+        let is_new = !self.groups.contains_key(self.key_buf.as_slice());
+        if is_new {
+            let states = self.specs.iter().map(|s| AggState::new(s.func)).collect();
+            self.groups.insert(self.key_buf.clone(), states);
+        }
+        // safety: the group was inserted above if it didn't exist
+        let states = self.groups.get_mut(self.key_buf.as_slice()).unwrap();
+        for (state, spec) in states.iter_mut().zip(&self.specs) {
+            state.update(spec.col.map(|c| record.get(c).unwrap_or_default()), rowno)?;
+        }
+
+        if is_new && self.groups.len() > self.max_groups {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// merge another aggregator (e.g. from a parallel chunk) into this one
+    fn absorb(&mut self, other: Aggregator) -> CliResult<()> {
+        self.runs.extend(other.runs);
+        for (key, states) in other.groups {
+            if let Some(existing) = self.groups.get_mut(&key) {
+                for (state, other_state) in existing.iter_mut().zip(states) {
+                    state.merge(other_state)?;
+                }
+            } else {
+                self.groups.insert(key, states);
+                if self.groups.len() > self.max_groups {
+                    self.spill()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// write the in-memory groups to a temporary file, sorted by key
+    fn spill(&mut self) -> CliResult<()> {
+        let mut groups: Vec<(GroupKey, Vec<AggState>)> = self.groups.drain().collect();
+        groups.par_sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        let tfile = NamedTempFile::new_in(&self.tmp_dir)?;
+        let mut wtr = BufWriter::with_capacity(RW_BUFFER_CAPACITY, tfile.as_file());
+        for (key, states) in &groups {
+            wtr.write_u64::<LittleEndian>(key.len() as u64)?;
+            for field in key {
+                write_bytes(&mut wtr, field)?;
+            }
+            for state in states {
+                state.write_to(&mut wtr)?;
+            }
+        }
+        wtr.flush()?;
+        drop(wtr);
+        log::debug!(
+            "spilled {} groups to {}",
+            groups.len(),
+            tfile.path().display()
+        );
+        self.runs.push(tfile);
+        Ok(())
+    }
+
+    /// call emit for every group in key order, merging spilled runs if needed
+    fn finish<F>(mut self, mut emit: F) -> CliResult<()>
+    where
+        F: FnMut(GroupKey, Vec<AggState>) -> CliResult<()>,
+    {
+        if self.runs.is_empty() {
+            let mut groups: Vec<(GroupKey, Vec<AggState>)> = self.groups.drain().collect();
+            groups.par_sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            for (key, states) in groups {
+                emit(key, states)?;
+            }
+            return Ok(());
+        }
+
+        if !self.groups.is_empty() {
+            self.spill()?;
+        }
+
+        // k-way merge of the sorted runs
+        let nstates = self.specs.len();
+        let mut readers = Vec::with_capacity(self.runs.len());
+        for run in &self.runs {
+            readers.push(BufReader::with_capacity(
+                RW_BUFFER_CAPACITY,
+                fs::File::open(run.path())?,
+            ));
+        }
+        let mut pending: Vec<Option<Vec<AggState>>> = vec![None; readers.len()];
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (i, rdr) in readers.iter_mut().enumerate() {
+            if let Some((key, states)) = read_group(rdr, nstates)? {
+                pending[i] = Some(states);
+                heap.push(Reverse((key, i)));
+            }
+        }
+
+        while let Some(Reverse((key, i))) = heap.pop() {
+            // safety: every run in the heap has pending states
+            let mut states = pending[i].take().unwrap();
+            if let Some((next_key, next_states)) = read_group(&mut readers[i], nstates)? {
+                pending[i] = Some(next_states);
+                heap.push(Reverse((next_key, i)));
+            }
+            while heap.peek().is_some_and(|Reverse((k, _))| *k == key) {
+                let Reverse((_, j)) = heap.pop().unwrap();
+                for (state, other) in states.iter_mut().zip(pending[j].take().unwrap()) {
+                    state.merge(other)?;
+                }
+                if let Some((next_key, next_states)) = read_group(&mut readers[j], nstates)? {
+                    pending[j] = Some(next_states);
+                    heap.push(Reverse((next_key, j)));
+                }
+            }
+            emit(key, states)?;
+        }
+        Ok(())
+    }
+}
+
+/// read the next group from a spilled run, returning None at the end of the run
+fn read_group<R: Read>(r: &mut R, nstates: usize) -> io::Result<Option<(GroupKey, Vec<AggState>)>> {
+    let nkeys = match r.read_u64::<LittleEndian>() {
+        Ok(n) => n as usize,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut key = Vec::with_capacity(nkeys);
+    for _ in 0..nkeys {
+        key.push(read_bytes(r)?);
+    }
+    let mut states = Vec::with_capacity(nstates);
+    for _ in 0..nstates {
+        states.push(AggState::read_from(r)?);
+    }
+    Ok(Some((key, states)))
+}
@@ -6,7 +6,27 @@ intersection of rows on the keys specified.
 
 Joins are always done by ignoring leading and trailing whitespace. By default,
 joins are done case sensitively, but this can be disabled with the --ignore-case
-flag.
+flag. Likewise, accents can be disregarded (e.g. "Zürich" matches "Zurich") with the
+--accent-insensitive flag.
+
+With --fuzzy, rows are joined on the similarity of their join columns rather than on
+their equality, e.g. to match company names or addresses that are spelled differently.
+The second input is loaded into memory, and every row of the first input is compared
+with every row of the second input, unless --block is used to only compare rows that
+have the same exact values in the blocking columns.
+
+  # join each customer with their best matching company within the same country
+  $ qsv join --fuzzy jaro-winkler --threshold 0.85 --block country \
+      company customers.csv name companies.csv
+
+  # list the 3 best candidates of every customer with their score, keeping non-matches
+  $ qsv join --fuzzy levenshtein --top-k 3 --left company customers.csv name companies.csv
+
+With --sort-merge, both inputs are sorted by their join columns with an external merge
+sort, and then joined with a streaming merge join. This joins inputs that are too large
+to be indexed in memory, in bounded memory.
+
+  $ qsv join --sort-merge --tmp-dir /tmp --full id huge1.csv id huge2.csv
 
 For examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_join.rs.
 
@@ -30,6 +50,7 @@ input arguments:
 
 join options:
     -i, --ignore-case      When set, joins are done case insensitively.
+    --accent-insensitive   When set, joins are done disregarding accents.
     --left                 Do a 'left outer' join. This returns all rows in
                            first CSV data set, including rows with no
                            corresponding row in the second data set. When no
@@ -61,6 +82,36 @@ join options:
                            (In fact, any row that has an empty field in the
                            key specified is ignored.)
 
+fuzzy join options:
+    --fuzzy <metric>       Join on the similarity of the join columns instead of
+                           their equality, using <metric>. Valid metrics are:
+                             jaro-winkler, levenshtein, damerau-levenshtein,
+                             sorensen-dice and eudex (sounds alike).
+                           With multiple join columns, the similarity is the mean
+                           of the similarities of the columns. A column with the
+                           similarity score of each match is added to the output.
+                           Only inner joins and --left joins are supported.
+    --threshold <arg>      The minimum similarity (0.0 to 1.0) for two rows
+                           to match. [default: 0.9]
+    --block <arg>          Only compare rows with the same values in these columns,
+                           which must be selected the same way in both inputs.
+    --top-k <n>            Join each row of <input1> with at most its <n> best
+                           matches, from the best to the worst. [default: 1]
+    --score-col <name>     The name of the similarity score column. [default: fuzzy_score]
+
+sort-merge join options:
+    --sort-merge           Sort both inputs by the join columns with an external merge
+                           sort that spills sorted runs to --tmp-dir, then merge join
+                           them, instead of indexing <input2> in memory. Only the rows
+                           of <input2> with the same join key are buffered in memory.
+                           The output is in join key order. --cross is not supported.
+    --memory-limit <arg>   The maximum amount of memory to buffer the external merge
+                           sort. If less than 50, this is a percentage of total memory.
+                           If more than 50, this is the memory in MB to allocate, capped
+                           at 90 percent of total memory. [default: 10]
+    --tmp-dir <arg>        The directory for the temporary files of the external
+                           merge sort. [default: ./]
+
 Common options:
     -h, --help             Display this message
     -o, --output <file>    Write output to <file> instead of stdout.
@@ -71,13 +122,26 @@ Common options:
                            Must be a single character. (default: ,)
 "#;
 
-use std::{collections::hash_map::Entry, fmt, io, iter::repeat, str};
+use std::{
+    cmp::Ordering,
+    collections::hash_map::Entry,
+    fmt, io,
+    iter::{once, repeat},
+    path::Path,
+    str,
+};
 
 use ahash::AHashMap;
 use byteorder::{BigEndian, WriteBytesExt};
+use ext_sort::{buffer::mem::MemoryLimitedBufferBuilder, ExternalSorter, ExternalSorterBuilder};
 use serde::Deserialize;
 
 use crate::{
+    cmd::{
+        dedup::FuzzyMetric,
+        diff::{decode_sort_item, encode_sort_item},
+        extdedup::calculate_memory_limit,
+    },
     config::{Config, Delimiter, SeekRead},
     index::Indexed,
     select::{SelectColumns, Selection},
@@ -88,25 +152,158 @@ use crate::{
 
 #[derive(Deserialize)]
 struct Args {
-    arg_columns1:     SelectColumns,
-    arg_input1:       String,
-    arg_columns2:     SelectColumns,
-    arg_input2:       String,
-    flag_left:        bool,
-    flag_left_anti:   bool,
-    flag_left_semi:   bool,
-    flag_right:       bool,
-    flag_full:        bool,
-    flag_cross:       bool,
-    flag_output:      Option<String>,
-    flag_no_headers:  bool,
-    flag_ignore_case: bool,
-    flag_nulls:       bool,
-    flag_delimiter:   Option<Delimiter>,
+    arg_columns1:            SelectColumns,
+    arg_input1:              String,
+    arg_columns2:            SelectColumns,
+    arg_input2:              String,
+    flag_left:               bool,
+    flag_left_anti:          bool,
+    flag_left_semi:          bool,
+    flag_right:              bool,
+    flag_full:               bool,
+    flag_cross:              bool,
+    flag_output:             Option<String>,
+    flag_no_headers:         bool,
+    flag_ignore_case:        bool,
+    flag_accent_insensitive: bool,
+    flag_nulls:              bool,
+    flag_delimiter:          Option<Delimiter>,
+    flag_fuzzy:              Option<String>,
+    flag_threshold:          f64,
+    flag_block:              Option<SelectColumns>,
+    flag_top_k:              usize,
+    flag_score_col:          String,
+    flag_sort_merge:         bool,
+    flag_memory_limit:       Option<u64>,
+    flag_tmp_dir:            Option<String>,
+}
+
+const RW_BUFFER_CAPACITY: usize = 1_000_000; // 1 MB
+
+type Sorter = ExternalSorter<Vec<u8>, io::Error, MemoryLimitedBufferBuilder>;
+
+/// How the values of the join columns are normalized before they're compared
+#[derive(Clone, Copy)]
+struct KeyFold {
+    casei:   bool,
+    accents: bool,
+}
+
+impl KeyFold {
+    #[inline]
+    fn transform(self, field: &[u8]) -> ByteString {
+        let value = util::transform(field, self.casei);
+        if self.accents {
+            if let Ok(s) = simdutf8::basic::from_utf8(&value) {
+                return util::strip_accents(s).into_bytes();
+            }
+        }
+        value
+    }
+}
+
+/// The join operations of a sort-merge join
+#[derive(Clone, Copy)]
+enum MergeJoin {
+    Inner,
+    Left,
+    LeftAnti,
+    LeftSemi,
+    Right,
+    Full,
+}
+
+/// The settings of a fuzzy join
+struct FuzzyJoin {
+    metric:    FuzzyMetric,
+    threshold: f64,
+    top_k:     usize,
+    block:     Option<SelectColumns>,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_sort_merge {
+        if args.flag_fuzzy.is_some() {
+            return fail_incorrectusage_clierror!("--sort-merge cannot be used with --fuzzy.");
+        }
+        let join = match (
+            args.flag_left,
+            args.flag_left_anti,
+            args.flag_left_semi,
+            args.flag_right,
+            args.flag_full,
+            args.flag_cross,
+        ) {
+            (true, false, false, false, false, false) => MergeJoin::Left,
+            (false, true, false, false, false, false) => MergeJoin::LeftAnti,
+            (false, false, true, false, false, false) => MergeJoin::LeftSemi,
+            (false, false, false, true, false, false) => MergeJoin::Right,
+            (false, false, false, false, true, false) => MergeJoin::Full,
+            (false, false, false, false, false, true) => {
+                return fail_incorrectusage_clierror!("--sort-merge cannot be used with --cross.");
+            },
+            (false, false, false, false, false, false) => MergeJoin::Inner,
+            _ => {
+                return fail_incorrectusage_clierror!("Please pick exactly one join operation.");
+            },
+        };
+
+        let tmp_dir = args.flag_tmp_dir.as_deref().unwrap_or("./");
+        if !Path::new(tmp_dir).exists() {
+            return fail_clierror!("tmp-dir '{tmp_dir}' does not exist");
+        }
+        let mem_limited_buffer_bytes = calculate_memory_limit(args.flag_memory_limit);
+        log::info!("{mem_limited_buffer_bytes} bytes used for in memory mergesort buffer...");
+        let sorter: Sorter = match ExternalSorterBuilder::new()
+            .with_tmp_dir(Path::new(tmp_dir))
+            .with_buffer(MemoryLimitedBufferBuilder::new(mem_limited_buffer_bytes))
+            .with_rw_buf_size(RW_BUFFER_CAPACITY)
+            .with_threads_number(util::njobs(None))
+            .build()
+        {
+            Ok(sorter) => sorter,
+            Err(e) => {
+                return fail_clierror!("cannot create external sorter: {e}");
+            },
+        };
+
+        let mut state = args.new_io_state()?;
+        if matches!(join, MergeJoin::LeftAnti | MergeJoin::LeftSemi) {
+            state.write_headers1()?;
+        } else {
+            state.write_headers()?;
+        }
+        return state.sort_merge_join(join, &sorter);
+    }
+
+    if let Some(ref metric) = args.flag_fuzzy {
+        if args.flag_left_anti
+            || args.flag_left_semi
+            || args.flag_right
+            || args.flag_full
+            || args.flag_cross
+        {
+            return fail_incorrectusage_clierror!(
+                "--fuzzy only supports inner joins and --left joins."
+            );
+        }
+        if !(0.0..=1.0).contains(&args.flag_threshold) {
+            return fail_incorrectusage_clierror!("--threshold must be between 0.0 and 1.0.");
+        }
+        if args.flag_top_k == 0 {
+            return fail_incorrectusage_clierror!("--top-k must be at least 1.");
+        }
+        let fuzzy = FuzzyJoin {
+            metric:    FuzzyMetric::from_name(metric)?,
+            threshold: args.flag_threshold,
+            top_k:     args.flag_top_k,
+            block:     args.flag_block.clone(),
+        };
+        let state = args.new_io_state()?;
+        return state.fuzzy_join(args.flag_left, &fuzzy, &args.flag_score_col);
+    }
+
     let mut state = args.new_io_state()?;
     match (
         args.flag_left,
@@ -155,7 +352,7 @@ struct IoState<R, W: io::Write> {
     rdr2:       csv::Reader<R>,
     sel2:       Selection,
     no_headers: bool,
-    casei:      bool,
+    fold:       KeyFold,
     nulls:      bool,
 }
 
@@ -179,11 +376,11 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
 
     fn inner_join(mut self) -> CliResult<()> {
         let mut scratch = csv::ByteRecord::new();
-        let mut validx = ValueIndex::new(self.rdr2, &self.sel2, self.casei, self.nulls)?;
+        let mut validx = ValueIndex::new(self.rdr2, &self.sel2, self.fold, self.nulls)?;
         let mut row = csv::ByteRecord::new();
         let mut key;
         while self.rdr1.read_byte_record(&mut row)? {
-            key = get_row_key(&self.sel1, &row, self.casei);
+            key = get_row_key(&self.sel1, &row, self.fold);
             if let Some(rows) = validx.values.get(&key) {
                 for &rowi in rows {
                     validx.idx.seek(rowi as u64)?;
@@ -205,11 +402,11 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
 
         let mut scratch = csv::ByteRecord::new();
         let (_, pad2) = self.get_padding()?;
-        let mut validx = ValueIndex::new(self.rdr2, &self.sel2, self.casei, self.nulls)?;
+        let mut validx = ValueIndex::new(self.rdr2, &self.sel2, self.fold, self.nulls)?;
         let mut row = csv::ByteRecord::new();
         let mut key;
         while self.rdr1.read_byte_record(&mut row)? {
-            key = get_row_key(&self.sel1, &row, self.casei);
+            key = get_row_key(&self.sel1, &row, self.fold);
             if let Some(rows) = validx.values.get(&key) {
                 for &rowi in rows {
                     validx.idx.seek(rowi as u64)?;
@@ -231,11 +428,11 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
     }
 
     fn left_join(mut self, anti: bool) -> CliResult<()> {
-        let validx = ValueIndex::new(self.rdr2, &self.sel2, self.casei, self.nulls)?;
+        let validx = ValueIndex::new(self.rdr2, &self.sel2, self.fold, self.nulls)?;
         let mut row = csv::ByteRecord::new();
         let mut key;
         while self.rdr1.read_byte_record(&mut row)? {
-            key = get_row_key(&self.sel1, &row, self.casei);
+            key = get_row_key(&self.sel1, &row, self.fold);
             if validx.values.get(&key).is_none() {
                 if anti {
                     self.wtr.write_record(&row)?;
@@ -250,14 +447,14 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
     fn full_outer_join(mut self) -> CliResult<()> {
         let mut scratch = csv::ByteRecord::new();
         let (pad1, pad2) = self.get_padding()?;
-        let mut validx = ValueIndex::new(self.rdr2, &self.sel2, self.casei, self.nulls)?;
+        let mut validx = ValueIndex::new(self.rdr2, &self.sel2, self.fold, self.nulls)?;
 
         // Keep track of which rows we've written from rdr2.
         let mut rdr2_written: Vec<_> = repeat(false).take(validx.num_rows).collect();
         let mut row1 = csv::ByteRecord::new();
         let mut key;
         while self.rdr1.read_byte_record(&mut row1)? {
-            key = get_row_key(&self.sel1, &row1, self.casei);
+            key = get_row_key(&self.sel1, &row1, self.fold);
             if let Some(rows) = validx.values.get(&key) {
                 for &rowi in rows {
                     rdr2_written[rowi] = true;
@@ -303,6 +500,157 @@ impl<R: io::Read + io::Seek, W: io::Write> IoState<R, W> {
         Ok(self.wtr.flush()?)
     }
 
+    fn fuzzy_join(mut self, left: bool, fuzzy: &FuzzyJoin, score_col: &str) -> CliResult<()> {
+        let (block1, block2) = match fuzzy.block {
+            Some(ref block) => {
+                let block1 = block.selection(self.rdr1.byte_headers()?, !self.no_headers)?;
+                let block2 = block.selection(self.rdr2.byte_headers()?, !self.no_headers)?;
+                if block1.len() != block2.len() {
+                    return fail_incorrectusage_clierror!(
+                        "--block must select the same number of columns in both inputs."
+                    );
+                }
+                (Some(block1), Some(block2))
+            },
+            None => (None, None),
+        };
+        let (_, pad2) = self.get_padding()?;
+        if !self.no_headers {
+            let mut headers = self.rdr1.byte_headers()?.clone();
+            headers.extend(self.rdr2.byte_headers()?.iter());
+            headers.push_field(score_col.as_bytes());
+            self.wtr.write_record(&headers)?;
+        }
+
+        // load the second input, grouped by blocking key
+        let mut rows2 = Vec::new();
+        let mut keys2 = Vec::new();
+        let mut blocks: AHashMap<Vec<ByteString>, Vec<usize>> = AHashMap::new();
+        let mut row = csv::ByteRecord::new();
+        while self.rdr2.read_byte_record(&mut row)? {
+            let Some(key) = get_fuzzy_key(&self.sel2, &row, self.fold, self.nulls) else {
+                continue;
+            };
+            let block_key = get_block_key(block2.as_ref(), &row, self.fold);
+            blocks.entry(block_key).or_default().push(rows2.len());
+            keys2.push(key);
+            rows2.push(row.clone());
+        }
+
+        let mut matches: Vec<(usize, f64)> = Vec::new();
+        while self.rdr1.read_byte_record(&mut row)? {
+            matches.clear();
+            if let Some(key1) = get_fuzzy_key(&self.sel1, &row, self.fold, self.nulls) {
+                let block_key = get_block_key(block1.as_ref(), &row, self.fold);
+                for &i in blocks.get(&block_key).into_iter().flatten() {
+                    let score = fuzzy.metric.mean_similarity(&key1, &keys2[i]);
+                    if score >= fuzzy.threshold {
+                        matches.push((i, score));
+                    }
+                }
+            }
+            // the best matches first. The sort is stable, so ties stay in input order
+            matches.sort_by(|a, b| b.1.total_cmp(&a.1));
+            matches.truncate(fuzzy.top_k);
+
+            for &(i, score) in &matches {
+                let score = util::round_num(score, 4);
+                self.wtr
+                    .write_record(row.iter().chain(&rows2[i]).chain(once(score.as_bytes())))?;
+            }
+            if matches.is_empty() && left {
+                self.wtr
+                    .write_record(row.iter().chain(&pad2).chain(once(&b""[..])))?;
+            }
+        }
+        Ok(self.wtr.flush()?)
+    }
+
+    fn sort_merge_join(mut self, join: MergeJoin, sorter: &Sorter) -> CliResult<()> {
+        let (pad1, pad2) = self.get_padding()?;
+        let IoState {
+            mut wtr,
+            rdr1,
+            sel1,
+            rdr2,
+            sel2,
+            fold,
+            nulls,
+            ..
+        } = self;
+        let mut rows1 = SortedRows::new(rdr1, sel1, fold, sorter, "<input1>")?;
+        let mut rows2 = SortedRows::new(rdr2, sel2, fold, sorter, "<input2>")?;
+        let joinable = |key: &Vec<ByteString>| nulls || !key.iter().any(Vec::is_empty);
+
+        rows1.advance()?;
+        rows2.advance()?;
+        let mut group2 = Vec::new();
+        loop {
+            // rows that cannot be joined are sent out unmatched right away
+            let ord = match (&rows1.current, &rows2.current) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((key1, _)), _) if !joinable(key1) => Ordering::Less,
+                (_, Some((key2, _))) if !joinable(key2) => Ordering::Greater,
+                (Some((key1, _)), Some((key2, _))) => key1.cmp(key2),
+            };
+            match ord {
+                Ordering::Less => {
+                    if let Some((_, ref row1)) = rows1.current {
+                        match join {
+                            MergeJoin::Left | MergeJoin::Full => {
+                                wtr.write_record(row1.iter().chain(&pad2))?;
+                            },
+                            MergeJoin::LeftAnti => wtr.write_record(row1)?,
+                            _ => {},
+                        }
+                    }
+                    rows1.advance()?;
+                },
+                Ordering::Greater => {
+                    if let Some((_, ref row2)) = rows2.current {
+                        if matches!(join, MergeJoin::Right | MergeJoin::Full) {
+                            wtr.write_record(pad1.iter().chain(row2))?;
+                        }
+                    }
+                    rows2.advance()?;
+                },
+                Ordering::Equal => {
+                    // buffer the rows of <input2> with this key, and join them
+                    // with every row of <input1> with the same key
+                    let key = rows2
+                        .current
+                        .as_ref()
+                        .map(|(key, _)| key.clone())
+                        .unwrap_or_default();
+                    group2.clear();
+                    while rows2.current.as_ref().is_some_and(|(k, _)| *k == key) {
+                        if let Some((_, row2)) = rows2.current.take() {
+                            group2.push(row2);
+                        }
+                        rows2.advance()?;
+                    }
+                    while rows1.current.as_ref().is_some_and(|(k, _)| *k == key) {
+                        if let Some((_, ref row1)) = rows1.current {
+                            match join {
+                                MergeJoin::LeftSemi => wtr.write_record(row1)?,
+                                MergeJoin::LeftAnti => {},
+                                _ => {
+                                    for row2 in &group2 {
+                                        wtr.write_record(row1.iter().chain(row2))?;
+                                    }
+                                },
+                            }
+                        }
+                        rows1.advance()?;
+                    }
+                },
+            }
+        }
+        Ok(wtr.flush()?)
+    }
+
     fn get_padding(&mut self) -> CliResult<(csv::ByteRecord, csv::ByteRecord)> {
         let len1 = self.rdr1.byte_headers()?.len();
         let len2 = self.rdr2.byte_headers()?.len();
@@ -336,7 +684,10 @@ impl Args {
             rdr2,
             sel2,
             no_headers: rconf1.no_headers,
-            casei: self.flag_ignore_case,
+            fold: KeyFold {
+                casei:   self.flag_ignore_case,
+                accents: self.flag_accent_insensitive,
+            },
             nulls: self.flag_nulls,
         })
     }
@@ -365,6 +716,60 @@ impl Args {
     }
 }
 
+/// The rows of one input of a sort-merge join, sorted by their join key
+/// with an external merge sort.
+struct SortedRows {
+    rows:    Box<dyn Iterator<Item = CliResult<csv::ByteRecord>>>,
+    sel:     Selection,
+    fold:    KeyFold,
+    current: Option<(Vec<ByteString>, csv::ByteRecord)>,
+}
+
+impl SortedRows {
+    fn new<R: io::Read>(
+        rdr: csv::Reader<R>,
+        sel: Selection,
+        fold: KeyFold,
+        sorter: &Sorter,
+        input: &'static str,
+    ) -> CliResult<Self> {
+        let items = rdr.into_byte_records().map(|row| {
+            row.map(|row| {
+                let key = get_row_key(&sel, &row, fold);
+                encode_sort_item(key.iter().map(Vec::as_slice), &row)
+            })
+            .map_err(io::Error::from)
+        });
+        let sorted = match sorter.sort_by(items, |a: &Vec<u8>, b: &Vec<u8>| a.cmp(b)) {
+            Ok(sorted) => sorted,
+            Err(e) => {
+                return fail!(format!("cannot do external sort of {input}: {e:?}"));
+            },
+        };
+        let num_key_cols = sel.len();
+        let rows = sorted.map(move |item| -> CliResult<csv::ByteRecord> {
+            match item {
+                Ok(item) => Ok(decode_sort_item(&item, num_key_cols)),
+                Err(e) => fail!(format!("cannot do external sort of {input}: {e:?}")),
+            }
+        });
+        Ok(Self {
+            rows: Box::new(rows),
+            sel,
+            fold,
+            current: None,
+        })
+    }
+
+    fn advance(&mut self) -> CliResult<()> {
+        self.current = match self.rows.next().transpose()? {
+            Some(row) => Some((get_row_key(&self.sel, &row, self.fold), row)),
+            None => None,
+        };
+        Ok(())
+    }
+}
+
 struct ValueIndex<R> {
     // This maps tuples of values to corresponding rows.
     values:   AHashMap<Vec<ByteString>, Vec<usize>>,
@@ -376,7 +781,7 @@ impl<R: io::Read + io::Seek> ValueIndex<R> {
     fn new(
         mut rdr: csv::Reader<R>,
         sel: &Selection,
-        casei: bool,
+        fold: KeyFold,
         nulls: bool,
     ) -> CliResult<ValueIndex<R>> {
         let mut val_idx = AHashMap::with_capacity(10000);
@@ -407,10 +812,7 @@ impl<R: io::Read + io::Seek> ValueIndex<R> {
             // indexes in one pass.
             row_idx.write_u64::<BigEndian>(row.position().unwrap().byte())?;
 
-            let fields: Vec<_> = sel
-                .select(&row)
-                .map(|v| util::transform(v, casei))
-                .collect();
+            let fields: Vec<_> = sel.select(&row).map(|v| fold.transform(v)).collect();
             if nulls || !fields.iter().any(std::vec::Vec::is_empty) {
                 match val_idx.entry(fields) {
                     Entry::Vacant(v) => {
@@ -455,6 +857,30 @@ impl<R> fmt::Debug for ValueIndex<R> {
 }
 
 #[inline]
-fn get_row_key(sel: &Selection, row: &csv::ByteRecord, casei: bool) -> Vec<ByteString> {
-    sel.select(row).map(|v| util::transform(v, casei)).collect()
+fn get_row_key(sel: &Selection, row: &csv::ByteRecord, fold: KeyFold) -> Vec<ByteString> {
+    sel.select(row).map(|v| fold.transform(v)).collect()
+}
+
+#[inline]
+fn get_block_key(sel: Option<&Selection>, row: &csv::ByteRecord, fold: KeyFold) -> Vec<ByteString> {
+    sel.map_or_else(Vec::new, |sel| get_row_key(sel, row, fold))
+}
+
+/// The key of a row for a fuzzy join, or None if the row has an empty key field
+/// and nulls are not joined.
+fn get_fuzzy_key(
+    sel: &Selection,
+    row: &csv::ByteRecord,
+    fold: KeyFold,
+    nulls: bool,
+) -> Option<Vec<String>> {
+    let key = get_row_key(sel, row, fold);
+    if !nulls && key.iter().any(Vec::is_empty) {
+        return None;
+    }
+    Some(
+        key.into_iter()
+            .map(|field| String::from_utf8_lossy(&field).into_owned())
+            .collect(),
+    )
 }
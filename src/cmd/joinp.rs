@@ -5,8 +5,8 @@ The default join operation is an 'inner' join. This corresponds to the
 intersection of rows on the keys specified.
 
 Unlike the join command, joinp can process files larger than RAM, is multithreaded,
-has join key validation, pre-join filtering, supports asof joins & its output columns
-can be coalesced (no duplicate columns).
+has join key validation, pre-join filtering, supports asof & range joins & its output
+columns can be coalesced (no duplicate columns).
 
 However, joinp doesn't have an --ignore-case option.
 
@@ -17,6 +17,7 @@ For examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_join
 Usage:
     qsv joinp [options] <columns1> <input1> <columns2> <input2>
     qsv joinp --cross [--validate <arg>] <input1> <input2> [--output <file>]
+    qsv joinp --range <arg> [options] <input1> <input2>
     qsv joinp --help
 
 joinp arguments:
@@ -171,6 +172,29 @@ joinp options:
                              large for their month should saturate at the largest date
                              (e.g. 2022-02-29 -> 2022-02-28) instead of erroring.
 
+                           RANGE JOIN OPTIONS:
+    --range <arg>          Do a range (interval) join. <arg> is "column,lower,upper",
+                           where column is a column of the left CSV and lower & upper
+                           are the bound columns of the right CSV. Rows are joined
+                           when the column value is between the bounds, e.g. an event
+                           timestamp between the start and end of a period, or a price
+                           within a price band. IP addresses have to be converted to
+                           integers first to join them with the bounds of CIDR ranges.
+                           Without <columns1> and <columns2>, this is an inequality
+                           join that doesn't compare every pair of rows, so it's
+                           efficient with large interval tables. With <columns1> and
+                           <columns2>, rows must also have equal join keys.
+                           --coalesce, --nulls and --validate can only be used
+                           with join keys.
+                           Range joins are inner joins, and dates are parsed
+                           automatically as with --try-parsedates.
+    --range-closed <arg>   Which bounds of the range are inclusive:
+                             both - lower <= column <= upper
+                             left - lower <= column < upper
+                             right - lower < column <= upper
+                             none - lower < column < upper
+                           [default: both]
+
                              OUTPUT FORMAT OPTIONS:
    --sql-filter <SQL>        The SQL expression to apply against the join result.
                              Ordinarily used to select columns and filter rows from 
@@ -252,6 +276,73 @@ struct Args {
     flag_delimiter:        Option<Delimiter>,
     flag_quiet:            bool,
     flag_ignore_case:      bool,
+    flag_range:            Option<String>,
+    flag_range_closed:     String,
+}
+
+const RANGE_LOWER_COL: &str = "_qsv-range-lower";
+const RANGE_UPPER_COL: &str = "_qsv-range-upper";
+
+/// The column and the bounds of a range join
+struct RangeJoin {
+    column:          String,
+    lower:           String,
+    upper:           String,
+    lower_inclusive: bool,
+    upper_inclusive: bool,
+}
+
+impl RangeJoin {
+    fn new(range: &str, closed: &str) -> CliResult<Self> {
+        let [column, lower, upper] = range.split(',').collect::<Vec<_>>()[..] else {
+            return fail_incorrectusage_clierror!(
+                "--range must be \"column,lower,upper\", got \"{range}\"."
+            );
+        };
+        let (lower_inclusive, upper_inclusive) = match closed.to_lowercase().as_str() {
+            "both" => (true, true),
+            "left" => (true, false),
+            "right" => (false, true),
+            "none" => (false, false),
+            s => return fail_incorrectusage_clierror!("Invalid --range-closed: {s}"),
+        };
+        Ok(Self {
+            column: column.to_string(),
+            lower: lower.to_string(),
+            upper: upper.to_string(),
+            lower_inclusive,
+            upper_inclusive,
+        })
+    }
+
+    /// Copy the bound columns of the right data set to temporary columns, so the
+    /// predicates still refer to them if a left column has the same name, in which
+    /// case the join renames the right column.
+    fn alias_bounds(&self, right_lf: LazyFrame) -> LazyFrame {
+        right_lf.with_columns([
+            col(self.lower.as_str()).alias(RANGE_LOWER_COL),
+            col(self.upper.as_str()).alias(RANGE_UPPER_COL),
+        ])
+    }
+
+    /// The predicates comparing the column to the (aliased) lower and upper bounds
+    fn predicates(&self) -> Vec<Expr> {
+        let value = col(self.column.as_str());
+        let lower = col(RANGE_LOWER_COL);
+        let upper = col(RANGE_UPPER_COL);
+        vec![
+            if self.lower_inclusive {
+                value.clone().gt_eq(lower)
+            } else {
+                value.clone().gt(lower)
+            },
+            if self.upper_inclusive {
+                value.lt_eq(upper)
+            } else {
+                value.lt(upper)
+            },
+        ]
+    }
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
@@ -264,8 +355,41 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         args.flag_try_parsedates = true;
     }
 
+    let range = match args.flag_range {
+        Some(ref range) => {
+            if args.flag_left
+                || args.flag_left_anti
+                || args.flag_left_semi
+                || args.flag_right
+                || args.flag_full
+                || args.flag_cross
+                || args.flag_asof
+            {
+                return fail_incorrectusage_clierror!("Range joins are always inner joins.");
+            }
+            // join_where doesn't validate or coalesce join keys, as there are none
+            if args.arg_columns1.is_empty()
+                && (args.flag_coalesce
+                    || args.flag_nulls
+                    || args
+                        .flag_validate
+                        .as_ref()
+                        .is_some_and(|v| !v.eq_ignore_ascii_case("none")))
+            {
+                return fail_incorrectusage_clierror!(
+                    "--coalesce, --nulls and --validate require join keys with --range."
+                );
+            }
+            // the bounds are often dates
+            args.flag_try_parsedates = true;
+            Some(RangeJoin::new(range, &args.flag_range_closed)?)
+        },
+        None => None,
+    };
+
     let tmpdir = tempdir()?;
-    let join = args.new_join(&tmpdir)?;
+    let mut join = args.new_join(&tmpdir)?;
+    join.range = range;
 
     let flag_validate = args
         .flag_validate
@@ -387,6 +511,7 @@ struct JoinStruct {
     float_precision:  Option<usize>,
     null_value:       String,
     ignore_case:      bool,
+    range:            Option<RangeJoin>,
 }
 
 impl JoinStruct {
@@ -396,14 +521,17 @@ impl JoinStruct {
         validation: JoinValidation,
         asof_join: bool,
     ) -> CliResult<(usize, usize)> {
+        // range joins without equality keys have no join columns
         let mut left_selcols: Vec<_> = self
             .left_sel
             .split(',')
+            .filter(|c| !c.is_empty())
             .map(polars::lazy::dsl::col)
             .collect();
         let mut right_selcols: Vec<_> = self
             .right_sel
             .split(',')
+            .filter(|c| !c.is_empty())
             .map(polars::lazy::dsl::col)
             .collect();
 
@@ -497,7 +625,40 @@ impl JoinStruct {
 
         // log::debug!("Optimization flags: {optimization_flags:?}");
 
-        let join_results = if jointype == JoinType::Cross {
+        let join_results = if let Some(ref range) = self.range {
+            let builder = self
+                .left_lf
+                .with_optimizations(optflags)
+                .join_builder()
+                .with(
+                    range
+                        .alias_bounds(self.right_lf)
+                        .with_optimizations(optflags),
+                )
+                .allow_parallel(true);
+            let joined = if left_selcols.is_empty() {
+                // an inequality join, which sorts the bounds instead of
+                // comparing every pair of rows
+                builder.join_where(range.predicates()).collect()?
+            } else {
+                // an equality join on the keys, with the rows filtered by range
+                let in_range = range
+                    .predicates()
+                    .into_iter()
+                    .reduce(Expr::and)
+                    .unwrap_or_else(|| lit(true));
+                builder
+                    .left_on(left_selcols)
+                    .right_on(right_selcols)
+                    .how(JoinType::Inner)
+                    .coalesce(coalesce_flag)
+                    .validate(validation)
+                    .finish()
+                    .filter(in_range)
+                    .collect()?
+            };
+            joined.drop_many([RANGE_LOWER_COL, RANGE_UPPER_COL])
+        } else if jointype == JoinType::Cross {
             // cross join doesn't need join columns
             self.left_lf
                 .with_optimizations(optflags)
@@ -851,6 +1012,7 @@ impl Args {
                 self.flag_null_value.clone()
             },
             ignore_case: self.flag_ignore_case,
+            range: None,
         })
     }
 }
@@ -0,0 +1,494 @@
+static USAGE: &str = r#"
+Merge one or more change files into a base CSV by key, e.g. to apply the insert,
+update & delete batches of a change data capture (CDC) feed to a master file.
+
+The change files are applied in order, row by row. A change row whose key is in the
+base is an update, otherwise it's an insert. The columns of the change files are matched
+to the base columns by name, so a change file can update a subset of the columns, with
+the other columns keeping their values (or staying empty for inserts). Change file
+columns that are not in the base are ignored.
+
+The output has the base rows in their original order, with updated rows updated in place
+and deleted rows removed. Inserted rows are appended at the end, in change order.
+
+Strategies (--strategy):
+    upsert       Apply updates and inserts (the default).
+    update-only  Only apply updates, skipping change rows with new keys.
+    insert-only  Only apply inserts, skipping change rows with existing keys.
+
+Deletes are applied with all strategies when --delete-col is set.
+
+With --timestamp, the last write wins: a change is only applied if its timestamp is the
+same or later than the timestamp of the row it changes, or of the delete of its key. The
+timestamps are parsed as dates (19 formats recognized, see `datefmt`), and compared as
+strings otherwise.
+
+With --scd2, the output is a Slowly Changing Dimension (SCD) Type 2 table: instead of
+being updated in place or removed, the current version of a row is closed by setting its
+valid_to column to the time of the change and its is_current column to false. Updated
+and inserted rows are appended as new versions, with valid_from set to the time of the
+change and is_current set to true. The time of a change is the value of its --timestamp
+column, or --as-of. If the base already has valid_from, valid_to & is_current columns,
+e.g. from a previous merge, they are maintained. Otherwise, they are added, and the base
+rows become current versions with an empty valid_from.
+Changes that don't modify any value don't create a new version.
+
+Examples:
+
+  # merge two batches of changes keyed by id into master.csv
+  $ qsv merge id master.csv batch1.csv batch2.csv -o new_master.csv
+
+  # last write wins using the updated_at column, with deletes marked by op=D
+  $ qsv merge id master.csv batch*.csv --timestamp updated_at --delete-col op
+
+  # maintain the history of customers as an SCD Type 2 table
+  $ qsv merge customer_id customers_scd.csv changes.csv --scd2 --as-of 2024-06-01
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_merge.rs.
+
+Usage:
+    qsv merge [options] <columns> <base> <changes>...
+    qsv merge --help
+
+merge arguments:
+    <columns>              The key columns of the base, which identify a row.
+                           See 'qsv select --help' for the format details.
+                           The change files must have the same key columns.
+    <base>                 The base CSV.
+    <changes>              The change files, applied in order.
+
+merge options:
+    --strategy <arg>       How to merge changes. One of upsert, update-only
+                           or insert-only. [default: upsert]
+    --timestamp <col>      The column with the time of a change, for last write wins.
+                           It must be in the base and in the change files.
+    --prefer-dmy           Prefer to parse --timestamp dates in dmy format.
+                           Otherwise, use mdy format.
+    --delete-col <col>     The column of the change files marking deletes.
+    --delete-value <arg>   The value of the --delete-col column marking a delete,
+                           compared case-insensitively. [default: D]
+    --scd2                 Output an SCD Type 2 table with valid_from, valid_to &
+                           is_current columns.
+    --as-of <arg>          With --scd2, the time of the changes when --timestamp
+                           is not set. (default: the current UTC time)
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. The columns of the change files are then
+                           matched to the base columns by position.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+"#;
+
+use std::cmp::Ordering;
+
+use ahash::AHashMap;
+use chrono::Utc;
+use csv::ByteRecord;
+use qsv_dateparser::parse_with_preference_and_timezone;
+use serde::Deserialize;
+
+use crate::{
+    config::{Config, Delimiter},
+    select::SelectColumns,
+    util, CliResult,
+};
+
+#[derive(Deserialize)]
+struct Args {
+    arg_columns:       SelectColumns,
+    arg_base:          String,
+    arg_changes:       Vec<String>,
+    flag_strategy:     String,
+    flag_timestamp:    Option<SelectColumns>,
+    flag_prefer_dmy:   bool,
+    flag_delete_col:   Option<SelectColumns>,
+    flag_delete_value: String,
+    flag_scd2:         bool,
+    flag_as_of:        Option<String>,
+    flag_output:       Option<String>,
+    flag_no_headers:   bool,
+    flag_delimiter:    Option<Delimiter>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    Upsert,
+    UpdateOnly,
+    InsertOnly,
+}
+
+const SCD2_COLUMNS: [&str; 3] = ["valid_from", "valid_to", "is_current"];
+
+type Key = Vec<Vec<u8>>;
+
+/// The SCD Type 2 columns of the output rows.
+#[derive(Clone, Copy)]
+struct Scd2Cols {
+    valid_from: usize,
+    valid_to:   usize,
+    is_current: usize,
+}
+
+impl Scd2Cols {
+    fn contains(self, col: usize) -> bool {
+        col == self.valid_from || col == self.valid_to || col == self.is_current
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    inserted:  u64,
+    updated:   u64,
+    deleted:   u64,
+    unchanged: u64,
+    skipped:   u64,
+}
+
+/// The merged rows.
+struct Merger {
+    strategy:   Strategy,
+    key_cols:   Vec<usize>,
+    ts_col:     Option<usize>,
+    prefer_dmy: bool,
+    scd2:       Option<Scd2Cols>,
+    /// the time of the changes with --scd2, when there is no --timestamp
+    as_of:      String,
+    /// the output rows. Deleted rows are None.
+    rows:       Vec<Option<ByteRecord>>,
+    /// the index of the current row of each key
+    current:    AHashMap<Key, usize>,
+    /// the timestamp of the delete of each deleted key
+    tombstones: AHashMap<Key, Vec<u8>>,
+    counts:     Counts,
+}
+
+impl Merger {
+    fn key(&self, row: &ByteRecord) -> Key {
+        self.key_cols
+            .iter()
+            .map(|&c| row.get(c).unwrap_or_default().to_vec())
+            .collect()
+    }
+
+    fn push(&mut self, row: ByteRecord) {
+        let key = self.key(&row);
+        self.current.insert(key, self.rows.len());
+        self.rows.push(Some(row));
+    }
+
+    /// Is a change with timestamp `ts` the same or later than `than`?
+    fn is_not_older(&self, ts: &[u8], than: &[u8]) -> bool {
+        let parse = |field: &[u8]| {
+            let s = simdutf8::basic::from_utf8(field).ok()?.trim();
+            parse_with_preference_and_timezone(s, self.prefer_dmy, &Utc)
+                .ok()
+                .map(|dt| dt.timestamp_micros())
+        };
+        let ord = match (parse(ts), parse(than)) {
+            (Some(ts), Some(than)) => ts.cmp(&than),
+            _ => ts.cmp(than),
+        };
+        ord != Ordering::Less
+    }
+
+    /// The change row with its columns arranged like the output rows. The columns
+    /// missing from the change keep the values of `base`, if any.
+    fn build(base: Option<&ByteRecord>, change: &ByteRecord, map: &[Option<usize>]) -> ByteRecord {
+        map.iter()
+            .enumerate()
+            .map(|(c, change_col)| match change_col {
+                Some(cc) => change.get(*cc).unwrap_or_default(),
+                None => base.and_then(|b| b.get(c)).unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Close the current version of an SCD Type 2 row.
+    fn close(&mut self, i: usize, scd2: Scd2Cols, time: &[u8]) {
+        if let Some(row) = self.rows[i].take() {
+            self.rows[i] = Some(
+                row.iter()
+                    .enumerate()
+                    .map(|(c, field)| {
+                        if c == scd2.valid_to {
+                            time
+                        } else if c == scd2.is_current {
+                            &b"false"[..]
+                        } else {
+                            field
+                        }
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    /// Open a new SCD Type 2 version.
+    fn open(&mut self, row: &ByteRecord, scd2: Scd2Cols, time: &[u8]) {
+        let version = row
+            .iter()
+            .enumerate()
+            .map(|(c, field)| {
+                if c == scd2.valid_from {
+                    time
+                } else if c == scd2.valid_to {
+                    &b""[..]
+                } else if c == scd2.is_current {
+                    &b"true"[..]
+                } else {
+                    field
+                }
+            })
+            .collect();
+        self.push(version);
+    }
+
+    /// Apply a change row. `map` maps the output columns to the change row columns.
+    fn apply(&mut self, change: &ByteRecord, map: &[Option<usize>], is_delete: bool) {
+        let key: Key = self
+            .key_cols
+            .iter()
+            .map(|&c| {
+                map[c]
+                    .and_then(|cc| change.get(cc))
+                    .unwrap_or_default()
+                    .to_vec()
+            })
+            .collect();
+        let ts = self
+            .ts_col
+            .map(|c| map[c].and_then(|cc| change.get(cc)).unwrap_or_default());
+        let time = ts.unwrap_or(self.as_of.as_bytes()).to_vec();
+
+        let Some(&i) = self.current.get(&key) else {
+            // a new key
+            if let (Some(ts), Some(deleted)) = (ts, self.tombstones.get(&key)) {
+                if !self.is_not_older(ts, deleted) {
+                    self.counts.skipped += 1;
+                    return;
+                }
+            }
+            if is_delete {
+                if let Some(ts) = ts {
+                    self.tombstones.insert(key, ts.to_vec());
+                }
+                self.counts.skipped += 1;
+                return;
+            }
+            if self.strategy == Strategy::UpdateOnly {
+                self.counts.skipped += 1;
+                return;
+            }
+            let row = Self::build(None, change, map);
+            match self.scd2 {
+                Some(scd2) => self.open(&row, scd2, &time),
+                None => self.push(row),
+            }
+            self.counts.inserted += 1;
+            return;
+        };
+
+        // safety: the current rows are never None
+        let base = self.rows[i].as_ref().unwrap();
+        if let (Some(ts), Some(ts_col)) = (ts, self.ts_col) {
+            if !self.is_not_older(ts, base.get(ts_col).unwrap_or_default()) {
+                self.counts.skipped += 1;
+                return;
+            }
+        }
+        if is_delete {
+            match self.scd2 {
+                Some(scd2) => self.close(i, scd2, &time),
+                None => self.rows[i] = None,
+            }
+            self.current.remove(&key);
+            if let Some(ts) = ts {
+                self.tombstones.insert(key, ts.to_vec());
+            }
+            self.counts.deleted += 1;
+            return;
+        }
+        if self.strategy == Strategy::InsertOnly {
+            self.counts.skipped += 1;
+            return;
+        }
+        let row = Self::build(Some(base), change, map);
+        if row.iter().eq(base.iter()) {
+            self.counts.unchanged += 1;
+            return;
+        }
+        match self.scd2 {
+            Some(scd2) => {
+                self.close(i, scd2, &time);
+                self.open(&row, scd2, &time);
+            },
+            None => self.rows[i] = Some(row),
+        }
+        self.counts.updated += 1;
+    }
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let strategy = match args.flag_strategy.to_lowercase().as_str() {
+        "upsert" => Strategy::Upsert,
+        "update-only" => Strategy::UpdateOnly,
+        "insert-only" => Strategy::InsertOnly,
+        strategy => return fail_incorrectusage_clierror!("Invalid --strategy: {strategy}"),
+    };
+    let use_names = !args.flag_no_headers;
+    let base_config = Config::new(Some(&args.arg_base))
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+    let prefer_dmy = args.flag_prefer_dmy || base_config.get_dmy_preference();
+
+    let mut base_rdr = base_config.reader()?;
+    let mut headers = base_rdr.byte_headers()?.clone();
+    let base_width = headers.len();
+    let key_cols = args.arg_columns.selection(&headers, use_names)?.to_vec();
+    let ts_col = match args.flag_timestamp {
+        Some(ref sel) => {
+            let ts_sel = sel.selection(&headers, use_names)?;
+            if ts_sel.len() != 1 {
+                return fail_incorrectusage_clierror!(
+                    "--timestamp must select exactly one column."
+                );
+            }
+            Some(ts_sel[0])
+        },
+        None => None,
+    };
+
+    // the SCD Type 2 columns, from the base if it has them, or appended to the base columns
+    let mut scd2_added = false;
+    let scd2 = if args.flag_scd2 {
+        let existing: Option<Vec<usize>> = if use_names {
+            SCD2_COLUMNS
+                .iter()
+                .map(|name| headers.iter().position(|h| h == name.as_bytes()))
+                .collect()
+        } else {
+            None
+        };
+        let cols = existing.unwrap_or_else(|| {
+            scd2_added = true;
+            for name in SCD2_COLUMNS {
+                headers.push_field(name.as_bytes());
+            }
+            vec![base_width, base_width + 1, base_width + 2]
+        });
+        Some(Scd2Cols {
+            valid_from: cols[0],
+            valid_to:   cols[1],
+            is_current: cols[2],
+        })
+    } else {
+        None
+    };
+
+    let mut merger = Merger {
+        strategy,
+        key_cols,
+        ts_col,
+        prefer_dmy,
+        scd2,
+        as_of: args
+            .flag_as_of
+            .clone()
+            .unwrap_or_else(|| Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()),
+        rows: Vec::new(),
+        current: AHashMap::new(),
+        tombstones: AHashMap::new(),
+        counts: Counts::default(),
+    };
+
+    for row in base_rdr.into_byte_records() {
+        let mut row = row?;
+        if scd2_added {
+            row.push_field(b"");
+            row.push_field(b"");
+            row.push_field(b"true");
+        }
+        match scd2 {
+            // only the current versions of an SCD Type 2 base can be changed
+            Some(scd2) if row.get(scd2.is_current) != Some(&b"true"[..]) => {
+                merger.rows.push(Some(row));
+            },
+            _ => merger.push(row),
+        }
+    }
+
+    let delete_value = args.flag_delete_value.as_bytes();
+    let mut change = ByteRecord::new();
+    for change_path in &args.arg_changes {
+        let change_config = Config::new(Some(change_path))
+            .delimiter(args.flag_delimiter)
+            .no_headers(args.flag_no_headers);
+        let mut change_rdr = change_config.reader()?;
+        let change_headers = change_rdr.byte_headers()?.clone();
+
+        // map the output columns to the columns of the change file
+        let map: Vec<Option<usize>> = (0..headers.len())
+            .map(|c| {
+                if scd2.is_some_and(|scd2| scd2.contains(c)) {
+                    None
+                } else if use_names {
+                    change_headers.iter().position(|h| h == &headers[c])
+                } else {
+                    (c < change_headers.len()).then_some(c)
+                }
+            })
+            .collect();
+        for &c in merger.key_cols.iter().chain(&merger.ts_col) {
+            if map[c].is_none() {
+                return fail_incorrectusage_clierror!(
+                    "Column '{}' is not in change file {change_path}.",
+                    String::from_utf8_lossy(&headers[c])
+                );
+            }
+        }
+        let delete_col = match args.flag_delete_col {
+            Some(ref sel) => {
+                let delete_sel = sel.selection(&change_headers, use_names)?;
+                if delete_sel.len() != 1 {
+                    return fail_incorrectusage_clierror!(
+                        "--delete-col must select exactly one column."
+                    );
+                }
+                Some(delete_sel[0])
+            },
+            None => None,
+        };
+
+        while change_rdr.read_byte_record(&mut change)? {
+            let is_delete = delete_col.is_some_and(|dc| {
+                change
+                    .get(dc)
+                    .is_some_and(|v| v.eq_ignore_ascii_case(delete_value))
+            });
+            merger.apply(&change, &map, is_delete);
+        }
+    }
+
+    let counts = &merger.counts;
+    log::info!(
+        "{} inserted, {} updated, {} deleted, {} unchanged, {} skipped.",
+        counts.inserted,
+        counts.updated,
+        counts.deleted,
+        counts.unchanged,
+        counts.skipped
+    );
+
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+    if use_names {
+        wtr.write_byte_record(&headers)?;
+    }
+    for row in merger.rows.iter().flatten() {
+        wtr.write_byte_record(row)?;
+    }
+    Ok(wtr.flush()?)
+}
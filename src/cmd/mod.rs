@@ -23,6 +23,8 @@ pub mod exclude;
 pub mod explode;
 pub mod extdedup;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
+pub mod extract;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
 pub mod extsort;
 #[cfg(all(feature = "fetch", feature = "feature_capable"))]
 pub mod fetch;
@@ -41,6 +43,8 @@ pub mod foreach;
 pub mod frequency;
 #[cfg(all(feature = "geocode", feature = "feature_capable"))]
 pub mod geocode;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+pub mod groupby;
 pub mod headers;
 pub mod index;
 pub mod input;
@@ -60,8 +64,17 @@ pub mod lens;
 #[cfg(feature = "luau")]
 pub mod luau;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
+pub mod merge;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
 pub mod partition;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
+pub mod patch;
+#[cfg(all(
+    feature = "polars",
+    any(feature = "feature_capable", feature = "datapusher_plus")
+))]
+pub mod pivotp;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
 pub mod pro;
 #[cfg(feature = "prompt")]
 pub mod prompt;
@@ -70,6 +83,8 @@ pub mod pseudo;
 pub mod python;
 pub mod rename;
 pub mod replace;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+pub mod resample;
 pub mod reverse;
 pub mod safenames;
 pub mod sample;
@@ -100,5 +115,13 @@ pub mod to;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
 pub mod tojsonl;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
+pub mod topn;
+#[cfg(feature = "feature_capable")]
+pub mod translate;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
 pub mod transpose;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+pub mod unpivot;
 pub mod validate;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+pub mod window;
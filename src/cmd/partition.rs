@@ -7,7 +7,23 @@ by kb-size.
 The files are written to the output directory with filenames based on the
 values in the partition column and the `--filename` flag.
 
-EXAMPLE:
+With --hive, the data can be partitioned on several columns, and the files are
+written to nested Hive-style directories named <column>=<value>, one level per
+partition column, e.g. state=NY/city=Buffalo/part-0.csv. Values are percent-encoded
+so any value maps to a distinct, safe directory name, and empty values are written to
+the __HIVE_DEFAULT_PARTITION__ directory. This layout can be read directly by query
+engines like DuckDB, Spark, Polars & Trino.
+
+With --buckets, the rows are hash-partitioned into a fixed number of buckets based on
+the values of the partition column/s, instead of one partition per distinct value.
+
+With --max-rows, a partition is rolled over to a new file once it has that many rows.
+
+With --format parquet, each partition file is written in Parquet format (requires the
+polars feature). The column types are inferred once from a full scan of the input,
+so all the partitions have the same schema.
+
+EXAMPLES:
 
 Partition nyc311.csv file into separate files based on the value of the
 "Borough" column in the current directory:
@@ -20,6 +36,17 @@ will create the following files, each containing the data for each borough:
     nyc311-Queens.csv
     nyc311-Staten_Island.csv
 
+Partition by year and month into Hive-style Parquet files of at most 1 million rows,
+dropping the partition columns from the files as they're encoded in the directories:
+    $ qsv partition year,month outdir --hive --drop --max-rows 1000000 --format parquet data.csv
+
+will create files like outdir/year=2024/month=1/part-0.parquet.
+
+Hash-partition by customer_id into 16 buckets:
+    $ qsv partition customer_id outdir --hive --buckets 16 orders.csv
+
+will create files like outdir/bucket=0/part-0.csv to outdir/bucket=15/part-0.csv.
+
 For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_partition.rs.
 
 Usage:
@@ -30,7 +57,8 @@ partition arguments:
     <column>                 The column to use as a key for partitioning.
                              You can use the `--select` option to select
                              the column by name or index, but only one
-                             column can be used for partitioning.
+                             column can be used for partitioning, unless
+                             --hive or --buckets is set.
                              See `select` command for more details.
     <outdir>                 The directory to write the output files to.
     <input>                  The CSV file to read from. If not specified, then
@@ -41,25 +69,40 @@ partition options:
                              names of the output files.  The string '{}' will
                              be replaced by a value based on the partition column,
                              but sanitized for shell safety.
+                             Ignored with --hive, where the files are named part-<N>.
                              [default: {}.csv]
     -p, --prefix-length <n>  Truncate the partition column after the
                              specified number of bytes when creating the
                              output file.
-    --drop                   Drop the partition column from results.
+    --drop                   Drop the partition column/s from results.
+    --hive                   Write the partitions to nested Hive-style
+                             <column>=<value> directories.
+    --buckets <n>            Hash-partition the rows into <n> buckets.
+                             The buckets are named by their 0-based number.
+    --max-rows <n>           The maximum number of rows per file. When a partition
+                             has more rows, they're written to additional files,
+                             named part-1, part-2, etc. with --hive, or with
+                             a -1, -2, etc. suffix otherwise.
+    --format <arg>           The format of the partition files: csv or parquet.
+                             Parquet requires the polars feature and a file input.
+                             [default: csv]
 
 Common options:
     -h, --help               Display this message
     -n, --no-headers         When set, the first row will NOT be interpreted
                              as column names. Otherwise, the first row will
                              appear in all chunks as the header row.
+                             With --hive, the directories are then named after
+                             the 1-based column index.
     -d, --delimiter <arg>    The field delimiter for reading CSV data.
                              Must be a single character. (default: ,)
 "#;
 
 use std::{
-    collections::{hash_map::Entry, HashSet},
+    collections::HashSet,
+    fmt::Write as _,
     fs, io,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use ahash::AHashMap;
@@ -81,12 +124,42 @@ struct Args {
     flag_filename:      FilenameTemplate,
     flag_prefix_length: Option<usize>,
     flag_drop:          bool,
+    flag_hive:          bool,
+    flag_buckets:       Option<u64>,
+    flag_max_rows:      Option<u64>,
+    flag_format:        String,
     flag_no_headers:    bool,
     flag_delimiter:     Option<Delimiter>,
 }
 
+/// the directory name Hive uses for empty partition values
+const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
+    if args.flag_buckets == Some(0) {
+        return fail_incorrectusage_clierror!("--buckets must be greater than 0.");
+    }
+    if args.flag_max_rows == Some(0) {
+        return fail_incorrectusage_clierror!("--max-rows must be greater than 0.");
+    }
+    match args.flag_format.to_lowercase().as_str() {
+        "csv" => {},
+        "parquet" => {
+            if cfg!(not(feature = "polars")) {
+                return fail_incorrectusage_clierror!(
+                    "Parquet output requires the polars feature."
+                );
+            }
+            if args.arg_input.is_none() {
+                return fail_incorrectusage_clierror!("Parquet output does not support stdin.");
+            }
+            if args.flag_no_headers {
+                return fail_incorrectusage_clierror!("Parquet output requires a header row.");
+            }
+        },
+        format => return fail_incorrectusage_clierror!("Invalid --format: {format}"),
+    }
     fs::create_dir_all(&args.arg_outdir)?;
 
     // It would be nice to support efficient parallel partitions, but doing
@@ -96,6 +169,17 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     args.sequential_partition()
 }
 
+/// the output file of a partition that is currently being written
+struct Partition {
+    /// the filename value (flat mode) or the relative directory (--hive) of the partition
+    base: String,
+    /// the number of the current file, when the partition is rolled over with --max-rows
+    part: u64,
+    /// the number of rows written to the current file
+    rows: u64,
+    wtr:  Option<BoxedWriter>,
+}
+
 impl Args {
     /// Configuration for our reader.
     fn rconfig(&self) -> Config {
@@ -105,14 +189,83 @@ impl Args {
             .select(self.arg_column.clone())
     }
 
-    /// Get the column to use as a key.
-    #[allow(clippy::unused_self)]
-    fn key_column(&self, rconfig: &Config, headers: &csv::ByteRecord) -> CliResult<usize> {
+    /// Get the columns to use as a key.
+    fn key_columns(&self, rconfig: &Config, headers: &csv::ByteRecord) -> CliResult<Vec<usize>> {
         let select_cols = rconfig.selection(headers)?;
-        if select_cols.len() == 1 {
-            Ok(select_cols[0])
+        if select_cols.len() == 1 || self.flag_hive || self.flag_buckets.is_some() {
+            Ok(select_cols.to_vec())
+        } else {
+            fail!("can only partition on one column, unless --hive or --buckets is set")
+        }
+    }
+
+    /// Compute the partition key of `row` into `key`: one value per key column,
+    /// or the bucket number with --buckets.
+    fn partition_key(&self, row: &csv::ByteRecord, key_cols: &[usize], key: &mut Vec<Vec<u8>>) {
+        key.clear();
+        for &i in key_cols {
+            let column = row.get(i).unwrap_or_default();
+            key.push(
+                match self.flag_prefix_length {
+                    // We exceed --prefix-length, so ignore the extra bytes.
+                    Some(len) if len < column.len() => &column[0..len],
+                    _ => column,
+                }
+                .to_vec(),
+            );
+        }
+        if let Some(buckets) = self.flag_buckets {
+            let bucket = hash_key(key) % buckets;
+            key.clear();
+            key.push(bucket.to_string().into_bytes());
+        }
+    }
+
+    /// The relative Hive-style directory of a partition.
+    fn hive_dir(&self, headers: &csv::ByteRecord, key_cols: &[usize], key: &[Vec<u8>]) -> String {
+        let names: Vec<String> = if self.flag_buckets.is_some() {
+            vec!["bucket".to_string()]
         } else {
-            fail!("can only partition on one column")
+            key_cols
+                .iter()
+                .map(|&i| {
+                    if self.flag_no_headers {
+                        (i + 1).to_string()
+                    } else {
+                        hive_escape(&headers[i])
+                    }
+                })
+                .collect()
+        };
+        names
+            .iter()
+            .zip(key)
+            .map(|(name, value)| format!("{name}={}", hive_escape(value)))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Create the writer of the current file of a partition, returning its path.
+    fn partition_writer(&self, partition: &Partition) -> CliResult<(BoxedWriter, PathBuf)> {
+        let outdir = Path::new(&self.arg_outdir);
+        if self.flag_hive {
+            let path = outdir
+                .join(&partition.base)
+                .join(format!("part-{}.csv", partition.part));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let wtr = Config::new(Some(path.display().to_string()).as_ref()).writer()?;
+            Ok((wtr, path))
+        } else {
+            let unique_value = if partition.part == 0 {
+                partition.base.clone()
+            } else {
+                format!("{}-{}", partition.base, partition.part)
+            };
+            let path = outdir.join(self.flag_filename.filename(&unique_value));
+            let wtr = self.flag_filename.writer(outdir, &unique_value)?;
+            Ok((wtr, path))
         }
     }
 
@@ -121,53 +274,172 @@ impl Args {
         let rconfig = self.rconfig();
         let mut rdr = rconfig.reader()?;
         let headers = rdr.byte_headers()?.clone();
-        let key_col = self.key_column(&rconfig, &headers)?;
+        let key_cols = self.key_columns(&rconfig, &headers)?;
         let mut gen = WriterGenerator::new(self.flag_filename.clone());
 
-        let mut writers: AHashMap<Vec<u8>, BoxedWriter> = AHashMap::new();
+        let drop_cols = |record: &csv::ByteRecord| -> csv::ByteRecord {
+            record
+                .iter()
+                .enumerate()
+                .filter_map(|(i, e)| if key_cols.contains(&i) { None } else { Some(e) })
+                .collect()
+        };
+        let out_headers = if self.flag_drop {
+            drop_cols(&headers)
+        } else {
+            headers.clone()
+        };
+
+        let mut partitions: AHashMap<Vec<Vec<u8>>, Partition> = AHashMap::new();
+        // all the files written, in case they have to be converted to parquet
+        let mut files: Vec<PathBuf> = Vec::new();
+        let mut key: Vec<Vec<u8>> = Vec::with_capacity(key_cols.len());
         let mut row = csv::ByteRecord::new();
         while rdr.read_byte_record(&mut row)? {
             // Decide what file to put this in.
-            let column = &row[key_col];
-            let key = match self.flag_prefix_length {
-                // We exceed --prefix-length, so ignore the extra bytes.
-                Some(len) if len < column.len() => &column[0..len],
-                _ => column,
-            };
-            let mut entry = writers.entry(key.to_vec());
-            let wtr =
-                match entry {
-                    Entry::Occupied(ref mut occupied) => occupied.get_mut(),
-                    Entry::Vacant(vacant) => {
-                        // We have a new key, so make a new writer.
-                        let mut wtr = gen.writer(&*self.arg_outdir, key)?;
-                        if !rconfig.no_headers {
-                            if self.flag_drop {
-                                wtr.write_record(headers.iter().enumerate().filter_map(
-                                    |(i, e)| if i == key_col { None } else { Some(e) },
-                                ))?;
-                            } else {
-                                wtr.write_record(&headers)?;
-                            }
-                        }
-                        vacant.insert(wtr)
-                    },
+            self.partition_key(&row, &key_cols, &mut key);
+            if !partitions.contains_key(&key) {
+                let base = if self.flag_hive {
+                    self.hive_dir(&headers, &key_cols, &key)
+                } else {
+                    gen.unique_value(&key.concat())
                 };
+                partitions.insert(
+                    key.clone(),
+                    Partition {
+                        base,
+                        part: 0,
+                        rows: 0,
+                        wtr: None,
+                    },
+                );
+            }
+            // safety: the partition was inserted above if it didn't exist
+            let partition = partitions.get_mut(&key).unwrap();
+
+            let rollover = self
+                .flag_max_rows
+                .is_some_and(|max_rows| partition.rows >= max_rows);
+            if partition.wtr.is_none() || rollover {
+                if let Some(mut wtr) = partition.wtr.take() {
+                    wtr.flush()?;
+                    partition.part += 1;
+                }
+                partition.rows = 0;
+                // We have a new key or a full file, so make a new writer.
+                let (mut wtr, path) = self.partition_writer(partition)?;
+                if !rconfig.no_headers {
+                    wtr.write_byte_record(&out_headers)?;
+                }
+                partition.wtr = Some(wtr);
+                files.push(path);
+            }
+
+            // safety: the writer was created above if it didn't exist
+            let wtr = partition.wtr.as_mut().unwrap();
             if self.flag_drop {
-                wtr.write_record(row.iter().enumerate().filter_map(|(i, e)| {
-                    if i == key_col {
-                        None
-                    } else {
-                        Some(e)
-                    }
-                }))?;
+                wtr.write_byte_record(&drop_cols(&row))?;
             } else {
                 wtr.write_byte_record(&row)?;
             }
             wtr.flush()?;
+            partition.rows += 1;
+        }
+        // close all the files
+        drop(partitions);
+
+        if self.flag_format.eq_ignore_ascii_case("parquet") {
+            #[cfg(feature = "polars")]
+            {
+                let dropped: Vec<String> = if self.flag_drop {
+                    key_cols
+                        .iter()
+                        .map(|&i| String::from_utf8_lossy(&headers[i]).to_string())
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                self.write_parquet(&files, &dropped)?;
+            }
         }
         Ok(())
     }
+
+    /// Convert the partition CSV files to parquet, using the schema inferred from the input.
+    #[cfg(feature = "polars")]
+    fn write_parquet(&self, files: &[PathBuf], dropped: &[String]) -> CliResult<()> {
+        use std::sync::Arc;
+
+        use polars::prelude::*;
+
+        // safety: we checked that the input is a file when --format is parquet
+        let input = self.arg_input.as_ref().unwrap();
+        // the same delimiter the input was partitioned with, e.g. tabs for a .tsv input
+        let delim = self.rconfig().get_delimiter();
+        let input_schema = LazyCsvReader::new(input)
+            .with_has_header(true)
+            .with_separator(delim)
+            // scan the whole input, so no partition has values that don't fit its schema
+            .with_infer_schema_length(None)
+            .finish()?
+            .collect_schema()?;
+        let mut schema = Schema::with_capacity(input_schema.len());
+        for (name, dtype) in input_schema.iter() {
+            if !dropped.iter().any(|d| d == name.as_str()) {
+                schema.insert(name.clone(), dtype.clone());
+            }
+        }
+        let schema = Arc::new(schema);
+
+        for csv_path in files {
+            // read the partition back with the delimiter it was written with
+            let partition_delim =
+                Config::new(Some(csv_path.display().to_string()).as_ref()).get_delimiter();
+            let mut df = LazyCsvReader::new(csv_path)
+                .with_has_header(true)
+                .with_separator(partition_delim)
+                .with_schema(Some(schema.clone()))
+                .finish()?
+                .collect()?;
+            let parquet_file = fs::File::create(csv_path.with_extension("parquet"))?;
+            ParquetWriter::new(parquet_file).finish(&mut df)?;
+            fs::remove_file(csv_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// A stable FNV-1a hash of a partition key, so rows are assigned to the same
+/// bucket across runs and platforms.
+fn hash_key(key: &[Vec<u8>]) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+    let mut hash = FNV_OFFSET;
+    for field in key {
+        // 0xff never appears in UTF-8, so it separates the fields unambiguously
+        for &byte in field.iter().chain(&[0xff]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+    hash
+}
+
+/// Percent-encode a value for use in a Hive-style directory name.
+fn hive_escape(value: &[u8]) -> String {
+    if value.is_empty() {
+        return HIVE_DEFAULT_PARTITION.to_string();
+    }
+    let mut escaped = String::with_capacity(value.len());
+    for &byte in value {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.') {
+            escaped.push(byte as char);
+        } else {
+            // safety: writing to a String cannot fail
+            write!(escaped, "%{byte:02X}").unwrap();
+        }
+    }
+    escaped
 }
 
 type BoxedWriter = csv::Writer<Box<dyn io::Write + 'static>>;
@@ -190,15 +462,6 @@ impl WriterGenerator {
         }
     }
 
-    /// Create a CSV writer for `key`.  Does not add headers.
-    fn writer<P>(&mut self, path: P, key: &[u8]) -> io::Result<BoxedWriter>
-    where
-        P: AsRef<Path>,
-    {
-        let unique_value = self.unique_value(key);
-        self.template.writer(path.as_ref(), &unique_value)
-    }
-
     /// Generate a unique value for `key`, suitable for use in a
     /// "shell-safe" filename.  If you pass `key` twice, you'll get two
     /// different values.
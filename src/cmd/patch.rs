@@ -0,0 +1,326 @@
+static USAGE: &str = r#"
+Apply a diff result from `qsv diff` to a base CSV, producing the updated CSV.
+
+The patch is the output of `qsv diff <base> <updated>`, with its leading `diffresult`
+column. Rows marked "-" are deleted from the base and rows marked "+" are added. A "-" row
+immediately followed by a "+" row with the same key is a modification. Only the fields that
+differ between the "-" and the "+" rows are modified, so patches created with
+`qsv diff --drop-equal-fields` are supported.
+
+Rows are matched by their key columns, which must be the same as the ones used with
+`qsv diff`. The updated CSV has the rows of the base CSV in their original order, with
+modified rows updated in place and deleted rows removed. Added rows are appended at the
+end, in patch order.
+
+An operation fails when:
+  * the key of a deleted or modified row is not in the base CSV (or was already deleted)
+  * the key of an added row is already in the base CSV
+  * with --strict, a deleted row doesn't match the base row, or the old values of a
+    modified row don't match the base row
+Failed operations are skipped, and their count is reported on stderr. With --strict,
+patch aborts on the first failed operation without writing the updated CSV.
+
+Examples:
+
+  # create a patch with the changes between two versions, then apply it
+  $ qsv diff -k id old.csv new.csv -o changes.csv
+  $ qsv patch -k id old.csv changes.csv -o new.csv
+
+  # abort if the base CSV doesn't match the patch, and report the operations
+  $ qsv patch -k id --strict --report report.csv old.csv changes.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_patch.rs.
+
+Usage:
+    qsv patch [options] <base> <patch>
+    qsv patch --help
+
+patch arguments:
+    <base>                 The CSV to apply the patch to.
+    <patch>                The diff result of `qsv diff`.
+
+patch options:
+    -k, --key <arg>        The column indices that uniquely identify a record
+                           as a comma separated list of 0-based indices, e.g. 0,1,2
+                           or column names, e.g. name,age, as with `qsv diff`.
+                           The columns are those of the base CSV.
+                           (default: 0)
+    --strict               Check that deleted and modified rows match the base rows,
+                           and abort on the first failed operation.
+    --report <file>        Write a CSV report of the patch operations to <file>, with
+                           the patch row number, the operation (add, delete or modify),
+                           the key, the status (applied or failed) and the reason
+                           of the failure.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, neither the base CSV nor the patch have a header row.
+    -d, --delimiter <arg>  The field delimiter for reading the base CSV and the patch.
+                           Must be a single character. (default: ,)
+"#;
+
+use ahash::AHashMap;
+use csv::ByteRecord;
+use serde::Deserialize;
+
+use crate::{
+    cmd::diff::ColumnResolver,
+    config::{Config, Delimiter},
+    util, CliResult,
+};
+
+#[derive(Deserialize)]
+struct Args {
+    arg_base:        String,
+    arg_patch:       String,
+    flag_key:        Option<String>,
+    flag_strict:     bool,
+    flag_report:     Option<String>,
+    flag_output:     Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter:  Option<Delimiter>,
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Add,
+    Delete,
+    Modify,
+}
+
+impl Op {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Op::Add => "add",
+            Op::Delete => "delete",
+            Op::Modify => "modify",
+        }
+    }
+}
+
+type Key = Vec<Vec<u8>>;
+
+/// The base rows being patched.
+struct Patcher {
+    /// the base rows, followed by the added rows. Deleted rows are None.
+    rows:     Vec<Option<ByteRecord>>,
+    /// the indices of the rows of each key
+    index:    AHashMap<Key, Vec<usize>>,
+    key_cols: Vec<usize>,
+    strict:   bool,
+}
+
+impl Patcher {
+    fn key(&self, record: &ByteRecord, offset: usize) -> Key {
+        self.key_cols
+            .iter()
+            .map(|&i| record.get(i + offset).unwrap_or_default().to_vec())
+            .collect()
+    }
+
+    fn insert(&mut self, record: ByteRecord) {
+        let key = self.key(&record, 0);
+        self.index.entry(key).or_default().push(self.rows.len());
+        self.rows.push(Some(record));
+    }
+
+    /// The index of the first row with `key` that wasn't deleted.
+    fn find(&self, key: &Key) -> Option<usize> {
+        self.index
+            .get(key)?
+            .iter()
+            .copied()
+            .find(|&i| self.rows[i].is_some())
+    }
+
+    fn add(&mut self, key: &Key, new: ByteRecord) -> Result<(), String> {
+        if self.find(key).is_some() {
+            return Err("key already exists".to_string());
+        }
+        self.insert(new);
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &Key, old: &ByteRecord) -> Result<(), String> {
+        let Some(i) = self.find(key) else {
+            return Err("key not found".to_string());
+        };
+        if self.strict
+            && self.rows[i]
+                .as_ref()
+                .is_some_and(|base| !base.iter().eq(old))
+        {
+            return Err("row does not match the base row".to_string());
+        }
+        self.rows[i] = None;
+        Ok(())
+    }
+
+    fn modify(&mut self, key: &Key, old: &ByteRecord, new: &ByteRecord) -> Result<(), String> {
+        let Some(i) = self.find(key) else {
+            return Err("key not found".to_string());
+        };
+        // safety: find only returns rows that weren't deleted
+        let base = self.rows[i].as_ref().unwrap();
+        let mut updated = ByteRecord::with_capacity(base.as_slice().len(), base.len());
+        for col in 0..base.len().max(new.len()) {
+            let base_value = base.get(col).unwrap_or_default();
+            let old_value = old.get(col).unwrap_or_default();
+            let new_value = new.get(col).unwrap_or_default();
+            // equal fields may have been dropped with --drop-equal-fields,
+            // so only a non-empty old value has to match the base
+            if self.strict
+                && (old_value != new_value || !old_value.is_empty())
+                && base_value != old_value
+            {
+                return Err(format!("column {col} does not match the base row"));
+            }
+            updated.push_field(if old_value == new_value {
+                base_value
+            } else {
+                new_value
+            });
+        }
+        self.rows[i] = Some(updated);
+        Ok(())
+    }
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let base_config = Config::new(Some(&args.arg_base))
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+    let patch_config = Config::new(Some(&args.arg_patch))
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+
+    let mut base_rdr = base_config.reader()?;
+    let base_headers = base_rdr.byte_headers()?.clone();
+    let key_cols = resolve_key(args.flag_key.as_deref(), &base_headers)?;
+
+    let mut patcher = Patcher {
+        rows: Vec::new(),
+        index: AHashMap::new(),
+        key_cols,
+        strict: args.flag_strict,
+    };
+    for record in base_rdr.into_byte_records() {
+        patcher.insert(record?);
+    }
+
+    let mut patch_rdr = patch_config.reader()?;
+    if !args.flag_no_headers && patch_rdr.byte_headers()?.get(0) != Some(&b"diffresult"[..]) {
+        return fail_incorrectusage_clierror!(
+            "{} is not a diff result. Its first column must be diffresult.",
+            args.arg_patch
+        );
+    }
+
+    let mut report_wtr = match args.flag_report {
+        Some(ref report) => {
+            let mut wtr = Config::new(Some(report)).writer()?;
+            wtr.write_record(["row", "op", "key", "status", "reason"])?;
+            Some(wtr)
+        },
+        None => None,
+    };
+
+    let mut records = patch_rdr.into_byte_records().peekable();
+    let mut rowno = 0_u64;
+    let (mut applied, mut failed) = (0_u64, 0_u64);
+    while let Some(record) = records.next() {
+        let record = record?;
+        rowno += 1;
+        let op_rowno = rowno;
+        let key = patcher.key(&record, 1);
+        let values: ByteRecord = record.iter().skip(1).collect();
+        let (op, result) = match record.get(0) {
+            Some(b"-") => {
+                // a "-" row followed by a "+" row with the same key is a modification
+                let is_modify = matches!(
+                    records.peek(),
+                    Some(Ok(next)) if next.get(0) == Some(&b"+"[..]) && patcher.key(next, 1) == key
+                );
+                if is_modify {
+                    // safety: we peeked the next record above
+                    let next = records.next().unwrap()?;
+                    rowno += 1;
+                    let new_values: ByteRecord = next.iter().skip(1).collect();
+                    (Op::Modify, patcher.modify(&key, &values, &new_values))
+                } else {
+                    (Op::Delete, patcher.delete(&key, &values))
+                }
+            },
+            Some(b"+") => (Op::Add, patcher.add(&key, values)),
+            _ => {
+                return fail_clierror!(
+                    "Invalid diffresult in patch row {rowno}. Expected \"+\" or \"-\"."
+                );
+            },
+        };
+
+        let key_str = String::from_utf8_lossy(&key.join(&b","[..])).to_string();
+        if let Err(ref reason) = result {
+            if args.flag_strict {
+                return fail_clierror!(
+                    "Patch row {op_rowno}: cannot {} key {key_str}: {reason}",
+                    op.as_str()
+                );
+            }
+            failed += 1;
+        } else {
+            applied += 1;
+        }
+        if let Some(ref mut wtr) = report_wtr {
+            let (status, reason) = match result {
+                Ok(()) => ("applied", String::new()),
+                Err(reason) => ("failed", reason),
+            };
+            wtr.write_record([
+                op_rowno.to_string().as_str(),
+                op.as_str(),
+                key_str.as_str(),
+                status,
+                reason.as_str(),
+            ])?;
+        }
+    }
+    if let Some(mut wtr) = report_wtr {
+        wtr.flush()?;
+    }
+    log::info!("{applied} patch operations applied, {failed} failed.");
+    if failed > 0 {
+        eprintln!("{failed} of {} patch operations failed.", applied + failed);
+    }
+
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+    if !args.flag_no_headers {
+        wtr.write_byte_record(&base_headers)?;
+    }
+    for row in patcher.rows.iter().flatten() {
+        wtr.write_byte_record(row)?;
+    }
+    Ok(wtr.flush()?)
+}
+
+/// Resolve the key columns with the same rules as `qsv diff`: 0-based indices, or column names.
+fn resolve_key(key: Option<&str>, headers: &ByteRecord) -> CliResult<Vec<usize>> {
+    let Some(key) = key else {
+        return Ok(vec![0]);
+    };
+    let columns = ColumnResolver {
+        headers_left:  headers,
+        headers_right: headers,
+        check_right:   false,
+    };
+    let key_cols = columns.resolve_list(key)?;
+    if let Some(col) = key_cols.iter().find(|&&col| col >= headers.len()) {
+        return fail_incorrectusage_clierror!(
+            "Key column index {col} is out of range. The base CSV has {} columns.",
+            headers.len()
+        );
+    }
+    Ok(key_cols)
+}
@@ -0,0 +1,209 @@
+static USAGE: &str = r#"
+Pivots CSV data from long to wide format using the Pola.rs engine.
+
+The distinct values of the <on-cols> column/s become new columns, with one row per
+distinct combination of the --index column/s. The cells are filled with the
+--values column/s, aggregated with --agg when several rows map to the same cell.
+
+See `unpivot` to reshape wide data back into long format.
+
+Examples:
+
+  # one row per date, one column per sensor, filled with the reading
+  $ qsv pivotp sensor --index date --values reading data.csv
+
+  # total sales per region, with one column per product
+  $ qsv pivotp product --index region --values amount --agg sum sales.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_pivotp.rs.
+
+Usage:
+    qsv pivotp [options] <on-cols> <input>
+    qsv pivotp --help
+
+pivotp arguments:
+    <on-cols>               The column/s whose values become the new column names.
+                            Columns are referenced by name. Specify multiple columns
+                            by separating them with a comma.
+    <input>                 The input CSV file. Stdin is not supported.
+
+pivotp options:
+    -i, --index <cols>      The column/s to use as the row index of the pivot table.
+                            If not set, all the columns that are not in <on-cols>
+                            or --values are used.
+    -v, --values <cols>     The column/s whose values fill the pivot table.
+                            If not set, all the columns that are not in <on-cols>
+                            or --index are used.
+    -a, --agg <func>        The aggregation function to use when several rows
+                            map to the same cell. One of:
+                              none, first, last, sum, min, max, mean, median, count, len
+                            With "none", it is an error if several rows map to the same cell.
+                            [default: none]
+    --sort-columns          Sort the new columns by their names. Otherwise, they are
+                            in order of first appearance.
+    --col-separator <arg>   The separator used when generating column names
+                            with several --values columns.
+                            [default: _]
+    --try-parsedates        When set, will attempt to parse columns as dates.
+    --infer-len <arg>       The number of rows to scan when inferring the schema of the CSV.
+                            Set to 0 to do a full table scan (warning: very slow).
+                            [default: 10000]
+    --decimal-comma         Use comma as the decimal separator when parsing CSVs.
+    --ignore-errors         Skip rows that can't be parsed.
+
+Common options:
+    -h, --help              Display this message
+    -o, --output <file>     Write output to <file> instead of stdout.
+    -d, --delimiter <arg>   The field delimiter for reading/writing CSV data.
+                            Must be a single character. (default: ,)
+    -Q, --quiet             Do not return the pivot table shape to stderr.
+"#;
+
+use std::{fs::File, io, io::Write, path::Path};
+
+use polars::prelude::{pivot::pivot_stable, *};
+use serde::Deserialize;
+
+use crate::{cmd::joinp::tsvssv_delim, config::Delimiter, util, CliResult};
+
+#[derive(Deserialize)]
+struct Args {
+    arg_on_cols:         String,
+    arg_input:           String,
+    flag_index:          Option<String>,
+    flag_values:         Option<String>,
+    flag_agg:            String,
+    flag_sort_columns:   bool,
+    flag_col_separator:  String,
+    flag_try_parsedates: bool,
+    flag_infer_len:      usize,
+    flag_decimal_comma:  bool,
+    flag_ignore_errors:  bool,
+    flag_output:         Option<String>,
+    flag_delimiter:      Option<Delimiter>,
+    flag_quiet:          bool,
+}
+
+/// split a comma-separated list of column names
+fn column_names(cols: &str) -> Vec<String> {
+    cols.split(',')
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+
+    let input_path = Path::new(&args.arg_input);
+    if !input_path.exists() {
+        return fail_clierror!("Input file {} does not exist.", args.arg_input);
+    }
+
+    let delim = args.flag_delimiter.map_or(b',', Delimiter::as_byte);
+
+    let agg_expr = match args.flag_agg.to_lowercase().as_str() {
+        "none" => None,
+        // the pivot aggregation is applied to the unnamed "element" column
+        "first" => Some(col("").first()),
+        "last" => Some(col("").last()),
+        "sum" => Some(col("").sum()),
+        "min" => Some(col("").min()),
+        "max" => Some(col("").max()),
+        "mean" => Some(col("").mean()),
+        "median" => Some(col("").median()),
+        "count" => Some(col("").count()),
+        "len" => Some(col("").len()),
+        agg => return fail_incorrectusage_clierror!("Invalid pivot aggregation: {agg}"),
+    };
+
+    let df = LazyCsvReader::new(&args.arg_input)
+        .with_has_header(true)
+        .with_separator(tsvssv_delim(&args.arg_input, delim))
+        .with_try_parse_dates(args.flag_try_parsedates)
+        .with_decimal_comma(args.flag_decimal_comma)
+        .with_ignore_errors(args.flag_ignore_errors)
+        .with_infer_schema_length(if args.flag_infer_len == 0 {
+            None
+        } else {
+            Some(args.flag_infer_len)
+        })
+        .finish()?
+        .collect()?;
+
+    let on_cols = column_names(&args.arg_on_cols);
+    if on_cols.is_empty() {
+        return fail_incorrectusage_clierror!("At least one <on-cols> column is required.");
+    }
+    let all_cols: Vec<String> = df
+        .get_column_names()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    // derive the index and values columns from each other if they're not set
+    let (index_cols, value_cols) = match (&args.flag_index, &args.flag_values) {
+        (Some(index), Some(values)) => (column_names(index), column_names(values)),
+        (Some(index), None) => {
+            let index = column_names(index);
+            let values = all_cols
+                .iter()
+                .filter(|c| !on_cols.contains(c) && !index.contains(c))
+                .cloned()
+                .collect();
+            (index, values)
+        },
+        (None, Some(values)) => {
+            let values = column_names(values);
+            let index = all_cols
+                .iter()
+                .filter(|c| !on_cols.contains(c) && !values.contains(c))
+                .cloned()
+                .collect();
+            (index, values)
+        },
+        (None, None) => {
+            return fail_incorrectusage_clierror!(
+                "At least one of --index or --values is required."
+            )
+        },
+    };
+    for c in on_cols.iter().chain(&index_cols).chain(&value_cols) {
+        if !all_cols.contains(c) {
+            return fail_incorrectusage_clierror!("Column '{c}' not found in {}", args.arg_input);
+        }
+    }
+    if index_cols.is_empty() || value_cols.is_empty() {
+        return fail_incorrectusage_clierror!("The pivot table needs index and values columns.");
+    }
+
+    let mut pivot_df = pivot_stable(
+        &df,
+        on_cols,
+        Some(index_cols),
+        Some(value_cols),
+        args.flag_sort_columns,
+        agg_expr,
+        Some(args.flag_col_separator.as_str()),
+    )?;
+
+    let (mut out_writer, out_delim) = match args.flag_output {
+        Some(ref output_file) => (
+            // no need to use buffered writer here, as CsvWriter already does that
+            Box::new(File::create(output_file)?) as Box<dyn Write>,
+            tsvssv_delim(output_file, delim),
+        ),
+        None => (Box::new(io::stdout()) as Box<dyn Write>, delim),
+    };
+
+    CsvWriter::new(&mut out_writer)
+        .include_header(true)
+        .with_separator(out_delim)
+        .include_bom(util::get_envvar_flag("QSV_OUTPUT_BOM"))
+        .finish(&mut pivot_df)?;
+
+    if !args.flag_quiet {
+        eprintln!("{:?}", pivot_df.shape());
+    }
+
+    Ok(())
+}
@@ -11,6 +11,8 @@ backslash or by wrapping the replacement string into single quotes:
 Returns exitcode 0 when replacements are done, returning number of replacements to stderr.
 Returns exitcode 1 when no replacements are done.
 
+If the CSV is indexed, it's processed in parallel chunks (see --jobs).
+
 For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_replace.rs.
 
 Usage:
@@ -42,6 +44,10 @@ replace options:
     --dfa-size-limit <mb>  Set the approximate size of the cache (MB) used by the regular
                            expression engine's Discrete Finite Automata.
                            [default: 10]
+    -j, --jobs <arg>       The number of jobs to run in parallel when the CSV is indexed.
+                           The output is in the same order as the input.
+                           When not set, the number of jobs is set to the
+                           number of CPUs detected.
 
 Common options:
     -h, --help             Display this message
@@ -84,12 +90,22 @@ struct Args {
     flag_literal:        bool,
     flag_size_limit:     usize,
     flag_dfa_size_limit: usize,
+    flag_jobs:           Option<usize>,
     flag_progressbar:    bool,
     flag_quiet:          bool,
 }
 
 const NULL_VALUE: &str = "<null>";
 
+/// The result of replacing the matches of a chunk of an indexed CSV
+#[cfg_attr(feature = "datapusher_plus", allow(dead_code))]
+#[derive(Default)]
+struct ReplaceChunk {
+    records:           Vec<csv::ByteRecord>,
+    matches:           u64,
+    rows_with_matches: u64,
+}
+
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
 
@@ -155,43 +171,99 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     #[cfg(any(feature = "feature_capable", feature = "lite"))]
     let mut match_found;
 
-    while rdr.read_byte_record(&mut record)? {
-        #[cfg(any(feature = "feature_capable", feature = "lite"))]
-        if show_progress {
-            progress.inc(1);
-        }
+    let njobs = util::njobs(args.flag_jobs);
+    let parallel_count = match rconfig.indexed()? {
+        Some(idx) if njobs > 1 => Some(idx.count()),
+        _ => None,
+    };
 
-        #[cfg(any(feature = "feature_capable", feature = "lite"))]
-        {
-            match_found = false;
-        }
-        record = record
-            .into_iter()
-            .enumerate()
-            .map(|(i, v)| {
-                if sel_indices.contains(&i) {
-                    if pattern.is_match(v) {
-                        total_match_ctr += 1;
-                        #[cfg(any(feature = "feature_capable", feature = "lite"))]
-                        {
-                            match_found = true;
+    if let Some(idx_count) = parallel_count {
+        let pattern = pattern.clone();
+        let replacement = replacement.to_vec();
+        let sel_indices: HashSet<usize> = sel.iter().copied().collect();
+        util::process_indexed_chunks(
+            &rconfig,
+            idx_count,
+            njobs,
+            move |_, records| {
+                let mut chunk = ReplaceChunk::default();
+                for record in records {
+                    let record = record?;
+                    let mut match_found = false;
+                    let record: csv::ByteRecord = record
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, v)| {
+                            if sel_indices.contains(&i) && pattern.is_match(v) {
+                                chunk.matches += 1;
+                                match_found = true;
+                                pattern.replace_all(v, &replacement[..])
+                            } else {
+                                Cow::Borrowed(v)
+                            }
+                        })
+                        .collect();
+                    if match_found {
+                        chunk.rows_with_matches += 1;
+                    }
+                    chunk.records.push(record);
+                }
+                Ok(chunk)
+            },
+            |chunk| {
+                #[cfg(any(feature = "feature_capable", feature = "lite"))]
+                {
+                    if show_progress {
+                        progress.inc(chunk.records.len() as u64);
+                    }
+                    rows_with_matches_ctr += chunk.rows_with_matches;
+                }
+                total_match_ctr += chunk.matches;
+                for record in &chunk.records {
+                    wtr.write_byte_record(record)?;
+                }
+                Ok(true)
+            },
+        )?;
+    } else {
+        while rdr.read_byte_record(&mut record)? {
+            #[cfg(any(feature = "feature_capable", feature = "lite"))]
+            if show_progress {
+                progress.inc(1);
+            }
+
+            #[cfg(any(feature = "feature_capable", feature = "lite"))]
+            {
+                match_found = false;
+            }
+            record = record
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    if sel_indices.contains(&i) {
+                        if pattern.is_match(v) {
+                            total_match_ctr += 1;
+                            #[cfg(any(feature = "feature_capable", feature = "lite"))]
+                            {
+                                match_found = true;
+                            }
+                            pattern.replace_all(v, replacement)
+                        } else {
+                            Cow::Borrowed(v)
                         }
-                        pattern.replace_all(v, replacement)
                     } else {
                         Cow::Borrowed(v)
                     }
-                } else {
-                    Cow::Borrowed(v)
-                }
-            })
-            .collect();
+                })
+                .collect();
 
-        #[cfg(any(feature = "feature_capable", feature = "lite"))]
-        if match_found {
-            rows_with_matches_ctr += 1;
-        }
+            #[cfg(any(feature = "feature_capable", feature = "lite"))]
+            if match_found {
+                rows_with_matches_ctr += 1;
+            }
 
-        wtr.write_byte_record(&record)?;
+            wtr.write_byte_record(&record)?;
+        }
     }
 
     wtr.flush()?;
@@ -0,0 +1,337 @@
+static USAGE: &str = r#"
+Resample time series CSV data into fixed intervals, optionally per group.
+
+The timestamps in the <column> column are parsed (19 formats recognized, see `datefmt`)
+and each row is assigned to the interval it falls in. Intervals are aligned to the
+Unix epoch in UTC, so 1d intervals start at midnight UTC, 1h intervals at the top of
+the hour, and so on. Timestamps without a timezone are assumed to be in UTC. Rows with
+empty or unrecognized timestamps are skipped.
+
+The rows in each interval are aggregated with the same aggregate functions as `groupby`
+(count, count_distinct, sum, mean, min, max, first & last), where first and last are
+in input order.
+
+Missing intervals between the first and the last interval of each group are filled
+according to --fill:
+
+    null      The aggregates are empty (counts are 0).
+    previous  The aggregates of the previous interval are carried forward.
+    linear    Numeric aggregates are linearly interpolated between the intervals
+              before and after the gap. Non-numeric aggregates are empty.
+
+Counts are always 0 for missing intervals, whatever the --fill method.
+
+The output has one row per group and interval, sorted by the group key and interval,
+with the group columns, the interval start timestamp and one column per aggregate.
+
+Examples:
+
+  # the average temperature of each sensor, every 15 minutes
+  $ qsv resample timestamp 15m --group sensor_id --agg mean:temp readings.csv
+
+  # daily number of trips and total fare, interpolating the missing days
+  $ qsv resample pickup_datetime 1d -a "count,sum:fare_amount" --fill linear taxi.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_resample.rs.
+
+Usage:
+    qsv resample [options] <column> <interval> [<input>]
+    qsv resample --help
+
+resample arguments:
+    <column>                 The column with the timestamps to resample.
+    <interval>               The length of the intervals, as a positive integer followed by
+                             a unit: s (seconds), m (minutes), h (hours), d (days) or
+                             w (weeks). e.g. 30s, 15m, 1h, 1d.
+    <input>                  The CSV file to read from. If not specified, then
+                             the input will be read from stdin.
+
+resample options:
+    -g, --group <cols>       The column/s to group by. Each group is resampled separately.
+                             See 'qsv select --help' for the format details.
+    -a, --agg <aggs>         A comma-separated list of aggregates to compute for each
+                             interval, in the form <function>[:<column>].
+                             See 'qsv groupby --help' for the aggregate functions.
+                             [default: count]
+    --fill <method>          How to fill missing intervals. One of null, previous or linear.
+                             [default: null]
+    --formatstr <string>     The strftime format of the interval start timestamps.
+                             [default: %Y-%m-%dT%H:%M:%SZ]
+    --prefer-dmy             Prefer to parse dates in dmy format. Otherwise, use mdy format.
+    --round <places>         Round means and interpolated values to <places> decimal places.
+                             [default: 4]
+
+Common options:
+    -h, --help               Display this message
+    -o, --output <file>      Write output to <file> instead of stdout.
+    -n, --no-headers         When set, the first row will not be interpreted
+                             as headers. Columns are then referred to by their
+                             1-based index and no header row is written.
+    -d, --delimiter <arg>    The field delimiter for reading CSV data.
+                             Must be a single character. (default: ,)
+"#;
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use qsv_dateparser::parse_with_preference_and_timezone;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    cmd::groupby::{parse_aggs, parse_decimal, AggFunc, AggSpec, AggState},
+    config::{Config, Delimiter},
+    select::SelectColumns,
+    util, CliResult,
+};
+
+#[derive(Deserialize)]
+struct Args {
+    arg_column:      SelectColumns,
+    arg_interval:    String,
+    arg_input:       Option<String>,
+    flag_group:      Option<SelectColumns>,
+    flag_agg:        String,
+    flag_fill:       String,
+    flag_formatstr:  String,
+    flag_prefer_dmy: bool,
+    flag_round:      u32,
+    flag_output:     Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter:  Option<Delimiter>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Fill {
+    Null,
+    Previous,
+    Linear,
+}
+
+type GroupKey = Vec<Vec<u8>>;
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(args.arg_input.as_ref())
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+
+    let interval = parse_interval(&args.arg_interval)?;
+    let fill = match args.flag_fill.to_lowercase().as_str() {
+        "null" => Fill::Null,
+        "previous" => Fill::Previous,
+        "linear" => Fill::Linear,
+        fill => return fail_incorrectusage_clierror!("Invalid --fill method: {fill}"),
+    };
+    let prefer_dmy = args.flag_prefer_dmy || rconfig.get_dmy_preference();
+
+    let mut rdr = rconfig.reader()?;
+    let headers = rdr.byte_headers()?.clone();
+    let use_names = !args.flag_no_headers;
+
+    let ts_sel = args.arg_column.selection(&headers, use_names)?;
+    if ts_sel.len() != 1 {
+        return fail_incorrectusage_clierror!("<column> must select exactly one column.");
+    }
+    let ts_col = ts_sel[0];
+    let group_sel: Vec<usize> = match args.flag_group {
+        Some(ref group) => group.selection(&headers, use_names)?.to_vec(),
+        None => vec![],
+    };
+    let specs = parse_aggs(&args.flag_agg, &headers, use_names)?;
+
+    // the aggregates of each interval of each group, keyed by the interval start timestamp
+    let mut groups: BTreeMap<GroupKey, BTreeMap<i64, Vec<AggState>>> = BTreeMap::new();
+    let mut key: GroupKey = Vec::with_capacity(group_sel.len());
+    let mut record = csv::ByteRecord::new();
+    let mut rowno = 0_u64;
+    let mut skipped = 0_u64;
+    while rdr.read_byte_record(&mut record)? {
+        rowno += 1;
+        let Some(ts) = parse_timestamp(record.get(ts_col).unwrap_or_default(), prefer_dmy) else {
+            skipped += 1;
+            continue;
+        };
+        let start = ts.div_euclid(interval) * interval;
+
+        key.clear();
+        key.extend(
+            group_sel
+                .iter()
+                .map(|&i| record.get(i).unwrap_or_default().to_vec()),
+        );
+        if !groups.contains_key(&key) {
+            groups.insert(key.clone(), BTreeMap::new());
+        }
+        // safety: the group was inserted above if it didn't exist
+        let states = groups
+            .get_mut(&key)
+            .unwrap()
+            .entry(start)
+            .or_insert_with(|| specs.iter().map(|s| AggState::new(s.func)).collect());
+        for (state, spec) in states.iter_mut().zip(&specs) {
+            state.update(spec.col.map(|c| record.get(c).unwrap_or_default()), rowno)?;
+        }
+    }
+    if skipped > 0 {
+        log::warn!("{skipped} rows skipped due to empty or unrecognized timestamps.");
+    }
+
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+    if use_names {
+        let mut out_headers: csv::ByteRecord = group_sel.iter().map(|&i| &headers[i]).collect();
+        out_headers.push_field(&headers[ts_col]);
+        for spec in &specs {
+            out_headers.push_field(spec.name.as_bytes());
+        }
+        wtr.write_byte_record(&out_headers)?;
+    }
+
+    let round = args.flag_round;
+    let empty_values: Vec<Vec<u8>> = specs
+        .iter()
+        .map(|s| AggState::new(s.func).output(round))
+        .collect();
+    let mut out_record = csv::ByteRecord::new();
+    let mut gap_values = empty_values.clone();
+    for (key, buckets) in groups {
+        // the missing intervals are written as they're filled, without materializing
+        // every interval of the group, as a small interval over a wide range has many
+        let mut prev: Option<(i64, Vec<Vec<u8>>)> = None;
+        for (start, states) in buckets {
+            let values: Vec<Vec<u8>> = states.iter().map(|s| s.output(round)).collect();
+            if let Some((prev_start, ref prev_values)) = prev {
+                let steps = (start - prev_start) / interval;
+                for step in 1..steps {
+                    gap_values.clone_from(&empty_values);
+                    fill_gap(
+                        &mut gap_values,
+                        prev_values,
+                        &values,
+                        step,
+                        steps,
+                        &specs,
+                        fill,
+                        round,
+                    );
+                    write_interval(
+                        &mut wtr,
+                        &mut out_record,
+                        &key,
+                        prev_start + step * interval,
+                        &args.flag_formatstr,
+                        &gap_values,
+                    )?;
+                }
+            }
+            write_interval(
+                &mut wtr,
+                &mut out_record,
+                &key,
+                start,
+                &args.flag_formatstr,
+                &values,
+            )?;
+            prev = Some((start, values));
+        }
+    }
+
+    Ok(wtr.flush()?)
+}
+
+/// parse an interval like 15m or 1d into seconds
+fn parse_interval(interval: &str) -> CliResult<i64> {
+    let interval = interval.trim().to_lowercase();
+    let unit_start = interval
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(interval.len());
+    let (num, unit) = interval.split_at(unit_start);
+    let unit_secs = match unit.trim() {
+        "s" | "sec" | "secs" => 1,
+        "m" | "min" | "mins" => 60,
+        "h" | "hr" | "hour" | "hours" => 3_600,
+        "d" | "day" | "days" => 86_400,
+        "w" | "week" | "weeks" => 604_800,
+        _ => return fail_incorrectusage_clierror!("Invalid interval unit: {interval}"),
+    };
+    match num
+        .parse::<i64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit_secs))
+    {
+        Some(secs) if secs > 0 => Ok(secs),
+        _ => fail_incorrectusage_clierror!("Invalid interval: {interval}"),
+    }
+}
+
+/// parse a timestamp into seconds since the Unix epoch
+#[inline]
+fn parse_timestamp(field: &[u8], prefer_dmy: bool) -> Option<i64> {
+    let s = simdutf8::basic::from_utf8(field).ok()?.trim();
+    if s.is_empty() {
+        return None;
+    }
+    parse_with_preference_and_timezone(s, prefer_dmy, &Utc)
+        .ok()
+        .map(|dt| dt.timestamp())
+}
+
+/// fill the aggregates of the `step`th of the `steps - 1` missing intervals between the
+/// intervals with the `prev` and `next` aggregates. Counts are left as 0.
+#[allow(clippy::too_many_arguments)]
+fn fill_gap(
+    gap: &mut [Vec<u8>],
+    prev: &[Vec<u8>],
+    next: &[Vec<u8>],
+    step: i64,
+    steps: i64,
+    specs: &[AggSpec],
+    fill: Fill,
+    round: u32,
+) {
+    for (j, spec) in specs.iter().enumerate() {
+        if matches!(spec.func, AggFunc::Count | AggFunc::CountDistinct) {
+            continue;
+        }
+        match fill {
+            Fill::Null => {},
+            Fill::Previous => gap[j].clone_from(&prev[j]),
+            Fill::Linear => {
+                let (Some(from), Some(to)) = (parse_decimal(&prev[j]), parse_decimal(&next[j]))
+                else {
+                    continue;
+                };
+                gap[j] = to
+                    .checked_sub(from)
+                    .and_then(|d| d.checked_mul(Decimal::from(step)))
+                    .and_then(|d| d.checked_div(Decimal::from(steps)))
+                    .and_then(|d| from.checked_add(d))
+                    .map(|d| d.round_dp(round).normalize().to_string().into_bytes())
+                    .unwrap_or_default();
+            },
+        }
+    }
+}
+
+/// write the aggregates of the interval starting at `start` of the group `key`
+fn write_interval<W: std::io::Write>(
+    wtr: &mut csv::Writer<W>,
+    out_record: &mut csv::ByteRecord,
+    key: &GroupKey,
+    start: i64,
+    formatstr: &str,
+    values: &[Vec<u8>],
+) -> CliResult<()> {
+    out_record.clear();
+    for field in key {
+        out_record.push_field(field);
+    }
+    let formatted = DateTime::from_timestamp(start, 0)
+        .map(|dt| dt.format(formatstr).to_string())
+        .unwrap_or_default();
+    out_record.push_field(formatted.as_bytes());
+    for value in values {
+        out_record.push_field(value);
+    }
+    Ok(wtr.write_byte_record(out_record)?)
+}
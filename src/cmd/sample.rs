@@ -16,7 +16,41 @@ This command is intended to provide a means to sample from a CSV data set that
 is too big to fit into memory (for example, for use with commands like
 'qsv stats' with the '--everything' option). 
 
-For examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_sample.rs.
+Besides uniform sampling, the following sampling modes are supported. Only one mode
+can be used at a time, and all of them honor the --seed and --rng options. Sampled rows
+are output in their input order. The modes marked with * need a row count or read the
+input twice, so they do not support stdin. This is also the case for --stratified and
+--weighted when <sample-size> is a percentage.
+
+  --stratified <cols>   Stratified sampling. Samples <sample-size> records from each
+                        group of the <cols> column/s (or a percentage of each group).
+  --weighted <col>      Weighted sampling without replacement, where the probability
+                        of sampling a record is proportional to the numeric weight in
+                        <col> (A-Res algorithm). Records with empty, non-numeric, zero
+                        or negative weights are never sampled.
+  --systematic          * Systematic sampling. Samples every k-th record starting from
+                        a random record, where k is the row count divided by <sample-size>.
+  --cluster <cols>      * Cluster sampling. Samples <sample-size> of the distinct values
+                        (clusters) of the <cols> column/s, and outputs ALL the records
+                        of the sampled clusters.
+  --bootstrap           * Bootstrap sampling. Samples <sample-size> records with
+                        replacement, so a record can be sampled several times.
+
+Examples:
+
+  # sample 10 records for each state
+  $ qsv sample 10 --stratified state data.csv
+
+  # sample 20 percent of the records of each state and gender
+  $ qsv sample 0.2 --stratified state,gender data.csv
+
+  # sample 1000 records, with probabilities proportional to their population
+  $ qsv sample 1000 --weighted population data.csv
+
+  # sample all the records of 5 random schools
+  $ qsv sample 5 --cluster school_id --seed 42 data.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_sample.rs.
 
 Usage:
     qsv sample [options] <sample-size> [<input>]
@@ -35,6 +69,11 @@ sample arguments:
                            visiting every record in the CSV.
 
 sample options:
+    --stratified <cols>    Sample <sample-size> records from each group of <cols>.
+    --weighted <col>       Sample records with probabilities proportional to <col>.
+    --systematic           Sample every k-th record starting from a random record.
+    --cluster <cols>       Sample <sample-size> clusters of <cols> and output all their records.
+    --bootstrap            Sample <sample-size> records with replacement.
     --seed <number>        Random Number Generator (RNG) seed.
     --rng <kind>           The RNG algorithm to use.
                            Three RNGs are supported:
@@ -71,9 +110,14 @@ Common options:
                            Must be a single character. (default: ,)
 "#;
 
-use std::{io, str::FromStr};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashMap, HashSet},
+    io,
+    str::FromStr,
+};
 
-use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
 use rand_hc::Hc128Rng;
 use rand_xoshiro::Xoshiro256Plus;
 use serde::Deserialize;
@@ -83,6 +127,7 @@ use url::Url;
 
 use crate::{
     config::{Config, Delimiter},
+    select::SelectColumns,
     util, CliResult,
 };
 
@@ -93,6 +138,11 @@ struct Args {
     flag_output:     Option<String>,
     flag_no_headers: bool,
     flag_delimiter:  Option<Delimiter>,
+    flag_stratified: Option<SelectColumns>,
+    flag_weighted:   Option<SelectColumns>,
+    flag_systematic: bool,
+    flag_cluster:    Option<SelectColumns>,
+    flag_bootstrap:  bool,
     flag_seed:       Option<u64>,
     flag_rng:        String,
     flag_user_agent: Option<String>,
@@ -102,7 +152,7 @@ struct Args {
 
 #[derive(Debug, EnumString, PartialEq)]
 #[strum(ascii_case_insensitive)]
-enum RngKind {
+pub(crate) enum RngKind {
     Standard,
     Faster,
     Cryptosecure,
@@ -157,6 +207,43 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         .delimiter(args.flag_delimiter)
         .writer()?;
 
+    let num_modes = [
+        args.flag_stratified.is_some(),
+        args.flag_weighted.is_some(),
+        args.flag_systematic,
+        args.flag_cluster.is_some(),
+        args.flag_bootstrap,
+    ]
+    .into_iter()
+    .filter(|&mode| mode)
+    .count();
+    if num_modes > 1 {
+        return fail_incorrectusage_clierror!(
+            "Only one of --stratified, --weighted, --systematic, --cluster or --bootstrap can be \
+             used at a time."
+        );
+    }
+    if num_modes == 1 {
+        let multi_pass = args.flag_systematic
+            || args.flag_cluster.is_some()
+            || args.flag_bootstrap
+            || sample_size < 1.0;
+        if multi_pass && args.arg_input.is_none() {
+            return fail_incorrectusage_clierror!(
+                "This sampling mode needs to read the input more than once, so it does not \
+                 support stdin."
+            );
+        }
+        let mut rng = init_rng(args.flag_seed, &rng_kind);
+        let mut rdr = rconfig.reader()?;
+        rconfig.write_headers(&mut rdr, &mut wtr)?;
+        let sampled = sample_mode(&args, &rconfig, &mut rdr, rng.as_mut())?;
+        for row in sampled {
+            wtr.write_byte_record(&row)?;
+        }
+        return Ok(wtr.flush()?);
+    }
+
     if let Some(mut idx) = rconfig.indexed()? {
         // the index is present, so we can use random indexing
         #[allow(clippy::cast_precision_loss)]
@@ -329,3 +416,229 @@ fn sample_reservoir<R: io::Read>(
 
     Ok(reservoir)
 }
+
+/// create the RNG for the sampling modes, seeded with seed if set
+pub(crate) fn init_rng(seed: Option<u64>, rng_kind: &RngKind) -> Box<dyn RngCore> {
+    match *rng_kind {
+        RngKind::Standard => Box::new(match seed {
+            None => StdRng::from_rng(rand::thread_rng()).unwrap(),
+            Some(seed) => StdRng::seed_from_u64(seed), //DevSkim: ignore DS148264
+        }),
+        RngKind::Faster => Box::new(match seed {
+            None => Xoshiro256Plus::from_rng(rand::thread_rng()).unwrap(),
+            Some(seed) => Xoshiro256Plus::seed_from_u64(seed), //DevSkim: ignore DS148264
+        }),
+        RngKind::Cryptosecure => Box::new(match seed {
+            None => Hc128Rng::from_rng(rand::thread_rng()).unwrap(),
+            Some(seed) => {
+                let mut seed_32 = [0u8; 32];
+                seed_32[..8].copy_from_slice(&seed.to_le_bytes());
+                Hc128Rng::from_seed(seed_32)
+            },
+        }),
+    }
+}
+
+/// the number of items to sample from a population, where sample_size
+/// is a percentage of the population if it is between 0 and 1 exclusive
+#[allow(clippy::cast_precision_loss)]
+fn target_size(sample_size: f64, population: u64) -> u64 {
+    if sample_size < 1.0 {
+        (sample_size * population as f64) as u64
+    } else {
+        sample_size as u64
+    }
+}
+
+type GroupKey = Vec<Vec<u8>>;
+
+/// a record sampled by the sampling modes, with its 0-based position in the input
+/// so the sample can be output in input order
+type Sampled = (u64, csv::ByteRecord);
+
+/// sample the input with the sampling mode set in args,
+/// returning the sampled records in input order
+fn sample_mode<R: io::Read>(
+    args: &Args,
+    rconfig: &Config,
+    rdr: &mut csv::Reader<R>,
+    rng: &mut dyn RngCore,
+) -> CliResult<Vec<csv::ByteRecord>> {
+    let headers = rdr.byte_headers()?.clone();
+    let use_names = !args.flag_no_headers;
+    let sample_size = args.arg_sample_size;
+
+    let mut sampled: Vec<Sampled> = if let Some(ref cols) = args.flag_stratified {
+        let key_sel = cols.selection(&headers, use_names)?;
+        // with a percentage, count the records of each stratum first
+        let strata_counts = if sample_size < 1.0 {
+            let mut counts: HashMap<GroupKey, u64> = HashMap::new();
+            let mut count_rdr = rconfig.reader()?;
+            for row in count_rdr.byte_records() {
+                let row = row?;
+                *counts
+                    .entry(key_sel.select(&row).map(<[u8]>::to_vec).collect())
+                    .or_default() += 1;
+            }
+            Some(counts)
+        } else {
+            None
+        };
+
+        // a reservoir per stratum, with the number of records seen in the stratum
+        let mut reservoirs: HashMap<GroupKey, (u64, Vec<Sampled>)> = HashMap::new();
+        for (i, row) in rdr.byte_records().enumerate() {
+            let row = row?;
+            let key: GroupKey = key_sel.select(&row).map(<[u8]>::to_vec).collect();
+            let size = match strata_counts {
+                Some(ref counts) => target_size(sample_size, counts[&key]),
+                None => target_size(sample_size, 0),
+            };
+            let (seen, reservoir) = reservoirs.entry(key).or_default();
+            *seen += 1;
+            if (reservoir.len() as u64) < size {
+                reservoir.push((i as u64, row));
+            } else {
+                let random = rng.gen_range(0..*seen);
+                if random < size {
+                    reservoir[random as usize] = (i as u64, row);
+                }
+            }
+        }
+        reservoirs
+            .into_values()
+            .flat_map(|(_, reservoir)| reservoir)
+            .collect()
+    } else if let Some(ref col) = args.flag_weighted {
+        let sel = col.selection(&headers, use_names)?;
+        if sel.len() != 1 {
+            return fail_incorrectusage_clierror!("--weighted must select exactly one column.");
+        }
+        let weight_col = sel[0];
+        let size = if sample_size < 1.0 {
+            target_size(sample_size, util::count_rows(rconfig)?)
+        } else {
+            target_size(sample_size, 0)
+        } as usize;
+
+        // A-Res: keep the records with the largest u^(1/w) keys, where u is uniform in [0, 1).
+        // We compare ln(u)/w instead, which has the same order but is numerically stable.
+        let mut heap: BinaryHeap<Reverse<WeightedRecord>> = BinaryHeap::with_capacity(size + 1);
+        for (i, row) in rdr.byte_records().enumerate() {
+            let row = row?;
+            let weight = simdutf8::basic::from_utf8(row.get(weight_col).unwrap_or_default())
+                .ok()
+                .and_then(|w| w.trim().parse::<f64>().ok())
+                .filter(|w| w.is_finite() && *w > 0.0);
+            let Some(weight) = weight else {
+                continue;
+            };
+            let key = rng.gen::<f64>().ln() / weight;
+            if heap.len() < size {
+                heap.push(Reverse(WeightedRecord {
+                    key,
+                    sampled: (i as u64, row),
+                }));
+            } else if heap.peek().is_some_and(|Reverse(min)| key > min.key) {
+                heap.pop();
+                heap.push(Reverse(WeightedRecord {
+                    key,
+                    sampled: (i as u64, row),
+                }));
+            }
+        }
+        heap.into_iter().map(|Reverse(wr)| wr.sampled).collect()
+    } else if args.flag_systematic {
+        let row_count = util::count_rows(rconfig)?;
+        let size = target_size(sample_size, row_count);
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        let interval = (row_count / size).max(1);
+        let start = rng.gen_range(0..interval);
+        rdr.byte_records()
+            .enumerate()
+            .skip(start as usize)
+            .step_by(interval as usize)
+            .take(size as usize)
+            .map(|(i, row)| -> CliResult<Sampled> { Ok((i as u64, row?)) })
+            .collect::<CliResult<_>>()?
+    } else if let Some(ref cols) = args.flag_cluster {
+        let key_sel = cols.selection(&headers, use_names)?;
+        // first pass: collect the clusters in order of first appearance
+        let mut clusters: Vec<GroupKey> = Vec::new();
+        let mut seen: HashSet<GroupKey> = HashSet::new();
+        for row in rdr.byte_records() {
+            let key: GroupKey = key_sel.select(&row?).map(<[u8]>::to_vec).collect();
+            if !seen.contains(&key) {
+                seen.insert(key.clone());
+                clusters.push(key);
+            }
+        }
+        let size = target_size(sample_size, clusters.len() as u64) as usize;
+        let chosen: HashSet<&GroupKey> = clusters.choose_multiple(rng, size).collect();
+
+        // second pass: output all the records of the chosen clusters
+        let mut sampled = Vec::new();
+        for (i, row) in rconfig.reader()?.byte_records().enumerate() {
+            let row = row?;
+            let key: GroupKey = key_sel.select(&row).map(<[u8]>::to_vec).collect();
+            if chosen.contains(&key) {
+                sampled.push((i as u64, row));
+            }
+        }
+        sampled
+    } else {
+        // bootstrap: draw the positions of the sampled records with replacement,
+        // then stream the input, outputting each record as many times as it was drawn
+        let row_count = util::count_rows(rconfig)?;
+        let size = target_size(sample_size, row_count);
+        if row_count == 0 {
+            return Ok(Vec::new());
+        }
+        let mut draws: Vec<u64> = (0..size).map(|_| rng.gen_range(0..row_count)).collect();
+        draws.sort_unstable();
+
+        let mut sampled = Vec::with_capacity(draws.len());
+        let mut draws = draws.into_iter().peekable();
+        for (i, row) in rdr.byte_records().enumerate() {
+            if draws.peek().is_none() {
+                break;
+            }
+            let row = row?;
+            while draws.next_if_eq(&(i as u64)).is_some() {
+                sampled.push((i as u64, row.clone()));
+            }
+        }
+        sampled
+    };
+
+    sampled.sort_by_key(|(i, _)| *i);
+    Ok(sampled.into_iter().map(|(_, row)| row).collect())
+}
+
+/// a record sampled by weighted sampling, ordered by its A-Res key
+struct WeightedRecord {
+    key:     f64,
+    sampled: Sampled,
+}
+
+impl PartialEq for WeightedRecord {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for WeightedRecord {}
+
+impl PartialOrd for WeightedRecord {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for WeightedRecord {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.total_cmp(&other.key)
+    }
+}
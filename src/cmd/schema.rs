@@ -478,6 +478,8 @@ fn get_unique_values(
         // internal mode for getting frequency tables
         flag_stats_mode:      "_schema".to_string(),
         flag_all_unique_text: "<ALL UNIQUE>".to_string(),
+        flag_approx:          false,
+        flag_approx_size:     0,
         flag_jobs:            Some(util::njobs(args.flag_jobs)),
         flag_output:          None,
         flag_no_headers:      args.flag_no_headers,
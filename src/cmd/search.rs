@@ -13,6 +13,8 @@ Returns exitcode 1 when no match is found, unless the '--not-one' flag is used.
 When --quick is enabled, no output is produced and exitcode 0 is returned on 
 the first match.
 
+If the CSV is indexed, it's searched in parallel chunks (see --jobs).
+
 For examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_search.rs.
 
 Usage:
@@ -51,6 +53,10 @@ search options:
                            N milliseconds, whichever occurs first. Returns the preview to
                            stderr. Output is still written to stdout or --output as usual.
     -c, --count            Return number of matches to stderr.
+    -j, --jobs <arg>       The number of jobs to run in parallel when the CSV is indexed.
+                           The output is in the same order as the input.
+                           When not set, the number of jobs is set to the
+                           number of CPUs detected. Not used with --preview-match.
     --size-limit <mb>      Set the approximate size limit (MB) of the compiled
                            regular expression. If the compiled expression exceeds this 
                            number, then a compilation error is returned.
@@ -113,10 +119,25 @@ struct Args {
     flag_quick:          bool,
     flag_preview_match:  Option<u64>,
     flag_count:          bool,
+    flag_jobs:           Option<usize>,
     flag_progressbar:    bool,
     flag_quiet:          bool,
 }
 
+/// The result of searching a chunk of an indexed CSV, also used by `searchset`
+#[derive(Default)]
+pub(crate) struct SearchChunk {
+    /// the records to output, with the --flag column
+    pub(crate) records:       Vec<csv::ByteRecord>,
+    /// the records that did not match, for searchset's --unmatched-output
+    pub(crate) unmatched:     Vec<csv::ByteRecord>,
+    pub(crate) matches:       u64,
+    /// the number of matches of all the patterns, for searchset
+    pub(crate) total_matches: u64,
+    /// the number of records searched, up to the first match with --quick
+    pub(crate) rows:          u64,
+}
+
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
     let regex_unicode = if util::get_envvar_flag("QSV_REGEX_UNICODE") {
@@ -339,64 +360,138 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         json_wtr.write_all(b"[")?;
     }
 
-    let mut match_row;
-    while rdr.read_byte_record(&mut record)? {
-        row_ctr += 1;
+    let njobs = util::njobs(args.flag_jobs);
+    let parallel_count = match rconfig.indexed()? {
+        Some(idx) if njobs > 1 && args.flag_preview_match.is_none() => Some(idx.count()),
+        _ => None,
+    };
 
-        #[cfg(any(feature = "feature_capable", feature = "lite"))]
-        if show_progress {
-            progress.inc(1);
-        }
-        m = sel.select(&record).any(|f| pattern.is_match(f));
-        if invert_match {
-            m = !m;
-        }
-        if m {
-            match_ctr += 1;
-            if flag_quick {
-                break;
-            }
-        }
+    if let Some(idx_count) = parallel_count {
+        let (sel, pattern) = (sel.clone(), pattern.clone());
+        util::process_indexed_chunks(
+            &rconfig,
+            idx_count,
+            njobs,
+            move |start, records| {
+                let mut chunk = SearchChunk::default();
+                let mut itoa_buf = itoa::Buffer::new();
+                for (flag_rowi, record) in (start + 2..).zip(records) {
+                    let mut record = record?;
+                    chunk.rows += 1;
+                    let mut m = sel.select(&record).any(|f| pattern.is_match(f));
+                    if invert_match {
+                        m = !m;
+                    }
+                    if m {
+                        chunk.matches += 1;
+                        if flag_quick {
+                            break;
+                        }
+                    }
 
-        if flag_flag {
-            flag_rowi += 1;
-            match_row = if m {
-                itoa::Buffer::new()
-                    .format(flag_rowi)
-                    .clone_into(&mut matched_rows);
-                matched_rows.as_bytes()
-            } else {
-                b"0"
-            };
-            if matches_only {
-                if match_row == b"0" {
-                    continue;
+                    if flag_flag {
+                        if matches_only {
+                            if !m {
+                                continue;
+                            }
+                            record.clear();
+                        }
+                        if m {
+                            record.push_field(itoa_buf.format(flag_rowi).as_bytes());
+                        } else {
+                            record.push_field(b"0");
+                        }
+                        chunk.records.push(record);
+                    } else if m {
+                        chunk.records.push(record);
+                    }
                 }
-                record.clear();
+                Ok(chunk)
+            },
+            |chunk| {
+                #[cfg(any(feature = "feature_capable", feature = "lite"))]
+                if show_progress {
+                    progress.inc(chunk.rows);
+                }
+                row_ctr += chunk.rows;
+                match_ctr += chunk.matches;
+                for record in &chunk.records {
+                    if flag_json {
+                        util::write_json_record(
+                            &mut json_wtr,
+                            flag_no_headers,
+                            &headers,
+                            record,
+                            &mut is_first,
+                        )?;
+                    } else {
+                        wtr.write_byte_record(record)?;
+                    }
+                }
+                // with --quick, stop at the first chunk with a match
+                Ok(!(flag_quick && chunk.matches > 0))
+            },
+        )?;
+    } else {
+        let mut match_row;
+        while rdr.read_byte_record(&mut record)? {
+            row_ctr += 1;
+
+            #[cfg(any(feature = "feature_capable", feature = "lite"))]
+            if show_progress {
+                progress.inc(1);
             }
-            record.push_field(match_row);
-            if flag_json {
-                util::write_json_record(
-                    &mut json_wtr,
-                    flag_no_headers,
-                    &headers,
-                    &record,
-                    &mut is_first,
-                )?;
-            } else {
-                wtr.write_byte_record(&record)?;
+            m = sel.select(&record).any(|f| pattern.is_match(f));
+            if invert_match {
+                m = !m;
             }
-        } else if m {
-            if flag_json {
-                util::write_json_record(
-                    &mut json_wtr,
-                    flag_no_headers,
-                    &headers,
-                    &record,
-                    &mut is_first,
-                )?;
-            } else {
-                wtr.write_byte_record(&record)?;
+            if m {
+                match_ctr += 1;
+                if flag_quick {
+                    break;
+                }
+            }
+
+            if flag_flag {
+                flag_rowi += 1;
+                match_row = if m {
+                    itoa::Buffer::new()
+                        .format(flag_rowi)
+                        .clone_into(&mut matched_rows);
+                    matched_rows.as_bytes()
+                } else {
+                    b"0"
+                };
+                if matches_only {
+                    if match_row == b"0" {
+                        continue;
+                    }
+                    record.clear();
+                }
+                record.push_field(match_row);
+                if flag_json {
+                    util::write_json_record(
+                        &mut json_wtr,
+                        flag_no_headers,
+                        &headers,
+                        &record,
+                        &mut is_first,
+                    )?;
+                } else {
+                    wtr.write_byte_record(&record)?;
+                }
+            } else if m {
+                if flag_json {
+                    util::write_json_record(
+                        &mut json_wtr,
+                        flag_no_headers,
+                        &headers,
+                        &record,
+                        &mut is_first,
+                    )?;
+                } else {
+                    wtr.write_byte_record(&record)?;
+                }
             }
         }
     }
@@ -19,6 +19,14 @@ Returns exitcode 1 when no match is found, unless the '--not-one' flag is used.
 When --quick is enabled, no output is produced and exitcode 0 is returned on 
 the first match.
 
+With --dictionary, the lines of the regexset-file are matched as literal terms using
+the Aho-Corasick algorithm instead of being compiled to a regex set, so there's no size
+limit and millions of terms can be matched, e.g. sanctions lists or product catalogs.
+With --flag, the matched terms are listed with the character offset (0-based) of every
+match in the field, e.g. "3;acme@0,widget@12".
+
+If the CSV is indexed, it's searched in parallel chunks (see --jobs).
+
 For examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_searchset.rs.
 
 Usage:
@@ -44,12 +52,19 @@ searchset options:
     -u, --unicode              Enable unicode support. When enabled, character classes
                                will match all unicode word characters instead of only
                                ASCII word characters. Decreases performance.
+    --dictionary               Match the lines of <regexset-file> as literal terms with
+                               the Aho-Corasick algorithm. Empty lines are ignored.
+                               With --ignore-case, only ASCII letters are compared
+                               case-insensitively.
+    --whole-word               With --dictionary, only match terms that are not preceded
+                               or followed by a letter, a digit or an underscore.
 
     -f, --flag <column>        If given, the command will not filter rows
                                but will instead flag the found rows in a new
                                column named <column>. For each found row, <column>
                                is set to the row number of the row, followed by a
-                               semicolon, then a list of the matching regexes.
+                               semicolon, then a list of the matching regexes
+                               (or terms, with --dictionary) of the first matching field.
     --flag-matches-only        When --flag is enabled, only rows that match are
                                sent to output. Rows that do not match are filtered.
     --unmatched-output <file>  When --flag-matches-only is enabled, output the rows
@@ -63,6 +78,10 @@ searchset options:
                                Ignored if --json is enabled.
     -j, --json                 Return number of matches, number of rows with matches,
                                and number of rows to stderr in JSON format.
+    --jobs <arg>               The number of jobs to run in parallel when the CSV
+                               is indexed. The output is in the same order as the input.
+                               When not set, the number of jobs is set to the
+                               number of CPUs detected.
     --size-limit <mb>          Set the approximate size limit (MB) of the compiled
                                regular expression. If the compiled expression exceeds this 
                                number, then a compilation error is returned.
@@ -91,14 +110,19 @@ use std::{
     io::{self, BufRead, BufReader},
 };
 
+use aho_corasick::AhoCorasick;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
 use indicatif::{HumanCount, ProgressBar, ProgressDrawTarget};
 use log::{debug, info};
-use regex::{bytes::RegexSetBuilder, Regex};
+use regex::{
+    bytes::{RegexSet, RegexSetBuilder},
+    Regex,
+};
 use serde::Deserialize;
 use serde_json::json;
 
 use crate::{
+    cmd::search::SearchChunk,
     config::{Config, Delimiter},
     select::SelectColumns,
     util, CliError, CliResult,
@@ -116,6 +140,8 @@ struct Args {
     flag_delimiter:         Option<Delimiter>,
     flag_invert_match:      bool,
     flag_unicode:           bool,
+    flag_dictionary:        bool,
+    flag_whole_word:        bool,
     flag_ignore_case:       bool,
     flag_flag:              Option<String>,
     flag_flag_matches_only: bool,
@@ -125,6 +151,7 @@ struct Args {
     flag_quick:             bool,
     flag_count:             bool,
     flag_json:              bool,
+    flag_jobs:              Option<usize>,
     flag_not_one:           bool,
     flag_progressbar:       bool,
     flag_quiet:             bool,
@@ -142,6 +169,113 @@ fn read_regexset(filename: &str, literal: bool) -> io::Result<Vec<String>> {
     }
 }
 
+fn read_dictionary(filename: &str) -> io::Result<Vec<String>> {
+    let reader = BufReader::new(File::open(filename)?);
+    let mut terms = reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(term) if term.is_empty()))
+        .collect::<io::Result<Vec<String>>>()?;
+    // duplicate terms would be reported more than once
+    terms.sort_unstable();
+    terms.dedup();
+    Ok(terms)
+}
+
+/// The patterns to search for
+#[derive(Clone)]
+enum Matcher {
+    RegexSet(RegexSet),
+    Dictionary {
+        terms:      AhoCorasick,
+        whole_word: bool,
+    },
+}
+
+impl Matcher {
+    fn is_match(&self, field: &[u8]) -> bool {
+        match self {
+            Self::RegexSet(set) => set.is_match(field),
+            Self::Dictionary {
+                terms,
+                whole_word: false,
+            } => terms.is_match(field),
+            Self::Dictionary {
+                terms,
+                whole_word: true,
+            } => terms
+                .find_overlapping_iter(field)
+                .any(|m| is_whole_word(field, m.start(), m.end())),
+        }
+    }
+
+    /// Set `match_list` to the comma-separated labels of the patterns matching `field`.
+    /// Dictionary terms are followed by @ and the character offset of every match.
+    /// Returns the number of matches.
+    fn match_list(&self, field: &[u8], labels: &[String], match_list: &mut String) -> u64 {
+        let mut count = 0;
+        match_list.clear();
+        match self {
+            Self::RegexSet(set) => {
+                for i in set.matches(field).iter() {
+                    if count > 0 {
+                        match_list.push(',');
+                    }
+                    match_list.push_str(&labels[i]);
+                    count += 1;
+                }
+            },
+            Self::Dictionary { terms, whole_word } => {
+                let mut itoa_buf = itoa::Buffer::new();
+                for m in terms.find_overlapping_iter(field) {
+                    if *whole_word && !is_whole_word(field, m.start(), m.end()) {
+                        continue;
+                    }
+                    if count > 0 {
+                        match_list.push(',');
+                    }
+                    // count the chars, i.e. the bytes that don't continue a UTF-8 sequence
+                    let offset = field[..m.start()]
+                        .iter()
+                        .filter(|&&b| b & 0xC0 != 0x80)
+                        .count();
+                    match_list.push_str(&labels[m.pattern().as_usize()]);
+                    match_list.push('@');
+                    match_list.push_str(itoa_buf.format(offset));
+                    count += 1;
+                }
+            },
+        }
+        count
+    }
+}
+
+/// Whether the match at `start..end` of `field` is not preceded or followed by a word char
+fn is_whole_word(field: &[u8], start: usize, end: usize) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    // the start of the char before the match
+    let mut before = start;
+    while before > 0 {
+        before -= 1;
+        if field[before] & 0xC0 != 0x80 {
+            break;
+        }
+    }
+    let char_before = std::str::from_utf8(&field[before..start])
+        .ok()
+        .and_then(|s| s.chars().next());
+
+    let after = &field[end..(end + 4).min(field.len())];
+    let after = match std::str::from_utf8(after) {
+        Ok(s) => s,
+        // safety: the bytes up to valid_up_to are valid UTF-8
+        Err(e) => std::str::from_utf8(&after[..e.valid_up_to()]).unwrap(),
+    };
+    let char_after = after.chars().next();
+
+    !char_before.is_some_and(is_word_char) && !char_after.is_some_and(is_word_char)
+}
+
 pub fn run(argv: &[&str]) -> CliResult<()> {
     let args: Args = util::get_args(USAGE, argv)?;
     let flag_not_one = args.flag_not_one;
@@ -155,34 +289,61 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         );
     }
 
-    let regexset = read_regexset(&args.arg_regexset_file, args.flag_literal)?;
-
-    let mut regex_labels: Vec<String> = Vec::with_capacity(regexset.len());
-    let labels_re = Regex::new(r".?#(?P<label>.*)$").unwrap();
-
-    // use regex comment labels if they exist, so matches are easier to understand
-    for (i, regex) in regexset.iter().enumerate() {
-        let label = labels_re
-            .captures(regex)
-            .and_then(|cap| cap.name("label"))
-            .map_or_else(|| (i + 1).to_string(), |m| m.as_str().to_string());
-        regex_labels.push(label);
+    if args.flag_whole_word && !args.flag_dictionary {
+        return fail_incorrectusage_clierror!("Cannot use --whole-word without --dictionary");
     }
 
-    let regex_unicode = if util::get_envvar_flag("QSV_REGEX_UNICODE") {
-        true
+    let (pattern, regex_labels) = if args.flag_dictionary {
+        // the terms are their own labels
+        let terms = read_dictionary(&args.arg_regexset_file)?;
+        debug!(
+            "Building Aho-Corasick automaton of {} terms...",
+            terms.len()
+        );
+        let automaton = match AhoCorasick::builder()
+            .ascii_case_insensitive(args.flag_ignore_case)
+            .build(&terms)
+        {
+            Ok(automaton) => automaton,
+            Err(e) => return fail_clierror!("Cannot build the dictionary: {e}"),
+        };
+        debug!("Successfully built Aho-Corasick automaton!");
+        let matcher = Matcher::Dictionary {
+            terms:      automaton,
+            whole_word: args.flag_whole_word,
+        };
+        (matcher, terms)
     } else {
-        args.flag_unicode
-    };
+        let regexset = read_regexset(&args.arg_regexset_file, args.flag_literal)?;
+
+        let mut regex_labels: Vec<String> = Vec::with_capacity(regexset.len());
+        let labels_re = Regex::new(r".?#(?P<label>.*)$").unwrap();
+
+        // use regex comment labels if they exist, so matches are easier to understand
+        for (i, regex) in regexset.iter().enumerate() {
+            let label = labels_re
+                .captures(regex)
+                .and_then(|cap| cap.name("label"))
+                .map_or_else(|| (i + 1).to_string(), |m| m.as_str().to_string());
+            regex_labels.push(label);
+        }
 
-    debug!("Compiling {} regex set expressions...", regexset.len());
-    let pattern = RegexSetBuilder::new(&regexset)
-        .case_insensitive(args.flag_ignore_case)
-        .unicode(regex_unicode)
-        .size_limit(args.flag_size_limit * (1 << 20))
-        .dfa_size_limit(args.flag_dfa_size_limit * (1 << 20))
-        .build()?;
-    debug!("Successfully compiled regex set!");
+        let regex_unicode = if util::get_envvar_flag("QSV_REGEX_UNICODE") {
+            true
+        } else {
+            args.flag_unicode
+        };
+
+        debug!("Compiling {} regex set expressions...", regexset.len());
+        let pattern = RegexSetBuilder::new(&regexset)
+            .case_insensitive(args.flag_ignore_case)
+            .unicode(regex_unicode)
+            .size_limit(args.flag_size_limit * (1 << 20))
+            .dfa_size_limit(args.flag_dfa_size_limit * (1 << 20))
+            .build()?;
+        debug!("Successfully compiled regex set!");
+        (Matcher::RegexSet(pattern), regex_labels)
+    };
 
     let rconfig = Config::new(args.arg_input.as_ref())
         .delimiter(args.flag_delimiter)
@@ -228,74 +389,147 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     // minimize allocs
     #[allow(unused_assignments)]
     let mut flag_column: Vec<u8> = Vec::with_capacity(20);
-    let mut match_list_vec = Vec::with_capacity(20);
-    #[allow(unused_assignments)]
     let mut match_list = String::with_capacity(20);
     let mut matched_rows = String::with_capacity(20);
     #[allow(unused_assignments)]
     let mut match_list_with_row = String::with_capacity(20);
     let mut m;
     let mut matched = false;
-    let mut matches: Vec<usize> = Vec::with_capacity(20);
 
-    while rdr.read_byte_record(&mut record)? {
-        row_ctr += 1;
-        #[cfg(any(feature = "feature_capable", feature = "lite"))]
-        if show_progress {
-            progress.inc(1);
-        }
-        m = sel.select(&record).any(|f| {
-            matched = pattern.is_match(f);
-            if matched && do_match_list {
-                matches = pattern.matches(f).into_iter().collect();
-                total_matches += matches.len() as u64;
-                for j in &mut matches {
-                    *j += 1; // so the list is human readable - i.e. not zero-based
+    let njobs = util::njobs(args.flag_jobs);
+    let parallel_count = match rconfig.indexed()? {
+        Some(idx) if njobs > 1 => Some(idx.count()),
+        _ => None,
+    };
+
+    if let Some(idx_count) = parallel_count {
+        let (sel, pattern, regex_labels) = (sel.clone(), pattern.clone(), regex_labels.clone());
+        let invert_match = args.flag_invert_match;
+        let flag_quick = args.flag_quick;
+        let flag_matches_only = args.flag_flag_matches_only;
+        let unmatched_output = args.flag_unmatched_output.is_some();
+        util::process_indexed_chunks(
+            &rconfig,
+            idx_count,
+            njobs,
+            move |start, records| {
+                let mut chunk = SearchChunk::default();
+                let mut itoa_buf = itoa::Buffer::new();
+                let mut match_list = String::new();
+                for (flag_rowi, record) in (start + 2..).zip(records) {
+                    let mut record = record?;
+                    chunk.rows += 1;
+                    let mut m = sel.select(&record).any(|f| {
+                        let matched = pattern.is_match(f);
+                        if matched && do_match_list {
+                            chunk.total_matches +=
+                                pattern.match_list(f, &regex_labels, &mut match_list);
+                        }
+                        matched
+                    });
+                    if invert_match {
+                        m = !m;
+                    }
+                    if m {
+                        chunk.matches += 1;
+                        if flag_quick {
+                            break;
+                        }
+                    }
+
+                    if do_match_list {
+                        let flag_column = if m {
+                            let matched_row = itoa_buf.format(flag_rowi);
+                            if invert_match {
+                                matched_row.to_string()
+                            } else {
+                                format!("{matched_row};{match_list}")
+                            }
+                        } else {
+                            "0".to_string()
+                        };
+                        if flag_matches_only && !m {
+                            if unmatched_output {
+                                chunk.unmatched.push(record);
+                            }
+                            continue;
+                        }
+                        record.push_field(flag_column.as_bytes());
+                        chunk.records.push(record);
+                    } else if m {
+                        chunk.records.push(record);
+                    }
+                }
+                Ok(chunk)
+            },
+            |chunk| {
+                #[cfg(any(feature = "feature_capable", feature = "lite"))]
+                if show_progress {
+                    progress.inc(chunk.rows);
                 }
-                match_list_vec.clone_from(&matches);
+                row_ctr += chunk.rows;
+                match_row_ctr += chunk.matches;
+                total_matches += chunk.total_matches;
+                for record in &chunk.unmatched {
+                    unmatched_wtr.write_byte_record(record)?;
+                }
+                for record in &chunk.records {
+                    wtr.write_byte_record(record)?;
+                }
+                // with --quick, stop at the first chunk with a match
+                Ok(!(flag_quick && chunk.matches > 0))
+            },
+        )?;
+    } else {
+        while rdr.read_byte_record(&mut record)? {
+            row_ctr += 1;
+            #[cfg(any(feature = "feature_capable", feature = "lite"))]
+            if show_progress {
+                progress.inc(1);
             }
-            matched
-        });
-        if args.flag_invert_match {
-            m = !m;
-        }
-        if m {
-            match_row_ctr += 1;
-            if args.flag_quick {
-                break;
+            m = sel.select(&record).any(|f| {
+                matched = pattern.is_match(f);
+                if matched && do_match_list {
+                    total_matches += pattern.match_list(f, &regex_labels, &mut match_list);
+                }
+                matched
+            });
+            if args.flag_invert_match {
+                m = !m;
+            }
+            if m {
+                match_row_ctr += 1;
+                if args.flag_quick {
+                    break;
+                }
             }
-        }
 
-        if do_match_list {
-            flag_rowi += 1;
-            flag_column = if m {
-                itoa::Buffer::new()
-                    .format(flag_rowi)
-                    .clone_into(&mut matched_rows);
-                if args.flag_invert_match {
-                    matched_rows.as_bytes().to_vec()
+            if do_match_list {
+                flag_rowi += 1;
+                flag_column = if m {
+                    itoa::Buffer::new()
+                        .format(flag_rowi)
+                        .clone_into(&mut matched_rows);
+                    if args.flag_invert_match {
+                        matched_rows.as_bytes().to_vec()
+                    } else {
+                        match_list_with_row = format!("{matched_rows};{match_list}");
+                        match_list_with_row.as_bytes().to_vec()
+                    }
                 } else {
-                    match_list = match_list_vec
-                        .iter()
-                        .map(|i| regex_labels[*i - 1].clone())
-                        .collect::<Vec<String>>()
-                        .join(",");
-                    match_list_with_row = format!("{matched_rows};{match_list}");
-                    match_list_with_row.as_bytes().to_vec()
-                }
-            } else {
-                b"0".to_vec()
-            };
-            if args.flag_flag_matches_only && !m {
-                if args.flag_unmatched_output.is_some() {
-                    unmatched_wtr.write_byte_record(&record)?;
+                    b"0".to_vec()
+                };
+                if args.flag_flag_matches_only && !m {
+                    if args.flag_unmatched_output.is_some() {
+                        unmatched_wtr.write_byte_record(&record)?;
+                    }
+                    continue;
                 }
-                continue;
+                record.push_field(&flag_column);
+                wtr.write_byte_record(&record)?;
+            } else if m {
+                wtr.write_byte_record(&record)?;
             }
-            record.push_field(&flag_column);
-            wtr.write_byte_record(&record)?;
-        } else if m {
-            wtr.write_byte_record(&record)?;
         }
     }
     unmatched_wtr.flush()?;
@@ -21,8 +21,37 @@ sort options:
     -N, --numeric           Compare according to string numerical value
     -R, --reverse           Reverse order
     -i, --ignore-case       Compare strings disregarding case
+    --collate <locale>      Compare strings with the Unicode Collation Algorithm,
+                            using the collation rules of <locale> (e.g. en, de, sv,
+                            fr-CA) instead of byte order, so "Ängelholm" sorts
+                            before "Zürich". Use "und" for the language-neutral
+                            root collation. Combine with --ignore-case to
+                            disregard case.
+    --accent-insensitive    Compare strings disregarding accents (e.g. "é" = "e").
+                            Uses the root collation if --collate is not set.
     -u, --unique            When set, identical consecutive lines will be dropped
                             to keep only one line per sorted value.
+    -k, --keys <spec>       Sort using per-column key specifications instead of a
+                            single comparison mode for all selected columns.
+                            A spec is a comma-separated list of keys, each of the
+                            form <column>[:<order>][:<type>][:<nulls>], e.g.
+                              -k date:desc:date,amount:asc:num,name:asc:natural
+                            where <column> is a column name or 1-based index and
+                            the optional parts (in any order) are:
+                              order - asc (default) or desc
+                              type  - str (default), istr (case-insensitive),
+                                      num, natural (digit runs compared as numbers,
+                                      e.g. file2 < file10), version (natural with
+                                      pre-releases first, e.g. 1.0.0-rc1 < 1.0.0)
+                                      or date (any format recognized by datefmt)
+                              nulls - nulls-first or nulls-last. Empty values and
+                                      values that cannot be parsed as the key type
+                                      are nulls. By default, nulls sort as the
+                                      smallest value (first for asc, last for desc).
+                            The same spec is accepted by sortcheck and extsort.
+                            Takes precedence over --select and cannot be combined
+                            with --numeric, --reverse, --ignore-case, --random,
+                            --collate or --accent-insensitive.
 
     --random                Random order
     --seed <number>         Random Number Generator (RNG) seed to use if --random is set
@@ -65,11 +94,18 @@ Common options:
 
 use std::{cmp, str::FromStr};
 
+use csv::ByteRecord;
+use icu_collator::{CaseLevel, Collator, CollatorOptions, Strength};
+use icu_locid::Locale;
+use qsv_dateparser::parse_with_preference;
 // use fastrand; //DevSkim: ignore DS148264
 use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use rand_hc::Hc128Rng;
 use rand_xoshiro::Xoshiro256Plus;
-use rayon::slice::ParallelSliceMut;
+use rayon::{
+    iter::{IntoParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
 use serde::Deserialize;
 use simdutf8::basic::from_utf8;
 use strum_macros::EnumString;
@@ -78,27 +114,30 @@ use self::Number::{Float, Int};
 use crate::{
     cmd::dedup::iter_cmp_ignore_case,
     config::{Config, Delimiter},
-    select::SelectColumns,
-    util, CliResult,
+    select::{SelectColumns, Selection},
+    util, CliError, CliResult,
 };
 
 #[derive(Deserialize)]
 struct Args {
-    arg_input:        Option<String>,
-    flag_select:      SelectColumns,
-    flag_numeric:     bool,
-    flag_reverse:     bool,
-    flag_ignore_case: bool,
-    flag_unique:      bool,
-    flag_random:      bool,
-    flag_seed:        Option<u64>,
-    flag_rng:         String,
-    flag_jobs:        Option<usize>,
-    flag_faster:      bool,
-    flag_output:      Option<String>,
-    flag_no_headers:  bool,
-    flag_delimiter:   Option<Delimiter>,
-    flag_memcheck:    bool,
+    arg_input:               Option<String>,
+    flag_select:             SelectColumns,
+    flag_numeric:            bool,
+    flag_reverse:            bool,
+    flag_ignore_case:        bool,
+    flag_collate:            Option<String>,
+    flag_accent_insensitive: bool,
+    flag_unique:             bool,
+    flag_keys:               Option<String>,
+    flag_random:             bool,
+    flag_seed:               Option<u64>,
+    flag_rng:                String,
+    flag_jobs:               Option<usize>,
+    flag_faster:             bool,
+    flag_output:             Option<String>,
+    flag_no_headers:         bool,
+    flag_delimiter:          Option<Delimiter>,
+    flag_memcheck:           bool,
 }
 
 #[derive(Debug, EnumString, PartialEq)]
@@ -141,6 +180,40 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let headers = rdr.byte_headers()?.clone();
     let sel = rconfig.selection(&headers)?;
 
+    let sort_keys = if let Some(ref spec) = args.flag_keys {
+        if numeric
+            || reverse
+            || random
+            || args.flag_ignore_case
+            || args.flag_collate.is_some()
+            || args.flag_accent_insensitive
+        {
+            return fail_incorrectusage_clierror!(
+                "--keys cannot be combined with --numeric, --reverse, --ignore-case, --random, \
+                 --collate or --accent-insensitive."
+            );
+        }
+        Some(parse_sort_keys(
+            spec,
+            &headers,
+            !args.flag_no_headers,
+            rconfig.get_dmy_preference(),
+        )?)
+    } else {
+        None
+    };
+
+    let collator = new_collator(
+        args.flag_collate.as_deref(),
+        args.flag_ignore_case,
+        args.flag_accent_insensitive,
+    )?;
+    if collator.is_some() && (numeric || random) {
+        return fail_incorrectusage_clierror!(
+            "--collate and --accent-insensitive cannot be combined with --numeric or --random."
+        );
+    }
+
     util::njobs(args.flag_jobs);
 
     // Seeding RNG
@@ -149,6 +222,74 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
     let ignore_case = args.flag_ignore_case;
 
     let mut all = rdr.byte_records().collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(keys) = sort_keys {
+        // encode the sort keys once upfront, so the sort itself is a plain byte comparison
+        let mut keyed: Vec<(Vec<u8>, ByteRecord)> = all
+            .into_par_iter()
+            .map(|r| {
+                let mut key = Vec::new();
+                encode_sort_keys(&keys, &r, &mut key);
+                (key, r)
+            })
+            .collect();
+        if faster {
+            keyed.par_sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        } else {
+            keyed.par_sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+        rconfig.write_headers(&mut rdr, &mut wtr)?;
+        let mut prev_key: Option<Vec<u8>> = None;
+        for (key, r) in keyed {
+            if args.flag_unique {
+                if prev_key.as_ref() == Some(&key) {
+                    continue;
+                }
+                prev_key = Some(key);
+            }
+            wtr.write_byte_record(&r)?;
+        }
+        return Ok(wtr.flush()?);
+    }
+
+    if let Some(ref collator) = collator {
+        let compare = |r1: &ByteRecord, r2: &ByteRecord| {
+            let ordering = iter_cmp_collate(collator, sel.select(r1), sel.select(r2));
+            if reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+        if faster {
+            all.par_sort_unstable_by(compare);
+        } else {
+            all.par_sort_by(compare);
+        }
+
+        let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+        rconfig.write_headers(&mut rdr, &mut wtr)?;
+        let mut prev: Option<ByteRecord> = None;
+        for r in all {
+            if args.flag_unique {
+                if let Some(ref other_r) = prev {
+                    if iter_cmp_collate(collator, sel.select(&r), sel.select(other_r))
+                        == cmp::Ordering::Equal
+                    {
+                        continue;
+                    }
+                }
+                wtr.write_byte_record(&r)?;
+                prev = Some(r);
+            } else {
+                wtr.write_byte_record(&r)?;
+            }
+        }
+        return Ok(wtr.flush()?);
+    }
+
     match (numeric, reverse, random, faster) {
         // --random sort
         (_, _, true, _) => {
@@ -323,6 +464,57 @@ where
     }
 }
 
+/// Order `a` and `b` lexicographically using a locale-aware `collator`
+#[inline]
+pub fn iter_cmp_collate<'a, L, R>(collator: &Collator, mut a: L, mut b: R) -> cmp::Ordering
+where
+    L: Iterator<Item = &'a [u8]>,
+    R: Iterator<Item = &'a [u8]>,
+{
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return cmp::Ordering::Equal,
+            (None, _) => return cmp::Ordering::Less,
+            (_, None) => return cmp::Ordering::Greater,
+            (Some(x), Some(y)) => match collator.compare_utf8(x, y) {
+                cmp::Ordering::Equal => (),
+                non_eq => return non_eq,
+            },
+        }
+    }
+}
+
+/// Create the Unicode Collation Algorithm collator for the `--collate <locale>` and
+/// `--accent-insensitive` options, or None if neither is set.
+pub fn new_collator(
+    locale: Option<&str>,
+    ignore_case: bool,
+    accent_insensitive: bool,
+) -> CliResult<Option<Collator>> {
+    if locale.is_none() && !accent_insensitive {
+        return Ok(None);
+    }
+    let locale_name = locale.unwrap_or("und");
+    let Ok(locale) = locale_name.parse::<Locale>() else {
+        return fail_incorrectusage_clierror!("Invalid --collate locale \"{locale_name}\".");
+    };
+
+    // primary strength ignores accents and case, secondary only case
+    let mut options = CollatorOptions::new();
+    options.strength = Some(match (accent_insensitive, ignore_case) {
+        (true, _) => Strength::Primary,
+        (false, true) => Strength::Secondary,
+        (false, false) => Strength::Tertiary,
+    });
+    if accent_insensitive && !ignore_case {
+        options.case_level = Some(CaseLevel::On);
+    }
+    match Collator::try_new(&(&locale).into(), options) {
+        Ok(collator) => Ok(Some(collator)),
+        Err(e) => fail_clierror!("Cannot create a collator for locale \"{locale_name}\": {e}"),
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 enum Number {
     Int(i64),
@@ -351,19 +543,292 @@ fn next_num<'a, X>(xs: &mut X) -> Option<Number>
 where
     X: Iterator<Item = &'a [u8]>,
 {
-    match xs.next() {
-        Some(bytes) => {
-            if let Ok(i) = atoi_simd::parse::<i64>(bytes) {
-                Some(Number::Int(i))
+    xs.next().and_then(parse_num)
+}
+
+#[inline]
+fn parse_num(bytes: &[u8]) -> Option<Number> {
+    if let Ok(i) = atoi_simd::parse::<i64>(bytes) {
+        Some(Number::Int(i))
+    } else {
+        // If parsing as i64 failed, try parsing as f64
+        match from_utf8(bytes).map(str::parse::<f64>) {
+            Ok(Ok(f)) => Some(Number::Float(f)),
+            _ => None,
+        }
+    }
+}
+
+// Sort keys are encoded into byte strings that compare the same way the typed values do,
+// so sort, sortcheck and extsort only ever compare plain bytes and always agree.
+// Every encoded value is prefix-free, which lets us negate the bytes of a descending key
+// and concatenate several keys without one key bleeding into the next.
+const NULL_FIRST_TAG: u8 = 0x01;
+const VALUE_TAG: u8 = 0x02;
+const NULL_LAST_TAG: u8 = 0x03;
+
+const NATURAL_END: u8 = 0x00;
+const NATURAL_NUM: u8 = 0x01;
+const NATURAL_TEXT: u8 = 0x02;
+
+#[derive(Clone, Copy, Debug, EnumString, PartialEq)]
+#[strum(ascii_case_insensitive)]
+enum SortKeyKind {
+    Str,
+    IStr,
+    Num,
+    Natural,
+    Version,
+    Date,
+}
+
+/// A single key of a `--keys` sort specification
+#[derive(Clone, Debug)]
+pub struct SortKey {
+    sel:        Selection,
+    descending: bool,
+    kind:       SortKeyKind,
+    nulls_last: bool,
+    prefer_dmy: bool,
+}
+
+/// Parse a `--keys` specification, e.g. `date:desc:date,amount:asc:num,name:natural`.
+/// `prefer_dmy` is the date format preference of `date` keys, usually from
+/// `Config::get_dmy_preference`.
+pub fn parse_sort_keys(
+    spec: &str,
+    headers: &ByteRecord,
+    use_names: bool,
+    prefer_dmy: bool,
+) -> CliResult<Vec<SortKey>> {
+    let mut keys = Vec::new();
+    for key_spec in spec.split(',') {
+        let mut parts = key_spec.split(':');
+        let column = parts.next().unwrap_or_default();
+        if column.is_empty() {
+            return fail_incorrectusage_clierror!("Missing column in sort key \"{key_spec}\".");
+        }
+        let sel = SelectColumns::parse(column)
+            .and_then(|sc| sc.selection(headers, use_names))
+            .map_err(|e| {
+                CliError::IncorrectUsage(format!("Invalid sort key \"{key_spec}\": {e}"))
+            })?;
+
+        let mut descending = false;
+        let mut kind = SortKeyKind::Str;
+        let mut nulls = None;
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "asc" => descending = false,
+                "desc" => descending = true,
+                "nulls-first" => nulls = Some(false),
+                "nulls-last" => nulls = Some(true),
+                other => {
+                    let Ok(k) = SortKeyKind::from_str(other) else {
+                        return fail_incorrectusage_clierror!(
+                            "Invalid option \"{part}\" in sort key \"{key_spec}\". Valid options \
+                             are asc, desc, str, istr, num, natural, version, date, nulls-first \
+                             and nulls-last."
+                        );
+                    };
+                    kind = k;
+                },
+            }
+        }
+        keys.push(SortKey {
+            sel,
+            descending,
+            kind,
+            // by default, nulls are the smallest value
+            nulls_last: nulls.unwrap_or(descending),
+            prefer_dmy,
+        });
+    }
+    Ok(keys)
+}
+
+/// Encode the sort keys of `record` into `buf`, replacing its contents.
+/// Comparing two encoded buffers bytewise orders the records as the keys specify.
+pub fn encode_sort_keys(keys: &[SortKey], record: &ByteRecord, buf: &mut Vec<u8>) {
+    buf.clear();
+    for key in keys {
+        for field in key.sel.select(record) {
+            key.encode_field(field, buf);
+        }
+    }
+}
+
+impl SortKey {
+    /// An ascending numeric key on the selected columns, as with `--numeric`
+    pub fn numeric(sel: Selection) -> Self {
+        Self {
+            sel,
+            descending: false,
+            kind: SortKeyKind::Num,
+            nulls_last: false,
+            prefer_dmy: false,
+        }
+    }
+
+    fn encode_field(&self, field: &[u8], buf: &mut Vec<u8>) {
+        let start = buf.len();
+        buf.push(VALUE_TAG);
+        let is_value = !field.is_empty()
+            && match self.kind {
+                SortKeyKind::Str => {
+                    push_escaped(field, buf);
+                    true
+                },
+                SortKeyKind::IStr => {
+                    if let Ok(s) = from_utf8(field) {
+                        push_escaped(s.to_lowercase().as_bytes(), buf);
+                    } else {
+                        push_escaped(&field.to_ascii_lowercase(), buf);
+                    }
+                    true
+                },
+                SortKeyKind::Num => match parse_num(field) {
+                    Some(Int(i)) => {
+                        push_int(i, buf);
+                        true
+                    },
+                    Some(Float(f)) if !f.is_nan() => {
+                        push_float(f, buf);
+                        // a float is its own nearest float, see push_int
+                        push_residual(0, buf);
+                        true
+                    },
+                    _ => false,
+                },
+                SortKeyKind::Natural => {
+                    push_natural(field, buf);
+                    true
+                },
+                SortKeyKind::Version => {
+                    push_version(field, buf);
+                    true
+                },
+                SortKeyKind::Date => match from_utf8(field)
+                    .ok()
+                    .and_then(|s| parse_with_preference(s.trim(), self.prefer_dmy).ok())
+                {
+                    Some(dt) => {
+                        #[allow(clippy::cast_sign_loss)]
+                        let micros = dt.timestamp_micros() as u64 ^ (1 << 63);
+                        buf.extend_from_slice(&micros.to_be_bytes());
+                        true
+                    },
+                    None => false,
+                },
+            };
+
+        if !is_value {
+            buf.truncate(start);
+            buf.push(if self.nulls_last {
+                NULL_LAST_TAG
             } else {
-                // If parsing as i64 failed, try parsing as f64
-                if let Ok(f) = from_utf8(bytes).unwrap().parse::<f64>() {
-                    Some(Number::Float(f))
-                } else {
-                    None
-                }
+                NULL_FIRST_TAG
+            });
+        } else if self.descending {
+            for b in &mut buf[start + 1..] {
+                *b = !*b;
             }
+        }
+    }
+}
+
+/// escape NUL bytes and terminate with a double NUL, so shorter strings sort first
+#[inline]
+fn push_escaped(bytes: &[u8], buf: &mut Vec<u8>) {
+    for &b in bytes {
+        buf.push(b);
+        if b == 0 {
+            buf.push(0xff);
+        }
+    }
+    buf.extend_from_slice(&[0, 0]);
+}
+
+/// NaN must be handled by the caller
+#[inline]
+fn push_float(f: f64, buf: &mut Vec<u8>) {
+    // normalize -0.0, then flip the bits so the IEEE 754 layout sorts bytewise
+    let bits = if f == 0.0 { 0.0_f64 } else { f }.to_bits();
+    let bits = if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | (1 << 63)
+    };
+    buf.extend_from_slice(&bits.to_be_bytes());
+}
+
+/// Integers are encoded as their nearest float, which orders them against floats,
+/// followed by the difference between the integer and that float, so that integers
+/// beyond 2^53 (e.g. 64-bit ids) that round to the same float still sort exactly.
+#[inline]
+fn push_int(i: i64, buf: &mut Vec<u8>) {
+    #[allow(clippy::cast_precision_loss)]
+    let f = i as f64;
+    push_float(f, buf);
+    // the float is integral and at most 2^63, so at most 1024 away from the integer
+    #[allow(clippy::cast_possible_truncation)]
+    push_residual((i128::from(i) - f as i128) as i16, buf);
+}
+
+#[inline]
+fn push_residual(residual: i16, buf: &mut Vec<u8>) {
+    #[allow(clippy::cast_sign_loss)]
+    buf.extend_from_slice(&(residual as u16 ^ (1 << 15)).to_be_bytes());
+}
+
+/// digit runs are compared by their numeric value, everything else bytewise
+fn push_natural(bytes: &[u8], buf: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        if bytes[i].is_ascii_digit() {
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits = &bytes[start..i];
+            let digits = &digits[digits.iter().take_while(|&&b| b == b'0').count()..];
+            // longer digit runs are larger numbers, equal lengths compare digit by digit
+            let len = u16::try_from(digits.len()).unwrap_or(u16::MAX);
+            buf.push(NATURAL_NUM);
+            buf.extend_from_slice(&len.to_be_bytes());
+            buf.extend_from_slice(&digits[..len as usize]);
+        } else {
+            while i < bytes.len() && !bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            buf.push(NATURAL_TEXT);
+            push_escaped(&bytes[start..i], buf);
+        }
+    }
+    buf.push(NATURAL_END);
+}
+
+/// natural ordering of the version core, with pre-releases (e.g. 1.0.0-rc1) sorting
+/// before the release itself. A leading "v" and any build metadata are ignored.
+fn push_version(bytes: &[u8], buf: &mut Vec<u8>) {
+    let mut version = bytes;
+    if let [b'v' | b'V', next, ..] = version {
+        if next.is_ascii_digit() {
+            version = &version[1..];
+        }
+    }
+    if let Some(pos) = version.iter().position(|&b| b == b'+') {
+        version = &version[..pos];
+    }
+    match version.iter().position(|&b| b == b'-') {
+        Some(pos) => {
+            push_natural(&version[..pos], buf);
+            buf.push(0x01);
+            push_natural(&version[pos + 1..], buf);
+        },
+        None => {
+            push_natural(version, buf);
+            buf.push(0x02);
         },
-        None => None,
     }
 }
@@ -30,6 +30,16 @@ sort options:
     -s, --select <arg>      Select a subset of columns to check for sort.
                             See 'qsv select --help' for the format details.
     -i, --ignore-case       Compare strings disregarding case
+    --collate <locale>      Compare strings with the Unicode Collation Algorithm,
+                            using the collation rules of <locale> (e.g. en, de, sv).
+                            See 'qsv sort --help' for details.
+    --accent-insensitive    Compare strings disregarding accents (e.g. "é" = "e").
+                            Uses the root collation if --collate is not set.
+    -k, --keys <spec>       Check the sort order using per-column key specifications,
+                            e.g. -k date:desc:date,amount:asc:num,name:asc:natural
+                            See 'qsv sort --help' for the format details.
+                            Takes precedence over --select and cannot be combined
+                            with --ignore-case, --collate or --accent-insensitive.
     --all                   Check all records. Do not stop/short-circuit the check 
                             on the first unsorted record.
     --json                  Return results in JSON format, scanning --all records. 
@@ -62,7 +72,10 @@ use indicatif::{HumanCount, ProgressBar, ProgressDrawTarget};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    cmd::{dedup, sort::iter_cmp},
+    cmd::{
+        dedup,
+        sort::{encode_sort_keys, iter_cmp, iter_cmp_collate, new_collator, parse_sort_keys},
+    },
     config::{Config, Delimiter},
     select::SelectColumns,
     util, CliResult,
@@ -71,15 +84,18 @@ use crate::{
 #[allow(dead_code)]
 #[derive(Deserialize)]
 struct Args {
-    arg_input:        Option<String>,
-    flag_select:      SelectColumns,
-    flag_ignore_case: bool,
-    flag_all:         bool,
-    flag_no_headers:  bool,
-    flag_delimiter:   Option<Delimiter>,
-    flag_progressbar: bool,
-    flag_json:        bool,
-    flag_pretty_json: bool,
+    arg_input:               Option<String>,
+    flag_select:             SelectColumns,
+    flag_ignore_case:        bool,
+    flag_collate:            Option<String>,
+    flag_accent_insensitive: bool,
+    flag_keys:               Option<String>,
+    flag_all:                bool,
+    flag_no_headers:         bool,
+    flag_delimiter:          Option<Delimiter>,
+    flag_progressbar:        bool,
+    flag_json:               bool,
+    flag_pretty_json:        bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -102,6 +118,26 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     let headers = rdr.byte_headers()?.clone();
     let sel = rconfig.selection(&headers)?;
+    let sort_keys = if let Some(ref spec) = args.flag_keys {
+        if ignore_case || args.flag_collate.is_some() || args.flag_accent_insensitive {
+            return fail_incorrectusage_clierror!(
+                "--keys cannot be combined with --ignore-case, --collate or --accent-insensitive."
+            );
+        }
+        Some(parse_sort_keys(
+            spec,
+            &headers,
+            !args.flag_no_headers,
+            rconfig.get_dmy_preference(),
+        )?)
+    } else {
+        None
+    };
+    let collator = new_collator(
+        args.flag_collate.as_deref(),
+        ignore_case,
+        args.flag_accent_insensitive,
+    )?;
     let record_count;
 
     // prep progress bar
@@ -130,12 +166,17 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     let mut record = ByteRecord::new();
     let mut next_record = ByteRecord::new();
+    let mut key = Vec::new();
+    let mut next_key = Vec::new();
     let mut sorted = true;
     let mut scan_ctr: u64 = 0;
     let mut dupe_count: u64 = 0;
     let mut unsorted_breaks: u64 = 0;
 
     rdr.read_byte_record(&mut record)?;
+    if let Some(ref keys) = sort_keys {
+        encode_sort_keys(keys, &record, &mut key);
+    }
     loop {
         #[cfg(any(feature = "feature_capable", feature = "lite"))]
         if show_progress {
@@ -146,12 +187,19 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
         if !more_records {
             break;
         };
-        let a = sel.select(&record);
-        let b = sel.select(&next_record);
-        let comparison = if ignore_case {
-            dedup::iter_cmp_ignore_case(a, b)
+        let comparison = if let Some(ref keys) = sort_keys {
+            encode_sort_keys(keys, &next_record, &mut next_key);
+            key.cmp(&next_key)
         } else {
-            iter_cmp(a, b)
+            let a = sel.select(&record);
+            let b = sel.select(&next_record);
+            if let Some(ref collator) = collator {
+                iter_cmp_collate(collator, a, b)
+            } else if ignore_case {
+                dedup::iter_cmp_ignore_case(a, b)
+            } else {
+                iter_cmp(a, b)
+            }
         };
 
         match comparison {
@@ -160,12 +208,14 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
             },
             cmp::Ordering::Less => {
                 record.clone_from(&next_record);
+                std::mem::swap(&mut key, &mut next_key);
             },
             cmp::Ordering::Greater => {
                 sorted = false;
                 if args.flag_all || do_json {
                     unsorted_breaks += 1;
                     record.clone_from(&next_record);
+                    std::mem::swap(&mut key, &mut next_key);
                 } else {
                     break;
                 }
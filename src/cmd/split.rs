@@ -1,6 +1,6 @@
 static USAGE: &str = r#"
-Splits the given CSV data into chunks. It has three modes: by size (rowcount),
-by number of chunks and by kb-size.
+Splits the given CSV data into chunks. It has four modes: by size (rowcount),
+by number of chunks, by kb-size and by ratios.
 
 See `partition` command for splitting by a column value.
 
@@ -20,6 +20,18 @@ desired size.
 Uses multithreading to go faster if the CSV has an index when splitting by size or
 by number of chunks. Splitting by kb-size is always done sequentially with a single thread.
 
+When splitting by ratios, the records are randomly assigned to named splits (e.g. for
+train/test/validation sets), with the number of records in each split proportional to the
+given ratios. The splits can be:
+  * stratified with --stratify, so each split has the same distribution of the values of
+    a label column as the whole data set (the ratios are applied to each label separately).
+  * grouped with --group, so records with the same group key are always in the same split.
+    The ratios are then applied to the number of groups rather than records.
+The files are named after the splits (e.g. train.csv, test.csv and validation.csv), and a
+JSON manifest with the number of records (and label distribution, if stratified) of each
+split is written to the output directory. Splitting by ratios reads the input twice, so it
+does not support stdin. It honors the --seed and --rng options for reproducible splits.
+
 The default is to split by size with a chunk size of 500.
 
 The files are written to the directory given with the name '{start}.csv',
@@ -48,10 +60,17 @@ Examples:
 
     qsv split splitoutdir -c 10 -j 4 input.csv
 
+    qsv split mlsplits --ratios 70,15,15 --stratify label --seed 42 input.csv
+    # This will create train.csv, test.csv, validation.csv & manifest.json in the
+    # directory 'mlsplits', with a 70/15/15 split of each label.
+
+    qsv split mlsplits -r 0.8,0.2 --split-names train,test --group patient_id input.csv
+    # This will create train.csv & test.csv, with all the records of a patient in the same file.
+
 For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_split.rs.
 
 Usage:
-    qsv split [options] (--size <arg> | --chunks <arg> | --kb-size <arg>) <outdir> [<input>]
+    qsv split [options] (--size <arg> | --chunks <arg> | --kb-size <arg> | --ratios <arg>) <outdir> [<input>]
     qsv split --help
 
 split arguments:
@@ -74,6 +93,24 @@ split options:
                            in each chunk may vary, but the size of each chunk will
                            not exceed the desired size.
                            This option is mutually exclusive with --size and --chunks.
+    -r, --ratios <arg>     Randomly split the records by the given comma-separated ratios,
+                           e.g. 70,15,15 or 0.7,0.15,0.15. The ratios are normalized,
+                           so they don't need to add up to 1 or 100.
+                           This option is mutually exclusive with the other split modes.
+
+                           RATIOS MODE OPTIONS:
+    --split-names <names>  The comma-separated names of the splits, in the order of --ratios.
+                           The names replace the '{}' in --filename.
+                           [default: train,test,validation]
+    --stratify <col>       Stratify the splits by the values of the <col> label column.
+    --group <cols>         Keep the records with the same values in the <cols> column/s
+                           in the same split.
+    --manifest <file>      The name of the manifest written to <outdir>.
+                           [default: manifest.json]
+    --seed <number>        Random Number Generator (RNG) seed.
+    --rng <kind>           The RNG algorithm to use: standard, faster or cryptosecure.
+                           See 'qsv sample --help' for details.
+                           [default: standard]
 
     -j, --jobs <arg>       The number of splitting jobs to run in parallel.
                            This only works when the given CSV data has
@@ -84,7 +121,8 @@ split options:
     --filename <filename>  A filename template to use when constructing
                            the names of the output files.  The string '{}'
                            will be replaced by the zero-based row number
-                           of the first row in the chunk.
+                           of the first row in the chunk, or by the split name
+                           when splitting by ratios.
                            [default: {}.csv]
     --pad <arg>            The zero padding width that is used in the
                            generated filename.
@@ -100,31 +138,46 @@ Common options:
     -Q, --quiet            Do not display an output summary to stderr.
 "#;
 
-use std::{fs, io, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
 
+use rand::seq::SliceRandom;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use serde::Deserialize;
 
 use crate::{
+    cmd::sample::{init_rng, RngKind},
     config::{Config, Delimiter},
     index::Indexed,
+    select::SelectColumns,
     util::{self, FilenameTemplate},
     CliResult,
 };
 
 #[derive(Clone, Deserialize)]
 struct Args {
-    arg_input:       Option<String>,
-    arg_outdir:      String,
-    flag_size:       usize,
-    flag_chunks:     Option<usize>,
-    flag_kb_size:    Option<usize>,
-    flag_jobs:       Option<usize>,
-    flag_filename:   FilenameTemplate,
-    flag_pad:        usize,
-    flag_no_headers: bool,
-    flag_delimiter:  Option<Delimiter>,
-    flag_quiet:      bool,
+    arg_input:        Option<String>,
+    arg_outdir:       String,
+    flag_size:        usize,
+    flag_chunks:      Option<usize>,
+    flag_kb_size:     Option<usize>,
+    flag_ratios:      Option<String>,
+    flag_split_names: String,
+    flag_stratify:    Option<SelectColumns>,
+    flag_group:       Option<SelectColumns>,
+    flag_manifest:    String,
+    flag_seed:        Option<u64>,
+    flag_rng:         String,
+    flag_jobs:        Option<usize>,
+    flag_filename:    FilenameTemplate,
+    flag_pad:         usize,
+    flag_no_headers:  bool,
+    flag_delimiter:   Option<Delimiter>,
+    flag_quiet:       bool,
 }
 
 pub fn run(argv: &[&str]) -> CliResult<()> {
@@ -140,7 +193,9 @@ pub fn run(argv: &[&str]) -> CliResult<()> {
 
     fs::create_dir_all(&args.arg_outdir)?;
 
-    if let Some(kb_size) = args.flag_kb_size {
+    if let Some(ref ratios) = args.flag_ratios {
+        args.split_by_ratios(ratios)
+    } else if let Some(kb_size) = args.flag_kb_size {
         args.split_by_kb_size(kb_size)
     } else {
         // we're splitting by rowcount or by number of chunks
@@ -327,14 +382,190 @@ impl Args {
         Ok(())
     }
 
+    fn split_by_ratios(&self, ratios: &str) -> CliResult<()> {
+        if self.arg_input.is_none() {
+            return fail_incorrectusage_clierror!(
+                "Splitting by ratios reads the input twice, so it does not support stdin."
+            );
+        }
+        let Ok(rng_kind) = RngKind::from_str(&self.flag_rng) else {
+            return fail_incorrectusage_clierror!(
+                "Invalid RNG algorithm `{}`. Supported RNGs are: standard, faster, cryptosecure.",
+                self.flag_rng
+            );
+        };
+
+        let ratios = ratios
+            .split(',')
+            .map(|r| r.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .filter(|r| !r.is_empty() && r.iter().all(|r| r.is_finite() && *r > 0.0));
+        let Some(mut ratios) = ratios else {
+            return fail_incorrectusage_clierror!(
+                "--ratios must be a comma-separated list of positive numbers."
+            );
+        };
+        let total: f64 = ratios.iter().sum();
+        for ratio in &mut ratios {
+            *ratio /= total;
+        }
+        let names: Vec<&str> = self.flag_split_names.split(',').map(str::trim).collect();
+        if names.len() < ratios.len() {
+            return fail_incorrectusage_clierror!(
+                "There are {} ratios but only {} --split-names.",
+                ratios.len(),
+                names.len()
+            );
+        }
+        let names = &names[..ratios.len()];
+
+        let rconfig = self.rconfig();
+        let mut rdr = rconfig.reader()?;
+        let headers = rdr.byte_headers()?.clone();
+        let label_col = match self.flag_stratify {
+            Some(ref stratify) => {
+                let sel = stratify.selection(&headers, !self.flag_no_headers)?;
+                if sel.len() != 1 {
+                    return fail_incorrectusage_clierror!(
+                        "--stratify must select exactly one column."
+                    );
+                }
+                Some(sel[0])
+            },
+            None => None,
+        };
+        let group_sel = match self.flag_group {
+            Some(ref group) => Some(group.selection(&headers, !self.flag_no_headers)?),
+            None => None,
+        };
+
+        // first pass: find the units to assign to the splits (records, or groups of records)
+        // and the label of each unit, i.e. the label of its first record
+        let mut units = RatioUnits::default();
+        let mut row = csv::ByteRecord::new();
+        while rdr.read_byte_record(&mut row)? {
+            let label = label_col.map(|c| row.get(c).unwrap_or_default());
+            match group_sel {
+                Some(ref sel) => {
+                    units.add_grouped(sel.select(&row).map(<[u8]>::to_vec).collect(), label);
+                },
+                None => units.add(label),
+            }
+        }
+
+        // shuffle the units of each label and allocate them to the splits by ratio
+        let mut rng = init_rng(self.flag_seed, &rng_kind);
+        let mut assignment = vec![0_usize; units.unit_labels.len()];
+        let mut strata: Vec<Vec<usize>> = vec![Vec::new(); units.labels.len().max(1)];
+        for (unit, &label) in units.unit_labels.iter().enumerate() {
+            strata[label].push(unit);
+        }
+        for stratum in &mut strata {
+            stratum.shuffle(rng.as_mut());
+            let mut start = 0;
+            for (split, count) in allocate(stratum.len(), &ratios).into_iter().enumerate() {
+                for &unit in &stratum[start..start + count] {
+                    assignment[unit] = split;
+                }
+                start += count;
+            }
+        }
+
+        // second pass: write the records to their splits
+        let mut wtrs = names
+            .iter()
+            .map(|name| self.new_named_writer(&headers, name))
+            .collect::<CliResult<Vec<_>>>()?;
+        let mut split_rows = vec![0_u64; names.len()];
+        let mut split_labels: Vec<BTreeMap<String, u64>> = vec![BTreeMap::new(); names.len()];
+        let mut rdr = rconfig.reader()?;
+        let mut rowno = 0;
+        while rdr.read_byte_record(&mut row)? {
+            let unit = match group_sel {
+                Some(ref sel) => {
+                    let key: Vec<Vec<u8>> = sel.select(&row).map(<[u8]>::to_vec).collect();
+                    units.group_units[&key]
+                },
+                None => rowno,
+            };
+            let split = assignment[unit];
+            wtrs[split].write_byte_record(&row)?;
+            split_rows[split] += 1;
+            if let Some(c) = label_col {
+                let label = String::from_utf8_lossy(row.get(c).unwrap_or_default()).to_string();
+                *split_labels[split].entry(label).or_default() += 1;
+            }
+            rowno += 1;
+        }
+        for wtr in &mut wtrs {
+            wtr.flush()?;
+        }
+
+        let splits: Vec<serde_json::Value> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mut split = serde_json::json!({
+                    "name": name,
+                    "file": self.flag_filename.filename(name),
+                    "ratio": ratios[i],
+                    "rows": split_rows[i],
+                });
+                if label_col.is_some() {
+                    split["labels"] = serde_json::json!(split_labels[i]);
+                }
+                split
+            })
+            .collect();
+        let manifest = serde_json::json!({
+            "input": self.arg_input,
+            "seed": self.flag_seed,
+            "rng": self.flag_rng,
+            "stratify": label_col.map(|c| String::from_utf8_lossy(&headers[c]).to_string()),
+            "grouped": group_sel.is_some(),
+            "rows": rowno,
+            "splits": splits,
+        });
+        fs::write(
+            Path::new(&self.arg_outdir).join(&self.flag_manifest),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+
+        if !self.flag_quiet {
+            let counts: Vec<String> = names
+                .iter()
+                .zip(&split_rows)
+                .map(|(name, rows)| format!("{name}: {rows}"))
+                .collect();
+            eprintln!(
+                "Wrote {} split/s to '{}'. Num records: {} ({})",
+                names.len(),
+                Path::new(&self.arg_outdir).canonicalize()?.display(),
+                rowno,
+                counts.join(", ")
+            );
+        }
+
+        Ok(())
+    }
+
     fn new_writer(
         &self,
         headers: &csv::ByteRecord,
         start: usize,
         width: usize,
+    ) -> CliResult<csv::Writer<Box<dyn io::Write + 'static>>> {
+        self.new_named_writer(headers, &format!("{start:0>width$}"))
+    }
+
+    fn new_named_writer(
+        &self,
+        headers: &csv::ByteRecord,
+        name: &str,
     ) -> CliResult<csv::Writer<Box<dyn io::Write + 'static>>> {
         let dir = Path::new(&self.arg_outdir);
-        let path = dir.join(self.flag_filename.filename(&format!("{start:0>width$}")));
+        let path = dir.join(self.flag_filename.filename(name));
         let spath = Some(path.display().to_string());
         let mut wtr = Config::new(spath.as_ref()).writer()?;
         if !self.rconfig().no_headers {
@@ -349,3 +580,54 @@ impl Args {
             .no_headers(self.flag_no_headers)
     }
 }
+
+/// the units that are assigned to the splits when splitting by ratios.
+/// A unit is a record, or all the records of a group with --group.
+#[derive(Default)]
+struct RatioUnits {
+    /// the label id of each unit
+    unit_labels: Vec<usize>,
+    /// the id of each distinct label
+    labels:      HashMap<Vec<u8>, usize>,
+    /// the unit of each group key with --group
+    group_units: HashMap<Vec<Vec<u8>>, usize>,
+}
+
+impl RatioUnits {
+    fn label_id(&mut self, label: Option<&[u8]>) -> usize {
+        let Some(label) = label else {
+            return 0;
+        };
+        let next_id = self.labels.len();
+        *self.labels.entry(label.to_vec()).or_insert(next_id)
+    }
+
+    fn add(&mut self, label: Option<&[u8]>) {
+        let label_id = self.label_id(label);
+        self.unit_labels.push(label_id);
+    }
+
+    fn add_grouped(&mut self, key: Vec<Vec<u8>>, label: Option<&[u8]>) {
+        if !self.group_units.contains_key(&key) {
+            self.group_units.insert(key, self.unit_labels.len());
+            self.add(label);
+        }
+    }
+}
+
+/// allocate n items to splits proportionally to their (normalized) ratios,
+/// using the largest remainder method so the counts add up to n
+#[allow(clippy::cast_precision_loss)]
+fn allocate(n: usize, ratios: &[f64]) -> Vec<usize> {
+    let exact: Vec<f64> = ratios.iter().map(|r| r * n as f64).collect();
+    let mut counts: Vec<usize> = exact.iter().map(|e| e.floor() as usize).collect();
+    let remaining = n.saturating_sub(counts.iter().sum());
+    let mut by_remainder: Vec<usize> = (0..ratios.len()).collect();
+    // stable sort, so ties go to the earlier splits
+    by_remainder
+        .sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+    for &i in by_remainder.iter().cycle().take(remaining) {
+        counts[i] += 1;
+    }
+    counts
+}
@@ -0,0 +1,394 @@
+static USAGE: &str = r#"
+Select the top N records of every group of a CSV, e.g. the 10 largest transactions
+per customer, without sorting the whole file.
+
+The records are ranked by <keys>, which has the same format as the --keys option of
+`sort`: a comma-separated list of <column>[:asc|desc][:<type>][:nulls-first|nulls-last]
+keys, e.g. "amount:num" to rank by the numeric value of the amount column, or
+"day:date,amount:num" to rank by date, then amount. The types are str, istr, num,
+natural, version & date. See 'qsv sort --help' for details.
+
+By default, the records with the largest keys are selected, i.e. the records that would
+be last if the CSV was sorted with `qsv sort --keys <keys>`. With --smallest, the records
+with the smallest keys, i.e. the first ones, are selected.
+
+The records are grouped by the --group columns. While streaming through the CSV, a
+bounded heap of the top N records is kept for every group, so memory use is proportional
+to the number of groups times N, not to the size of the CSV. If the CSV is indexed, it's
+split in chunks that are processed in parallel, and the heaps of the chunks are merged.
+
+The output has the selected records of every group, from the best to the Nth, with the
+groups in the order of their first appearance in the CSV.
+
+Ties, i.e. records with the same key as the Nth record of a group, are handled
+with --ties:
+    first  Select the tied records that appear first in the CSV (the default).
+    last   Select the tied records that appear last in the CSV.
+    all    Select all the tied records, so a group can have more than N records.
+
+Examples:
+
+  # the 10 largest transactions per customer
+  $ qsv topn amount:num --group customer_id transactions.csv
+
+  # the 3 most recent orders per store, with all the orders of the 3rd most recent date
+  $ qsv topn order_date:date -g store --top 3 --ties all orders.csv
+
+  # the 5 smallest values of the whole file, with their rank
+  $ qsv topn value:num --smallest --top 5 --rank-col rank data.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_topn.rs.
+
+Usage:
+    qsv topn [options] <keys> [<input>]
+    qsv topn --help
+
+topn arguments:
+    <keys>                 The keys to rank the records by. See above.
+    <input>                The CSV to read. (default: stdin)
+
+topn options:
+    --top <n>              The number of records to select per group. [default: 10]
+    -g, --group <cols>     The columns to group the records by. When not set,
+                           the whole CSV is a single group.
+                           See 'qsv select --help' for the format details.
+    --smallest             Select the records with the smallest keys instead
+                           of the largest.
+    --ties <mode>          How to handle ties. One of first, last or all. [default: first]
+    --rank-col <name>      Add a column with this name, with the rank of every
+                           record in its group, starting at 1. Tied records have
+                           the same rank, e.g. 1, 2, 2, 4.
+    -j, --jobs <arg>       The number of jobs to run in parallel when the CSV is indexed.
+                           When not set, the number of jobs is set to the
+                           number of CPUs detected.
+
+Common options:
+    -h, --help             Display this message
+    -o, --output <file>    Write output to <file> instead of stdout.
+    -n, --no-headers       When set, the first row will not be interpreted
+                           as headers. Columns must then be selected by index.
+    -d, --delimiter <arg>  The field delimiter for reading CSV data.
+                           Must be a single character. (default: ,)
+"#;
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    fs,
+};
+
+use ahash::AHashMap;
+use crossbeam_channel;
+use csv::ByteRecord;
+use serde::Deserialize;
+use threadpool::ThreadPool;
+
+use crate::{
+    cmd::sort::{encode_sort_keys, parse_sort_keys, SortKey},
+    config::{Config, Delimiter},
+    index::Indexed,
+    select::{SelectColumns, Selection},
+    util, CliResult,
+};
+
+#[derive(Clone, Deserialize)]
+struct Args {
+    arg_keys:        String,
+    arg_input:       Option<String>,
+    flag_top:        usize,
+    flag_group:      Option<SelectColumns>,
+    flag_smallest:   bool,
+    flag_ties:       String,
+    flag_rank_col:   Option<String>,
+    flag_jobs:       Option<usize>,
+    flag_output:     Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter:  Option<Delimiter>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Ties {
+    First,
+    Last,
+    All,
+}
+
+/// The settings needed to rank the records, shared by all the jobs
+#[derive(Clone)]
+struct Ranking {
+    keys:     Vec<SortKey>,
+    group:    Option<Selection>,
+    top:      usize,
+    smallest: bool,
+    ties:     Ties,
+}
+
+/// A candidate record of a group. The greater entry is the better one.
+struct Entry {
+    /// the encoded sort keys, inverted with --smallest
+    key:      Vec<u8>,
+    /// breaks ties between equal keys, derived from the position of the record
+    tiebreak: u64,
+    record:   ByteRecord,
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key
+            .cmp(&other.key)
+            .then(self.tiebreak.cmp(&other.tiebreak))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+/// The top records of a group, with the worst one at the root of the heap
+struct TopGroup {
+    /// the position of the first record of the group, to output groups in input order
+    first_seen: u64,
+    heap:       BinaryHeap<Reverse<Entry>>,
+    /// with --ties all, the records beyond the top N with the same key as the worst one
+    ties:       Vec<Entry>,
+}
+
+type Groups = AHashMap<Vec<Vec<u8>>, TopGroup>;
+
+impl TopGroup {
+    fn new(first_seen: u64, top: usize) -> Self {
+        Self {
+            first_seen,
+            heap: BinaryHeap::with_capacity(top + 1),
+            ties: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, entry: Entry, ranking: &Ranking) {
+        if self.heap.len() < ranking.top {
+            self.heap.push(Reverse(entry));
+            return;
+        }
+        // safety: top is at least 1, so the heap is not empty
+        let worst = &self.heap.peek().unwrap().0;
+        let keep_ties = ranking.ties == Ties::All;
+        if entry > *worst {
+            let same_key = entry.key == worst.key;
+            let Reverse(evicted) = self.heap.pop().unwrap();
+            self.heap.push(Reverse(entry));
+            if keep_ties {
+                if !same_key {
+                    // the worst key may have changed, so drop the ties of the previous one
+                    let worst_key = &self.heap.peek().unwrap().0.key;
+                    self.ties.retain(|t| t.key == *worst_key);
+                    if evicted.key != *worst_key {
+                        return;
+                    }
+                }
+                self.ties.push(evicted);
+            }
+        } else if keep_ties && entry.key == worst.key {
+            self.ties.push(entry);
+        }
+    }
+
+    fn merge(&mut self, other: TopGroup, ranking: &Ranking) {
+        self.first_seen = self.first_seen.min(other.first_seen);
+        for Reverse(entry) in other.heap {
+            self.push(entry, ranking);
+        }
+        for entry in other.ties {
+            self.push(entry, ranking);
+        }
+    }
+
+    /// The selected records, from the best to the worst
+    fn into_sorted(self) -> Vec<Entry> {
+        let mut entries: Vec<Entry> = self.heap.into_iter().map(|Reverse(e)| e).collect();
+        entries.extend(self.ties);
+        entries.sort_unstable_by(|a, b| b.cmp(a));
+        entries
+    }
+}
+
+impl Ranking {
+    fn entry(&self, record: ByteRecord, pos: u64) -> Entry {
+        let mut key = Vec::new();
+        encode_sort_keys(&self.keys, &record, &mut key);
+        if self.smallest {
+            // the encoding is prefix-free, so inverting it reverses the order
+            for b in &mut key {
+                *b = !*b;
+            }
+        }
+        Entry {
+            key,
+            tiebreak: if self.ties == Ties::Last {
+                pos
+            } else {
+                u64::MAX - pos
+            },
+            record,
+        }
+    }
+
+    /// Compute the top records of every group of the records of `it`,
+    /// the first one being at position `start` in the CSV
+    fn top_groups<I>(&self, it: I, start: u64) -> CliResult<Groups>
+    where
+        I: Iterator<Item = csv::Result<ByteRecord>>,
+    {
+        let mut groups = Groups::new();
+        for (pos, record) in (start..).zip(it) {
+            let record = record?;
+            let group_key: Vec<Vec<u8>> = match self.group {
+                Some(ref sel) => sel.select(&record).map(<[u8]>::to_vec).collect(),
+                None => Vec::new(),
+            };
+            let entry = self.entry(record, pos);
+            groups
+                .entry(group_key)
+                .or_insert_with(|| TopGroup::new(pos, self.top))
+                .push(entry, self);
+        }
+        Ok(groups)
+    }
+}
+
+impl Args {
+    fn rconfig(&self) -> Config {
+        Config::new(self.arg_input.as_ref())
+            .delimiter(self.flag_delimiter)
+            .no_headers(self.flag_no_headers)
+    }
+
+    fn parallel_top_groups(
+        &self,
+        idx: &Indexed<fs::File, fs::File>,
+        ranking: &Ranking,
+    ) -> CliResult<Groups> {
+        let idx_count = idx.count() as usize;
+        if idx_count == 0 {
+            return Ok(Groups::new());
+        }
+
+        let njobs = util::njobs(self.flag_jobs);
+        let chunk_size = util::chunk_size(idx_count, njobs);
+        let nchunks = util::num_of_chunks(idx_count, chunk_size);
+
+        let pool = ThreadPool::new(njobs);
+        let (send, recv) = crossbeam_channel::bounded(0);
+        for i in 0..nchunks {
+            let (send, args, ranking) = (send.clone(), self.clone(), ranking.clone());
+            pool.execute(move || {
+                // safety: we know the file is indexed and seekable
+                let mut idx = args.rconfig().indexed().unwrap().unwrap();
+                let start = (i * chunk_size) as u64;
+                idx.seek(start).unwrap();
+                let it = idx.byte_records().take(chunk_size);
+                send.send(ranking.top_groups(it, start)).unwrap();
+            });
+        }
+        drop(send);
+
+        let mut groups = Groups::new();
+        for chunk_groups in recv {
+            for (group_key, chunk_group) in chunk_groups? {
+                match groups.get_mut(&group_key) {
+                    Some(group) => group.merge(chunk_group, ranking),
+                    None => {
+                        groups.insert(group_key, chunk_group);
+                    },
+                }
+            }
+        }
+        Ok(groups)
+    }
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+
+    if args.flag_top == 0 {
+        return fail_incorrectusage_clierror!("--top must be at least 1.");
+    }
+    let ties = match args.flag_ties.to_lowercase().as_str() {
+        "first" => Ties::First,
+        "last" => Ties::Last,
+        "all" => Ties::All,
+        _ => {
+            return fail_incorrectusage_clierror!(
+                "Invalid --ties mode: {}. Valid modes are first, last and all.",
+                args.flag_ties
+            )
+        },
+    };
+
+    let rconfig = args.rconfig();
+    let mut rdr = rconfig.reader()?;
+    let headers = rdr.byte_headers()?.clone();
+    let use_names = !args.flag_no_headers;
+    let group = match args.flag_group {
+        Some(ref cols) => Some(cols.selection(&headers, use_names)?),
+        None => None,
+    };
+    let ranking = Ranking {
+        keys: parse_sort_keys(
+            &args.arg_keys,
+            &headers,
+            use_names,
+            rconfig.get_dmy_preference(),
+        )?,
+        group,
+        top: args.flag_top,
+        smallest: args.flag_smallest,
+        ties,
+    };
+
+    let groups = match rconfig.indexed()? {
+        Some(ref idx) if util::njobs(args.flag_jobs) > 1 => {
+            args.parallel_top_groups(idx, &ranking)?
+        },
+        _ => ranking.top_groups(rdr.into_byte_records(), 0)?,
+    };
+
+    let mut groups: Vec<TopGroup> = groups.into_values().collect();
+    groups.sort_unstable_by_key(|g| g.first_seen);
+
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+    if !rconfig.no_headers {
+        let mut headers = headers;
+        if let Some(ref rank_col) = args.flag_rank_col {
+            headers.push_field(rank_col.as_bytes());
+        }
+        wtr.write_byte_record(&headers)?;
+    }
+
+    let mut itoa_buf = itoa::Buffer::new();
+    for group in groups {
+        let mut rank = 0;
+        let mut prev_key: Option<Vec<u8>> = None;
+        for (i, mut entry) in group.into_sorted().into_iter().enumerate() {
+            if args.flag_rank_col.is_some() {
+                if prev_key.as_ref() != Some(&entry.key) {
+                    rank = i + 1;
+                }
+                entry.record.push_field(itoa_buf.format(rank).as_bytes());
+                prev_key = Some(entry.key);
+            }
+            wtr.write_byte_record(&entry.record)?;
+        }
+    }
+
+    Ok(wtr.flush()?)
+}
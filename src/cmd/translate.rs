@@ -0,0 +1,299 @@
+static USAGE: &str = r#"
+Translates the values of a column with a lookup table, e.g. to map codes to labels.
+
+The lookup table is a CSV with a key column, with the values to look up, and one or more
+value columns, with their translations. It can be a local file, a URL, a datHere lookup
+table (dathere://) or a CKAN resource (ckan://), and is cached like the lookup tables of
+the `luau` qsv_register_lookup helper and the `validate` dynamicEnum keyword, under a
+name derived from the full URI of the lookup table.
+See https://github.com/dathere/qsv-lookup-tables for the datHere lookup tables.
+
+By default, the values of <column> are replaced with their translation. With --append,
+the value columns of the lookup table are appended after the existing columns instead.
+
+Values that are not in the lookup table are kept as is (empty with --append), unless
+--default is set. The distinct values that are not in the lookup table can be written
+with their number of occurrences to a report with --unmatched.
+
+Examples:
+
+  # replace the country codes of the country column with country names
+  $ qsv translate country countries.csv data.csv
+
+  # append the name & region columns of the lookup table, looking up the code column
+  $ qsv translate country countries.csv --key-col code --value-col name,region --append data.csv
+
+  # use a datHere lookup table, reporting the codes that are not in it
+  $ qsv translate state dathere://us-states-example.csv --unmatched unmatched.csv data.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_translate.rs.
+
+Usage:
+    qsv translate [options] <column> <lookup-table> [<input>]
+    qsv translate --help
+
+translate arguments:
+    <column>                The column with the values to translate.
+                            See 'qsv select --help' for the format details.
+    <lookup-table>          The lookup table. A local file, an http/https URL,
+                            a dathere:// or a ckan:// URI.
+    <input>                 The CSV file to read. If not given, reads from stdin.
+
+translate options:
+    --key-col <col>         The column of the lookup table with the values to look up.
+                            (default: the first column)
+    --value-col <cols>      The columns of the lookup table with the translations.
+                            Only one column can be selected without --append.
+                            (default: the second column)
+    -a, --append            Append the value columns after the existing columns
+                            instead of replacing the values of <column>.
+    --prefix <arg>          With --append, prefix the names of the appended columns
+                            with <arg>, e.g. to avoid clashes with existing columns.
+    --default <arg>         The translation of the values that are not in the lookup table.
+    -i, --ignore-case       Look up the values case-insensitively.
+    --unmatched <file>      Write the distinct values that are not in the lookup table,
+                            with their number of occurrences, to <file>.
+    --cache-age <secs>      The number of seconds a downloaded lookup table is cached.
+                            Set to 0 to always use the cached lookup table, or to a
+                            negative number to always download it. [default: 3600]
+    --cache-dir <dir>       The directory to use for caching downloaded lookup tables.
+                            If the directory does not exist, qsv will attempt to create it.
+                            If the QSV_CACHE_DIR envvar is set, it will be used instead.
+                            [default: ~/.qsv-cache]
+    --ckan-api <url>        The URL of the CKAN API to use for downloading lookup tables
+                            with the "ckan://" scheme.
+                            If the QSV_CKAN_API envvar is set, it will be used instead.
+                            [default: https://data.dathere.com/api/3/action]
+    --ckan-token <token>    The CKAN API token to use. Only required if downloading
+                            private resources.
+                            If the QSV_CKAN_TOKEN envvar is set, it will be used instead.
+    --timeout <secs>        Timeout for downloading lookup tables on URLs. [default: 30]
+
+Common options:
+    -h, --help              Display this message
+    -o, --output <file>     Write output to <file> instead of stdout.
+    -n, --no-headers        When set, the first row will not be interpreted
+                            as headers. The lookup table must still have headers.
+    -d, --delimiter <arg>   The field delimiter for reading CSV data.
+                            Must be a single character. (default: ,)
+"#;
+
+use std::borrow::Cow;
+
+use ahash::AHashMap;
+use csv::ByteRecord;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::{
+    config::{Config, Delimiter},
+    lookup::{self, load_lookup_table, LookupTableOptions},
+    select::SelectColumns,
+    util, CliResult,
+};
+
+#[derive(Deserialize)]
+struct Args {
+    arg_column:       SelectColumns,
+    arg_lookup_table: String,
+    arg_input:        Option<String>,
+    flag_key_col:     Option<SelectColumns>,
+    flag_value_col:   Option<SelectColumns>,
+    flag_append:      bool,
+    flag_prefix:      Option<String>,
+    flag_default:     Option<String>,
+    flag_ignore_case: bool,
+    flag_unmatched:   Option<String>,
+    flag_cache_age:   i64,
+    flag_cache_dir:   String,
+    flag_ckan_api:    String,
+    flag_ckan_token:  Option<String>,
+    flag_timeout:     u16,
+    flag_output:      Option<String>,
+    flag_no_headers:  bool,
+    flag_delimiter:   Option<Delimiter>,
+}
+
+/// Select columns of the lookup table, or only the column at `default_index` if none are given
+fn lookup_column(
+    cols: Option<SelectColumns>,
+    default_index: usize,
+    headers: &ByteRecord,
+    option: &str,
+) -> CliResult<Vec<usize>> {
+    match cols {
+        Some(cols) => Ok(cols.selection(headers, true)?.to_vec()),
+        None if default_index < headers.len() => Ok(vec![default_index]),
+        None => fail_incorrectusage_clierror!(
+            "The lookup table has no column {}. Use {option} to select its column.",
+            default_index + 1
+        ),
+    }
+}
+
+fn lookup_key(value: &[u8], ignore_case: bool) -> Cow<[u8]> {
+    if ignore_case {
+        Cow::Owned(String::from_utf8_lossy(value).to_lowercase().into_bytes())
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+
+    if args.flag_prefix.is_some() && !args.flag_append {
+        return fail_incorrectusage_clierror!("Cannot use --prefix without --append");
+    }
+
+    // load the lookup table, downloading it if required. The table is cached under its
+    // name and a hash of its full URI, so tables with the same name from different
+    // hosts or CKAN instances don't share a cache entry.
+    let basename = args
+        .arg_lookup_table
+        .trim_end_matches('?')
+        .rsplit('/')
+        .next()
+        .unwrap_or("translate")
+        .trim_end_matches(".csv");
+    let ckan_api_url = std::env::var("QSV_CKAN_API").unwrap_or(args.flag_ckan_api);
+    // a ckan:// URI is relative to the CKAN API, so it's part of the full URI
+    let full_uri = if args.arg_lookup_table.starts_with("ckan://") {
+        format!("{ckan_api_url}|{}", args.arg_lookup_table)
+    } else {
+        args.arg_lookup_table.clone()
+    };
+    let lookup_name = format!("{basename}-{:016x}", xxh3_64(full_uri.as_bytes()));
+    let opts = LookupTableOptions {
+        name:           lookup_name,
+        uri:            args.arg_lookup_table.clone(),
+        cache_age_secs: args.flag_cache_age,
+        cache_dir:      lookup::set_qsv_cache_dir(&args.flag_cache_dir)?,
+        delimiter:      None,
+        ckan_api_url:   Some(ckan_api_url),
+        ckan_token:     std::env::var("QSV_CKAN_TOKEN")
+            .ok()
+            .or(args.flag_ckan_token),
+        timeout_secs:   args.flag_timeout,
+    };
+    let lookup_result = match load_lookup_table(&opts) {
+        Ok(result) => result,
+        Err(e) => return fail_clierror!("Error loading lookup table: {e}"),
+    };
+
+    let mut lookup_rdr = Config::new(Some(lookup_result.filepath).as_ref())
+        .comment(Some(b'#'))
+        .no_headers(false)
+        .reader()?;
+    let lookup_headers = lookup_rdr.byte_headers()?.clone();
+    let key_col = lookup_column(args.flag_key_col, 0, &lookup_headers, "--key-col")?;
+    if key_col.len() != 1 {
+        return fail_incorrectusage_clierror!("Only one --key-col column can be selected.");
+    }
+    let key_col = key_col[0];
+    let value_cols = lookup_column(args.flag_value_col, 1, &lookup_headers, "--value-col")?;
+    if value_cols.len() != 1 && !args.flag_append {
+        return fail_incorrectusage_clierror!(
+            "Only one --value-col column can be selected without --append."
+        );
+    }
+
+    // the first translation of a key wins
+    let mut translations: AHashMap<Vec<u8>, Vec<Vec<u8>>> = AHashMap::new();
+    let mut lookup_record = ByteRecord::new();
+    while lookup_rdr.read_byte_record(&mut lookup_record)? {
+        let key = lookup_key(&lookup_record[key_col], args.flag_ignore_case).into_owned();
+        translations.entry(key).or_insert_with(|| {
+            value_cols
+                .iter()
+                .map(|&i| lookup_record.get(i).unwrap_or_default().to_vec())
+                .collect()
+        });
+    }
+
+    let rconfig = Config::new(args.arg_input.as_ref())
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers)
+        .select(args.arg_column);
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+
+    let mut headers = rdr.byte_headers()?.clone();
+    let sel = rconfig.selection(&headers)?;
+    if sel.len() != 1 {
+        return fail_incorrectusage_clierror!("Only one column can be translated.");
+    }
+    let column_index = sel[0];
+
+    if !rconfig.no_headers {
+        if args.flag_append {
+            let prefix = args.flag_prefix.as_deref().unwrap_or_default();
+            for &i in &value_cols {
+                let mut column_name = prefix.as_bytes().to_vec();
+                column_name.extend_from_slice(&lookup_headers[i]);
+                if headers.iter().any(|h| h == &column_name[..]) {
+                    return fail_incorrectusage_clierror!(
+                        "The \"{}\" column already exists. Use --prefix to rename the appended \
+                         columns.",
+                        String::from_utf8_lossy(&column_name)
+                    );
+                }
+                headers.push_field(&column_name);
+            }
+        }
+        wtr.write_byte_record(&headers)?;
+    }
+
+    let default = args.flag_default.as_ref().map(String::as_bytes);
+    let mut unmatched: IndexMap<Vec<u8>, u64> = IndexMap::new();
+    let mut record = ByteRecord::new();
+    let mut output = ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        let value = &record[column_index];
+        let translation = translations.get(lookup_key(value, args.flag_ignore_case).as_ref());
+        if translation.is_none() && args.flag_unmatched.is_some() {
+            *unmatched.entry(value.to_vec()).or_insert(0) += 1;
+        }
+
+        if args.flag_append {
+            output.clone_from(&record);
+            match translation {
+                Some(values) => {
+                    for v in values {
+                        output.push_field(v);
+                    }
+                },
+                None => {
+                    for _ in &value_cols {
+                        output.push_field(default.unwrap_or_default());
+                    }
+                },
+            }
+        } else {
+            let new_value = match translation {
+                Some(values) => &values[0][..],
+                None => default.unwrap_or(value),
+            };
+            output.clear();
+            for (i, field) in record.iter().enumerate() {
+                output.push_field(if i == column_index { new_value } else { field });
+            }
+        }
+        wtr.write_byte_record(&output)?;
+    }
+    wtr.flush()?;
+
+    if let Some(unmatched_path) = args.flag_unmatched {
+        let mut unmatched_wtr = Config::new(Some(unmatched_path).as_ref()).writer()?;
+        unmatched_wtr.write_record(["value", "count"])?;
+        let mut itoa_buf = itoa::Buffer::new();
+        for (value, count) in unmatched {
+            unmatched_wtr.write_record([&value[..], itoa_buf.format(count).as_bytes()])?;
+        }
+        unmatched_wtr.flush()?;
+    }
+
+    Ok(())
+}
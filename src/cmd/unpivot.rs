@@ -0,0 +1,141 @@
+static USAGE: &str = r#"
+Unpivots (melts) CSV data from wide to long format.
+
+Each input row is streamed into one output row per unpivoted column, with the
+--index column/s, followed by a "variable" column with the name of the unpivoted
+column and a "value" column with its value:
+
+    id,2022,2023            id,variable,value
+    a,1,2           =>      a,2022,1
+    b,3,4                   a,2023,2
+                            b,2022,3
+                            b,2023,4
+
+Unlike `pivotp`, it doesn't load the CSV into memory, so it works with arbitrarily
+large files. `melt` is an alias of this command.
+
+Examples:
+
+  # unpivot all the columns except id
+  $ qsv unpivot --index id data.csv
+
+  # unpivot only the 2022 and 2023 columns, naming the output columns year & sales
+  $ qsv unpivot --index id --on 2022,2023 --variable-name year --value-name sales data.csv
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_unpivot.rs.
+
+Usage:
+    qsv unpivot [options] [<input>]
+    qsv melt [options] [<input>]
+    qsv unpivot --help
+
+unpivot options:
+    -i, --index <cols>       The column/s to keep as identifiers in every output row.
+                             See 'qsv select --help' for the format details.
+    --on <cols>              The column/s to unpivot. If not set, all the columns that
+                             are not in --index are unpivoted.
+    --variable-name <arg>    The name of the output column with the unpivoted column names.
+                             [default: variable]
+    --value-name <arg>       The name of the output column with the unpivoted values.
+                             [default: value]
+    --drop-empty             Do not output rows for empty values.
+
+Common options:
+    -h, --help               Display this message
+    -o, --output <file>      Write output to <file> instead of stdout.
+    -n, --no-headers         When set, the first row will not be interpreted
+                             as headers. The variable column will then contain
+                             the 1-based index of the unpivoted columns.
+    -d, --delimiter <arg>    The field delimiter for reading CSV data.
+                             Must be a single character. (default: ,)
+"#;
+
+use serde::Deserialize;
+
+use crate::{
+    config::{Config, Delimiter},
+    select::SelectColumns,
+    util, CliResult,
+};
+
+#[derive(Deserialize)]
+struct Args {
+    arg_input:          Option<String>,
+    flag_index:         Option<SelectColumns>,
+    flag_on:            Option<SelectColumns>,
+    flag_variable_name: String,
+    flag_value_name:    String,
+    flag_drop_empty:    bool,
+    flag_output:        Option<String>,
+    flag_no_headers:    bool,
+    flag_delimiter:     Option<Delimiter>,
+}
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(args.arg_input.as_ref())
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+
+    let headers = rdr.byte_headers()?.clone();
+    let use_names = !args.flag_no_headers;
+
+    let index_sel: Vec<usize> = match args.flag_index {
+        Some(ref index) => index.selection(&headers, use_names)?.to_vec(),
+        None => vec![],
+    };
+    let on_sel: Vec<usize> = match args.flag_on {
+        Some(ref on) => on.selection(&headers, use_names)?.to_vec(),
+        None => (0..headers.len())
+            .filter(|i| !index_sel.contains(i))
+            .collect(),
+    };
+    if on_sel.is_empty() {
+        return fail_incorrectusage_clierror!("There are no columns to unpivot.");
+    }
+
+    // the names written to the variable column
+    let variable_names: Vec<Vec<u8>> = on_sel
+        .iter()
+        .map(|&i| {
+            if use_names {
+                headers[i].to_vec()
+            } else {
+                (i + 1).to_string().into_bytes()
+            }
+        })
+        .collect();
+
+    if use_names {
+        let mut out_headers = csv::ByteRecord::with_capacity(100, index_sel.len() + 2);
+        for &i in &index_sel {
+            out_headers.push_field(&headers[i]);
+        }
+        out_headers.push_field(args.flag_variable_name.as_bytes());
+        out_headers.push_field(args.flag_value_name.as_bytes());
+        wtr.write_byte_record(&out_headers)?;
+    }
+
+    let mut record = csv::ByteRecord::new();
+    let mut out_record = csv::ByteRecord::with_capacity(100, index_sel.len() + 2);
+    while rdr.read_byte_record(&mut record)? {
+        for (&i, variable_name) in on_sel.iter().zip(&variable_names) {
+            let value = record.get(i).unwrap_or_default();
+            if args.flag_drop_empty && value.is_empty() {
+                continue;
+            }
+            out_record.clear();
+            for &idx in &index_sel {
+                out_record.push_field(record.get(idx).unwrap_or_default());
+            }
+            out_record.push_field(variable_name);
+            out_record.push_field(value);
+            wtr.write_byte_record(&out_record)?;
+        }
+    }
+
+    Ok(wtr.flush()?)
+}
@@ -0,0 +1,515 @@
+static USAGE: &str = r#"
+Compute window functions over sorted CSV data, appending one column per function.
+
+Rows are streamed in input order, so the input must already be sorted by the
+--partition column/s followed by the --order-by column/s (e.g. with `qsv sort` or
+`qsv extsort`). The sort order is verified as the rows are read, the same way
+`sortcheck` does, and window fails at the first out of order row. Window functions
+are computed within each partition, i.e. their state is reset whenever the value
+of the --partition column/s changes.
+
+The following window functions are supported:
+
+    row_number                   The 1-based number of the row within its partition.
+    lag:<column>[:<n>]           The value of <column> <n> rows before the current row.
+                                 <n> defaults to 1.
+    lead:<column>[:<n>]          The value of <column> <n> rows after the current row.
+                                 <n> defaults to 1.
+    cumsum:<column>              The running sum of the numeric values of <column>.
+    cumcount[:<column>]          The running number of rows, or of non-empty values
+                                 of <column> if set.
+    rolling_sum:<column>:<n>     The sum of the numeric values of <column> in the
+                                 window of the last <n> rows, including the current one.
+    rolling_mean:<column>:<n>    The mean of the numeric values in the window.
+    rolling_min:<column>:<n>     The minimum of the numeric values in the window.
+    rolling_max:<column>:<n>     The maximum of the numeric values in the window.
+
+Lag/lead values that fall outside the partition are empty. Numeric functions use
+exact decimal arithmetic and ignore values that are not numbers. They return an
+empty value until there is at least one number to aggregate.
+
+The appended columns are named <function><n>_<column>, with <n> omitted for lag/lead
+with the default offset of 1 (e.g. row_number, lag_price, lead2_price, cumsum_qty,
+rolling_mean7_price).
+
+Examples:
+
+  # previous and next closing price, and the 7-day moving average per ticker
+  $ qsv window "lag:close,lead:close,rolling_mean:close:7" -p ticker -s date prices.csv
+
+  # running total of sales per store and number the rows within each store
+  $ qsv sort -s store,date sales.csv | qsv window "row_number,cumsum:amount" -p store -s date
+
+For more examples, see https://github.com/jqnatividad/qsv/blob/master/tests/test_window.rs.
+
+Usage:
+    qsv window [options] <functions> [<input>]
+    qsv window --help
+
+window arguments:
+    <functions>              A comma-separated list of window functions to compute,
+                             in the form <function>[:<column>[:<n>]].
+                             The column can be a column name or a 1-based index.
+    <input>                  The CSV file to read from. If not specified, then
+                             the input will be read from stdin.
+
+window options:
+    -p, --partition <cols>   The column/s that partition the rows. Window functions
+                             are computed separately for each partition.
+                             See 'qsv select --help' for the format details.
+    -s, --order-by <cols>    The column/s the rows are sorted by within each partition.
+                             Only used to verify the sort order of the input.
+    -N, --numeric            Verify the sort order numerically instead of
+                             lexicographically, as `qsv sort --numeric` does.
+    --no-check               Do not verify the sort order of the input. Partitions are
+                             then any run of consecutive rows with the same --partition values.
+    --round <places>         Round rolling means to <places> decimal places.
+                             [default: 4]
+
+Common options:
+    -h, --help               Display this message
+    -o, --output <file>      Write output to <file> instead of stdout.
+    -n, --no-headers         When set, the first row will not be interpreted
+                             as headers. Columns are then referred to by their
+                             1-based index and no header row is written.
+    -d, --delimiter <arg>    The field delimiter for reading CSV data.
+                             Must be a single character. (default: ,)
+"#;
+
+use std::{cmp, collections::VecDeque, io};
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    cmd::{
+        groupby::parse_decimal,
+        sort::{iter_cmp, iter_cmp_num},
+    },
+    config::{Config, Delimiter},
+    select::SelectColumns,
+    util, CliResult,
+};
+
+#[derive(Deserialize)]
+struct Args {
+    arg_functions:   String,
+    arg_input:       Option<String>,
+    flag_partition:  Option<SelectColumns>,
+    flag_order_by:   Option<SelectColumns>,
+    flag_numeric:    bool,
+    flag_no_check:   bool,
+    flag_round:      u32,
+    flag_output:     Option<String>,
+    flag_no_headers: bool,
+    flag_delimiter:  Option<Delimiter>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WindowFunc {
+    RowNumber,
+    Lag,
+    Lead,
+    CumSum,
+    CumCount,
+    RollingSum,
+    RollingMean,
+    RollingMin,
+    RollingMax,
+}
+
+struct WindowSpec {
+    func: WindowFunc,
+    col:  Option<usize>,
+    /// the lag/lead offset or the rolling window size
+    n:    usize,
+    name: String,
+}
+
+/// the running state of one window function within the current partition
+enum WindowState {
+    Stateless,
+    CumSum(Option<Decimal>),
+    CumCount(u64),
+    /// the numeric values of the last n rows, and the sum of the numbers among them
+    Rolling(VecDeque<Option<Decimal>>, Decimal),
+}
+
+type CsvWriter = csv::Writer<Box<dyn io::Write + 'static>>;
+
+pub fn run(argv: &[&str]) -> CliResult<()> {
+    let args: Args = util::get_args(USAGE, argv)?;
+    let rconfig = Config::new(args.arg_input.as_ref())
+        .delimiter(args.flag_delimiter)
+        .no_headers(args.flag_no_headers);
+
+    let mut rdr = rconfig.reader()?;
+    let mut wtr = Config::new(args.flag_output.as_ref()).writer()?;
+
+    let headers = rdr.byte_headers()?.clone();
+    let use_names = !args.flag_no_headers;
+
+    let partition_sel: Vec<usize> = match args.flag_partition {
+        Some(ref partition) => partition.selection(&headers, use_names)?.to_vec(),
+        None => vec![],
+    };
+    let order_sel: Vec<usize> = match args.flag_order_by {
+        Some(ref order_by) => order_by.selection(&headers, use_names)?.to_vec(),
+        None => vec![],
+    };
+    // the rows must be sorted by the partition columns, then the order-by columns
+    let sort_sel: Vec<usize> = partition_sel.iter().chain(&order_sel).copied().collect();
+    let partition_len = partition_sel.len();
+
+    let specs = parse_functions(&args.arg_functions, &headers, use_names)?;
+
+    if use_names {
+        let mut out_headers = headers.clone();
+        for spec in &specs {
+            out_headers.push_field(spec.name.as_bytes());
+        }
+        wtr.write_byte_record(&out_headers)?;
+    }
+
+    let mut window = Window::new(specs, args.flag_round);
+    let mut record = csv::ByteRecord::new();
+    let mut key: Vec<Vec<u8>> = Vec::with_capacity(sort_sel.len());
+    let mut prev_key: Vec<Vec<u8>> = Vec::with_capacity(sort_sel.len());
+    let mut rowno = 0_u64;
+    while rdr.read_byte_record(&mut record)? {
+        rowno += 1;
+        key.clear();
+        key.extend(
+            sort_sel
+                .iter()
+                .map(|&i| record.get(i).unwrap_or_default().to_vec()),
+        );
+
+        if rowno > 1 {
+            if !args.flag_no_check {
+                let ordering = if args.flag_numeric {
+                    iter_cmp_num(
+                        prev_key.iter().map(Vec::as_slice),
+                        key.iter().map(Vec::as_slice),
+                    )
+                } else {
+                    iter_cmp(prev_key.iter(), key.iter())
+                };
+                if ordering == cmp::Ordering::Greater {
+                    return fail_clierror!(
+                        "Input is not sorted by the --partition and --order-by columns at record \
+                         {rowno}. Sort it first with `qsv sort` or `qsv extsort`."
+                    );
+                }
+            }
+            if prev_key[..partition_len] != key[..partition_len] {
+                window.finish_partition(&mut wtr)?;
+            }
+        }
+
+        window.push(&record, &mut wtr)?;
+        std::mem::swap(&mut key, &mut prev_key);
+    }
+    window.finish_partition(&mut wtr)?;
+
+    Ok(wtr.flush()?)
+}
+
+fn parse_functions(
+    functions: &str,
+    headers: &csv::ByteRecord,
+    use_names: bool,
+) -> CliResult<Vec<WindowSpec>> {
+    let mut specs = Vec::new();
+    for function in functions
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let mut parts = function.split(':').map(str::trim);
+        let func_name = parts.next().unwrap_or_default().to_lowercase();
+        let column = parts.next();
+        let n = parts.next();
+        if parts.next().is_some() {
+            return fail_incorrectusage_clierror!("Invalid window function: {function}");
+        }
+
+        let func = match func_name.as_str() {
+            "row_number" => WindowFunc::RowNumber,
+            "lag" => WindowFunc::Lag,
+            "lead" => WindowFunc::Lead,
+            "cumsum" => WindowFunc::CumSum,
+            "cumcount" => WindowFunc::CumCount,
+            "rolling_sum" => WindowFunc::RollingSum,
+            "rolling_mean" | "rolling_avg" => WindowFunc::RollingMean,
+            "rolling_min" => WindowFunc::RollingMin,
+            "rolling_max" => WindowFunc::RollingMax,
+            _ => return fail_incorrectusage_clierror!("Unknown window function: {func_name}"),
+        };
+        let is_rolling = matches!(
+            func,
+            WindowFunc::RollingSum
+                | WindowFunc::RollingMean
+                | WindowFunc::RollingMin
+                | WindowFunc::RollingMax
+        );
+
+        let n = match n {
+            Some(n) => match n.parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ => {
+                    return fail_incorrectusage_clierror!(
+                        "Window function {function} requires a positive integer <n>."
+                    )
+                },
+            },
+            None if is_rolling => {
+                return fail_incorrectusage_clierror!(
+                    "Window function {function} requires a window size <n>."
+                )
+            },
+            None => 1,
+        };
+        if n != 1 && !is_rolling && !matches!(func, WindowFunc::Lag | WindowFunc::Lead) {
+            return fail_incorrectusage_clierror!("Window function {func_name} does not take <n>.");
+        }
+
+        let Some(column) = column else {
+            if func == WindowFunc::RowNumber || func == WindowFunc::CumCount {
+                specs.push(WindowSpec {
+                    func,
+                    col: None,
+                    n,
+                    name: func_name,
+                });
+                continue;
+            }
+            return fail_incorrectusage_clierror!("Window function {func_name} requires a column.");
+        };
+        if func == WindowFunc::RowNumber {
+            return fail_incorrectusage_clierror!("row_number does not take a column.");
+        }
+        let sel = SelectColumns::parse(column)?.selection(headers, use_names)?;
+        if sel.len() != 1 {
+            return fail_incorrectusage_clierror!(
+                "Window function {function} must select exactly one column."
+            );
+        }
+        let col = sel[0];
+        let col_name = if use_names {
+            String::from_utf8_lossy(&headers[col]).to_string()
+        } else {
+            (col + 1).to_string()
+        };
+        let name = if n == 1 && !is_rolling {
+            format!("{func_name}_{col_name}")
+        } else {
+            format!("{func_name}{n}_{col_name}")
+        };
+        specs.push(WindowSpec {
+            func,
+            col: Some(col),
+            n,
+            name,
+        });
+    }
+    if specs.is_empty() {
+        return fail_incorrectusage_clierror!("No window functions specified.");
+    }
+    Ok(specs)
+}
+
+/// computes the window functions over the rows of the current partition.
+/// Rows are written out as soon as all their lead values are known.
+struct Window {
+    specs:      Vec<WindowSpec>,
+    states:     Vec<WindowState>,
+    round:      u32,
+    max_lag:    usize,
+    max_lead:   usize,
+    row_number: u64,
+    /// the last max_lag rows of the partition
+    history:    VecDeque<csv::ByteRecord>,
+    /// the rows waiting for their lead values, with their computed values
+    pending:    VecDeque<(csv::ByteRecord, Vec<Vec<u8>>)>,
+    out_record: csv::ByteRecord,
+}
+
+impl Window {
+    fn new(specs: Vec<WindowSpec>, round: u32) -> Self {
+        let offset = |func| {
+            specs
+                .iter()
+                .filter(|s| s.func == func)
+                .map(|s| s.n)
+                .max()
+                .unwrap_or(0)
+        };
+        let max_lag = offset(WindowFunc::Lag);
+        let max_lead = offset(WindowFunc::Lead);
+        let states = specs.iter().map(WindowState::new).collect();
+        Self {
+            specs,
+            states,
+            round,
+            max_lag,
+            max_lead,
+            row_number: 0,
+            history: VecDeque::with_capacity(max_lag + 1),
+            pending: VecDeque::with_capacity(max_lead + 1),
+            out_record: csv::ByteRecord::new(),
+        }
+    }
+
+    fn push(&mut self, record: &csv::ByteRecord, wtr: &mut CsvWriter) -> CliResult<()> {
+        self.row_number += 1;
+        let mut values = Vec::with_capacity(self.specs.len());
+        for (spec, state) in self.specs.iter().zip(self.states.iter_mut()) {
+            let field = spec.col.and_then(|i| record.get(i)).unwrap_or_default();
+            let value = match spec.func {
+                WindowFunc::RowNumber => itoa::Buffer::new()
+                    .format(self.row_number)
+                    .as_bytes()
+                    .to_vec(),
+                WindowFunc::Lag => {
+                    if spec.n <= self.history.len() {
+                        self.history[self.history.len() - spec.n]
+                            .get(spec.col.unwrap_or_default())
+                            .unwrap_or_default()
+                            .to_vec()
+                    } else {
+                        Vec::new()
+                    }
+                },
+                // filled in when the row is written out
+                WindowFunc::Lead => Vec::new(),
+                _ => state.update(spec, field, self.round)?,
+            };
+            values.push(value);
+        }
+
+        if self.max_lag > 0 {
+            if self.history.len() == self.max_lag {
+                self.history.pop_front();
+            }
+            self.history.push_back(record.clone());
+        }
+        self.pending.push_back((record.clone(), values));
+        while self.pending.len() > self.max_lead {
+            self.write_next(wtr)?;
+        }
+        Ok(())
+    }
+
+    /// write out the oldest pending row, looking up its lead values in the rows after it
+    fn write_next(&mut self, wtr: &mut CsvWriter) -> CliResult<()> {
+        let Some((record, mut values)) = self.pending.pop_front() else {
+            return Ok(());
+        };
+        for (spec, value) in self.specs.iter().zip(values.iter_mut()) {
+            if spec.func == WindowFunc::Lead {
+                if let Some((next, _)) = self.pending.get(spec.n - 1) {
+                    *value = next
+                        .get(spec.col.unwrap_or_default())
+                        .unwrap_or_default()
+                        .to_vec();
+                }
+            }
+        }
+        self.out_record.clone_from(&record);
+        for value in &values {
+            self.out_record.push_field(value);
+        }
+        wtr.write_byte_record(&self.out_record)?;
+        Ok(())
+    }
+
+    /// write out the remaining rows of the partition and reset the window state
+    fn finish_partition(&mut self, wtr: &mut CsvWriter) -> CliResult<()> {
+        while !self.pending.is_empty() {
+            self.write_next(wtr)?;
+        }
+        self.history.clear();
+        self.row_number = 0;
+        for (spec, state) in self.specs.iter().zip(self.states.iter_mut()) {
+            *state = WindowState::new(spec);
+        }
+        Ok(())
+    }
+}
+
+impl WindowState {
+    fn new(spec: &WindowSpec) -> Self {
+        match spec.func {
+            WindowFunc::RowNumber | WindowFunc::Lag | WindowFunc::Lead => WindowState::Stateless,
+            WindowFunc::CumSum => WindowState::CumSum(None),
+            WindowFunc::CumCount => WindowState::CumCount(0),
+            WindowFunc::RollingSum
+            | WindowFunc::RollingMean
+            | WindowFunc::RollingMin
+            | WindowFunc::RollingMax => {
+                WindowState::Rolling(VecDeque::with_capacity(spec.n + 1), Decimal::ZERO)
+            },
+        }
+    }
+
+    /// add the field of the current row and return the function value for it
+    fn update(&mut self, spec: &WindowSpec, field: &[u8], round: u32) -> CliResult<Vec<u8>> {
+        match self {
+            WindowState::Stateless => Ok(Vec::new()),
+            WindowState::CumSum(sum) => {
+                if let Some(num) = parse_decimal(field) {
+                    *sum = match sum {
+                        Some(s) => match s.checked_add(num) {
+                            Some(s) => Some(s),
+                            None => return fail_clierror!("cumsum overflow in {}", spec.name),
+                        },
+                        None => Some(num),
+                    };
+                }
+                Ok(sum
+                    .map(|s| s.normalize().to_string().into_bytes())
+                    .unwrap_or_default())
+            },
+            WindowState::CumCount(count) => {
+                if spec.col.is_none() || !field.is_empty() {
+                    *count += 1;
+                }
+                Ok(itoa::Buffer::new().format(*count).as_bytes().to_vec())
+            },
+            WindowState::Rolling(window, sum) => {
+                if window.len() == spec.n {
+                    if let Some(Some(evicted)) = window.pop_front() {
+                        *sum -= evicted;
+                    }
+                }
+                let num = parse_decimal(field);
+                if let Some(n) = num {
+                    *sum = match sum.checked_add(n) {
+                        Some(s) => s,
+                        None => return fail_clierror!("rolling sum overflow in {}", spec.name),
+                    };
+                }
+                window.push_back(num);
+
+                let mut nums = window.iter().flatten();
+                let Some(first) = nums.next() else {
+                    return Ok(Vec::new());
+                };
+                let value = match spec.func {
+                    WindowFunc::RollingSum => sum.normalize().to_string(),
+                    WindowFunc::RollingMean => {
+                        let count = 1 + nums.count();
+                        (*sum / Decimal::from(count))
+                            .round_dp(round)
+                            .normalize()
+                            .to_string()
+                    },
+                    WindowFunc::RollingMin => nums.fold(*first, |m, &n| m.min(n)).to_string(),
+                    _ => nums.fold(*first, |m, &n| m.max(n)).to_string(),
+                };
+                Ok(value.into_bytes())
+            },
+        }
+    }
+}
@@ -112,6 +112,7 @@ fn main() -> QsvExitCode {
     exclude     Excludes the records in one CSV from another
     explode     Explode rows based on some column separator
     extdedup    Remove duplicates rows from an arbitrarily large text file
+    extract     Extract named regex capture groups into new columns
     extsort     Sort arbitrarily large text file\n",
     );
 
@@ -138,7 +139,8 @@ fn main() -> QsvExitCode {
         .push_str("    geocode     Geocodes a location against the Geonames cities database.\n");
 
     enabled_commands.push_str(
-        "    headers     Show header names
+        "    groupby     Aggregate CSV data by key columns
+    headers     Show header names
     help        Show this usage message
     index       Create CSV index for faster access
     input       Read CSVs w/ special quoting, skipping, trimming & transcoding rules
@@ -159,10 +161,14 @@ fn main() -> QsvExitCode {
     #[cfg(all(feature = "luau", feature = "feature_capable"))]
     enabled_commands.push_str("    luau        Execute Luau script on CSV data\n");
 
-    enabled_commands.push_str(
-        "    partition   Partition CSV data based on a column value
-    pro         Interact with the qsv pro API\n",
-    );
+    enabled_commands.push_str("    merge       Merge change files into a CSV by key\n");
+    enabled_commands.push_str("    partition   Partition CSV data based on a column value\n");
+    enabled_commands.push_str("    patch       Apply a diff result to a CSV\n");
+
+    #[cfg(all(feature = "polars", feature = "feature_capable"))]
+    enabled_commands.push_str("    pivotp      Pivot CSV data from long to wide format\n");
+
+    enabled_commands.push_str("    pro         Interact with the qsv pro API\n");
 
     #[cfg(all(feature = "prompt", feature = "feature_capable"))]
     enabled_commands.push_str("    prompt      Open a file dialog to pick a file\n");
@@ -175,6 +181,7 @@ fn main() -> QsvExitCode {
     enabled_commands.push_str(
         "    rename      Rename the columns of CSV data efficiently
     replace     Replace patterns in CSV data
+    resample    Resample time series into fixed intervals with gap filling
     reverse     Reverse rows of CSV data
     safenames   Modify a CSV's header names to db-safe names
     sample      Randomly sample CSV data
@@ -207,8 +214,12 @@ fn main() -> QsvExitCode {
         .push_str("    to          Convert CSVs to PostgreSQL/XLSX/SQLite/Data Package\n");
 
     enabled_commands.push_str(
-        "    transpose   Transpose rows/columns of CSV data
-    validate    Validate CSV data for RFC4180-compliance or with JSON Schema",
+        "    topn        Select the top N records per group
+    translate   Translate values with a lookup table
+    transpose   Transpose rows/columns of CSV data
+    unpivot     Unpivot (melt) CSV data from wide to long format
+    validate    Validate CSV data for RFC4180-compliance or with JSON Schema
+    window      Compute lag/lead, running totals & rolling aggregates",
     );
     let num_commands = enabled_commands.split('\n').count();
 
@@ -348,6 +359,7 @@ enum Command {
     Exclude,
     Explode,
     ExtDedup,
+    Extract,
     ExtSort,
     #[cfg(all(feature = "fetch", feature = "feature_capable"))]
     Fetch,
@@ -362,6 +374,7 @@ enum Command {
     Frequency,
     #[cfg(all(feature = "geocode", feature = "feature_capable"))]
     Geocode,
+    GroupBy,
     Headers,
     Help,
     Index,
@@ -375,7 +388,12 @@ enum Command {
     Lens,
     #[cfg(all(feature = "luau", feature = "feature_capable"))]
     Luau,
+    Melt,
+    Merge,
     Partition,
+    Patch,
+    #[cfg(all(feature = "polars", feature = "feature_capable"))]
+    PivotP,
     Pro,
     #[cfg(all(feature = "prompt", feature = "feature_capable"))]
     Prompt,
@@ -384,6 +402,7 @@ enum Command {
     Py,
     Rename,
     Replace,
+    Resample,
     Reverse,
     Safenames,
     Sample,
@@ -402,11 +421,15 @@ enum Command {
     Stats,
     Table,
     Template,
+    Topn,
+    Translate,
     Transpose,
     #[cfg(all(feature = "to", feature = "feature_capable"))]
     To,
     Tojsonl,
+    Unpivot,
     Validate,
+    Window,
 }
 
 impl Command {
@@ -442,6 +465,7 @@ impl Command {
             Command::Exclude => cmd::exclude::run(argv),
             Command::Explode => cmd::explode::run(argv),
             Command::ExtDedup => cmd::extdedup::run(argv),
+            Command::Extract => cmd::extract::run(argv),
             Command::ExtSort => cmd::extsort::run(argv),
             #[cfg(all(feature = "fetch", feature = "feature_capable"))]
             Command::Fetch => cmd::fetch::run(argv),
@@ -456,6 +480,7 @@ impl Command {
             Command::Frequency => cmd::frequency::run(argv),
             #[cfg(all(feature = "geocode", feature = "feature_capable"))]
             Command::Geocode => cmd::geocode::run(argv),
+            Command::GroupBy => cmd::groupby::run(argv),
             Command::Headers => cmd::headers::run(argv),
             Command::Help => {
                 wout!("{USAGE}\n\n{SPONSOR_MESSAGE}");
@@ -473,7 +498,12 @@ impl Command {
             Command::Lens => cmd::lens::run(argv),
             #[cfg(all(feature = "luau", feature = "feature_capable"))]
             Command::Luau => cmd::luau::run(argv),
+            Command::Melt | Command::Unpivot => cmd::unpivot::run(argv),
+            Command::Merge => cmd::merge::run(argv),
             Command::Partition => cmd::partition::run(argv),
+            Command::Patch => cmd::patch::run(argv),
+            #[cfg(all(feature = "polars", feature = "feature_capable"))]
+            Command::PivotP => cmd::pivotp::run(argv),
             Command::Pro => cmd::pro::run(argv),
             #[cfg(all(feature = "prompt", feature = "feature_capable"))]
             Command::Prompt => cmd::prompt::run(argv),
@@ -482,6 +512,7 @@ impl Command {
             Command::Py => cmd::python::run(argv),
             Command::Rename => cmd::rename::run(argv),
             Command::Replace => cmd::replace::run(argv),
+            Command::Resample => cmd::resample::run(argv),
             Command::Reverse => cmd::reverse::run(argv),
             Command::Safenames => cmd::safenames::run(argv),
             Command::Sample => cmd::sample::run(argv),
@@ -500,11 +531,14 @@ impl Command {
             Command::Stats => cmd::stats::run(argv),
             Command::Table => cmd::table::run(argv),
             Command::Template => cmd::template::run(argv),
+            Command::Topn => cmd::topn::run(argv),
+            Command::Translate => cmd::translate::run(argv),
             Command::Transpose => cmd::transpose::run(argv),
             #[cfg(all(feature = "to", feature = "feature_capable"))]
             Command::To => cmd::to::run(argv),
             Command::Tojsonl => cmd::tojsonl::run(argv),
             Command::Validate => cmd::validate::run(argv),
+            Command::Window => cmd::window::run(argv),
         }
     }
 }
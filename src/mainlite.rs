@@ -32,12 +32,14 @@ static COMMAND_LIST: &str = r#"
     exclude     Excludes the records in one CSV from another
     explode     Explode rows based on some column separator
     extdedup    Remove duplicates rows from an arbitrarily large text file
+    extract     Extract named regex capture groups into new columns
     extsort     Sort arbitrarily large text file
     fill        Fill empty values
     fixlengths  Makes all records have same length
     flatten     Show one field per line
     fmt         Format CSV output (change field delimiter)
     frequency   Show frequency tables
+    groupby     Aggregate CSV data by key columns
     headers     Show header names
     help        Show this usage message
     index       Create CSV index for faster access
@@ -45,11 +47,14 @@ static COMMAND_LIST: &str = r#"
     join        Join CSV files
     json        Convert JSON to CSV
     jsonl       Convert newline-delimited JSON files to CSV
+    merge       Merge change files into a CSV by key
     partition   Partition CSV data based on a column value
+    patch       Apply a diff result to a CSV
     pro         Interact with the qsv pro API
     pseudo      Pseudonymise the values of a column
     rename      Rename the columns of CSV data efficiently
     replace     Replace patterns in CSV data
+    resample    Resample time series into fixed intervals with gap filling
     reverse     Reverse rows of CSV data
     safenames   Modify a CSV's header names to db-safe names
     sample      Randomly sample CSV data
@@ -66,8 +71,11 @@ static COMMAND_LIST: &str = r#"
     stats       Infer data types and compute summary statistics
     table       Align CSV data into columns
     tojsonl     Convert CSV to newline-delimited JSON
+    topn        Select the top N records per group
     transpose   Transpose rows/columns of CSV data
-    validate    Validate CSV data for RFC4180-compliance or with JSON Schema"#;
+    unpivot     Unpivot (melt) CSV data from wide to long format
+    validate    Validate CSV data for RFC4180-compliance or with JSON Schema
+    window      Compute lag/lead, running totals & rolling aggregates"#;
 
 mod clitypes;
 mod cmd;
@@ -145,7 +153,7 @@ fn main() -> QsvExitCode {
         None => {
             werr!(
                 "qsvlite is a suite of CSV command line utilities.\n\nPlease choose one of the \
-                 following 49 commands:\n{COMMAND_LIST}\n\n{SPONSOR_MESSAGE}",
+                 following 57 commands:\n{COMMAND_LIST}\n\n{SPONSOR_MESSAGE}",
             );
 
             // if no command is specified, auto-check for updates 10% of the time
@@ -235,12 +243,14 @@ enum Command {
     Exclude,
     Explode,
     ExtDedup,
+    Extract,
     ExtSort,
     Fill,
     FixLengths,
     Flatten,
     Fmt,
     Frequency,
+    GroupBy,
     Headers,
     Help,
     Index,
@@ -248,11 +258,15 @@ enum Command {
     Join,
     Json,
     Jsonl,
+    Melt,
+    Merge,
     Partition,
+    Patch,
     Pro,
     Pseudo,
     Rename,
     Replace,
+    Resample,
     Reverse,
     Safenames,
     Sample,
@@ -269,8 +283,11 @@ enum Command {
     Stats,
     Table,
     Tojsonl,
+    Topn,
     Transpose,
+    Unpivot,
     Validate,
+    Window,
 }
 
 impl Command {
@@ -301,12 +318,14 @@ impl Command {
             Command::Exclude => cmd::exclude::run(argv),
             Command::Explode => cmd::explode::run(argv),
             Command::ExtDedup => cmd::extdedup::run(argv),
+            Command::Extract => cmd::extract::run(argv),
             Command::ExtSort => cmd::extsort::run(argv),
             Command::Fill => cmd::fill::run(argv),
             Command::FixLengths => cmd::fixlengths::run(argv),
             Command::Flatten => cmd::flatten::run(argv),
             Command::Fmt => cmd::fmt::run(argv),
             Command::Frequency => cmd::frequency::run(argv),
+            Command::GroupBy => cmd::groupby::run(argv),
             Command::Headers => cmd::headers::run(argv),
             Command::Help => {
                 wout!("{USAGE}\n\n{SPONSOR_MESSAGE}");
@@ -318,11 +337,15 @@ impl Command {
             Command::Join => cmd::join::run(argv),
             Command::Json => cmd::json::run(argv),
             Command::Jsonl => cmd::jsonl::run(argv),
+            Command::Melt | Command::Unpivot => cmd::unpivot::run(argv),
+            Command::Merge => cmd::merge::run(argv),
             Command::Partition => cmd::partition::run(argv),
+            Command::Patch => cmd::patch::run(argv),
             Command::Pro => cmd::pro::run(argv),
             Command::Pseudo => cmd::pseudo::run(argv),
             Command::Rename => cmd::rename::run(argv),
             Command::Replace => cmd::replace::run(argv),
+            Command::Resample => cmd::resample::run(argv),
             Command::Reverse => cmd::reverse::run(argv),
             Command::Safenames => cmd::safenames::run(argv),
             Command::Sample => cmd::sample::run(argv),
@@ -339,8 +362,10 @@ impl Command {
             Command::Stats => cmd::stats::run(argv),
             Command::Table => cmd::table::run(argv),
             Command::Tojsonl => cmd::tojsonl::run(argv),
+            Command::Topn => cmd::topn::run(argv),
             Command::Transpose => cmd::transpose::run(argv),
             Command::Validate => cmd::validate::run(argv),
+            Command::Window => cmd::window::run(argv),
         }
     }
 }
@@ -579,6 +579,101 @@ pub const fn num_of_chunks(nitems: usize, chunk_size: usize) -> usize {
     n
 }
 
+/// Process the records of an indexed CSV in parallel chunks of at most DEFAULT_BATCH_SIZE
+/// records, and pass the results of the chunks to `consume` in the order of the chunks,
+/// so output derived from them keeps the order of the input.
+/// `process` is called with the index of the first record of its chunk and the records of
+/// the chunk. When `consume` returns false, the remaining chunks are skipped.
+pub fn process_indexed_chunks<T, P, C>(
+    rconfig: &Config,
+    idx_count: u64,
+    njobs: usize,
+    process: P,
+    mut consume: C,
+) -> CliResult<()>
+where
+    T: Send + 'static,
+    P: Fn(u64, &mut dyn Iterator<Item = csv::Result<ByteRecord>>) -> CliResult<T>
+        + Send
+        + Sync
+        + 'static,
+    C: FnMut(T) -> CliResult<bool>,
+{
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    let idx_count = idx_count as usize;
+    if idx_count == 0 {
+        return Ok(());
+    }
+    let chunk_size = chunk_size(idx_count, njobs).clamp(1, DEFAULT_BATCH_SIZE);
+    let nchunks = num_of_chunks(idx_count, chunk_size);
+
+    let process = Arc::new(process);
+    let stop = Arc::new(AtomicBool::new(false));
+    let pool = threadpool::ThreadPool::new(njobs);
+    let submit = |i: usize, send: crossbeam_channel::Sender<(usize, CliResult<T>)>| {
+        let (rconfig, process, stop) = (rconfig.clone(), process.clone(), stop.clone());
+        pool.execute(move || {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            let start = (i * chunk_size) as u64;
+            let result = rconfig.indexed().and_then(|idx| {
+                // safety: we know the file is indexed and seekable
+                let mut idx = idx.unwrap();
+                idx.seek(start)?;
+                process(start, &mut idx.byte_records().take(chunk_size))
+            });
+            // the receiver is gone if an earlier chunk failed or stopped the processing
+            let _ = send.send((i, result));
+        });
+    };
+
+    // only run a window of chunks ahead of the next one to consume, so the results of
+    // later chunks don't pile up in memory while an earlier chunk is slow.
+    // The sender is dropped after the last chunk is submitted, ending the receiving loop.
+    let window = njobs.max(1) * 2;
+    let (send, recv) = crossbeam_channel::bounded(0);
+    let mut send = Some(send);
+    let mut submitted = 0;
+    let mut submit_until = |limit: usize| {
+        while submitted < limit.min(nchunks) {
+            if let Some(ref send) = send {
+                submit(submitted, send.clone());
+            }
+            submitted += 1;
+        }
+        if submitted == nchunks {
+            send = None;
+        }
+    };
+    submit_until(window);
+
+    // chunks finish out of order, so keep the results of later chunks until their turn
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    for (i, result) in &recv {
+        pending.insert(i, result);
+        while let Some(result) = pending.remove(&next) {
+            next += 1;
+            match result.and_then(&mut consume) {
+                Ok(true) => submit_until(next + window),
+                outcome => {
+                    stop.store(true, Ordering::Relaxed);
+                    return outcome.map(|_| ());
+                },
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn file_metadata(md: &fs::Metadata) -> (u64, u64) {
     use filetime::FileTime;
     let last_modified = FileTime::from_last_modification_time(md).unix_seconds() as u64;
@@ -1529,6 +1624,17 @@ pub fn to_lowercase_into(s: &str, buf: &mut String) {
     }
 }
 
+/// Remove the accents from `s` (e.g. "Ängelholm" -> "Angelholm") for accent-insensitive
+/// matching, by decomposing it and dropping the combining marks.
+pub fn strip_accents(s: &str) -> String {
+    let general_category = icu_properties::maps::general_category();
+    icu_normalizer::DecomposingNormalizer::new_nfd()
+        .normalize(s)
+        .chars()
+        .filter(|&c| general_category.get(c) != icu_properties::GeneralCategory::NonspacingMark)
+        .collect()
+}
+
 /// load the first BUFFER*8 (1024k) bytes of the file and check if it is utf8
 pub fn isutf8_file(path: &Path) -> Result<bool, CliError> {
     let metadata = std::fs::metadata(path)?;
@@ -287,3 +287,158 @@ fn dedup_not_sorted2() {
     let got: String = wrk.output_stderr(&mut cmd);
     assert!(got.contains("Aborting! Input not sorted!"));
 }
+
+fn fuzzy_people() -> Vec<Vec<String>> {
+    vec![
+        svec!["name", "zip"],
+        svec!["Jon Smith", "10001"],
+        svec!["John Smith", "10001"],
+        svec!["Jane Doe", "10001"],
+        svec!["John Smith", "20002"],
+        svec!["Jane Do", "10001"],
+    ]
+}
+
+#[test]
+fn dedup_fuzzy_block() {
+    let wrk = Workdir::new("dedup_fuzzy_block");
+    wrk.create("in.csv", fuzzy_people());
+
+    let mut cmd = wrk.command("dedup");
+    cmd.args(["--fuzzy", "jaro-winkler", "-s", "name", "--block", "zip"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "zip"],
+        svec!["Jon Smith", "10001"],
+        svec!["Jane Doe", "10001"],
+        svec!["John Smith", "20002"],
+    ];
+    assert_eq!(got, expected);
+
+    let got: String = wrk.output_stderr(&mut cmd);
+    assert_eq!(got, "2\n");
+}
+
+#[test]
+fn dedup_fuzzy_survivor() {
+    let wrk = Workdir::new("dedup_fuzzy_survivor");
+    wrk.create("in.csv", fuzzy_people());
+
+    let mut cmd = wrk.command("dedup");
+    cmd.args(["--fuzzy", "jaro-winkler", "-s", "name", "--block", "zip"])
+        .args(["--survivor", "longest", "-D", "dupes.csv"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "zip"],
+        svec!["John Smith", "10001"],
+        svec!["Jane Doe", "10001"],
+        svec!["John Smith", "20002"],
+    ];
+    assert_eq!(got, expected);
+
+    let got: String = wrk.read_to_string("dupes.csv");
+    assert_eq!(got, "name,zip\nJon Smith,10001\nJane Do,10001\n");
+}
+
+#[test]
+fn dedup_fuzzy_survivor_last_input_order() {
+    let wrk = Workdir::new("dedup_fuzzy_survivor_last_input_order");
+    wrk.create("in.csv", fuzzy_people());
+
+    // the survivor of the Jane cluster comes after the survivor of the 20002 block
+    let mut cmd = wrk.command("dedup");
+    cmd.args(["--fuzzy", "jaro-winkler", "-s", "name", "--block", "zip"])
+        .args(["--survivor", "last"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "zip"],
+        svec!["John Smith", "10001"],
+        svec!["John Smith", "20002"],
+        svec!["Jane Do", "10001"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn dedup_fuzzy_cluster_col() {
+    let wrk = Workdir::new("dedup_fuzzy_cluster_col");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["fruit"],
+            svec!["apple"],
+            svec!["appel"],
+            svec!["banana"],
+            svec!["APPLE"],
+            svec!["aple"],
+            svec!["orange"],
+        ],
+    );
+
+    let mut cmd = wrk.command("dedup");
+    cmd.args(["--fuzzy", "levenshtein", "--threshold", "0.5", "-i"])
+        .args(["--cluster-col", "cluster"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["fruit", "cluster"],
+        svec!["apple", "1"],
+        svec!["appel", "1"],
+        svec!["banana", "2"],
+        svec!["APPLE", "1"],
+        svec!["aple", "1"],
+        svec!["orange", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn dedup_fuzzy_errors() {
+    let wrk = Workdir::new("dedup_fuzzy_errors");
+    wrk.create("in.csv", fuzzy_people());
+
+    let mut cmd = wrk.command("dedup");
+    cmd.args(["--fuzzy", "soundex", "in.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("dedup");
+    cmd.args(["--fuzzy", "levenshtein", "--sorted", "in.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("dedup");
+    cmd.args(["--fuzzy", "levenshtein", "--threshold", "1.5", "in.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn dedup_accent_insensitive() {
+    let wrk = Workdir::new("dedup_accent_insensitive");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["name"],
+            svec!["Zoe"],
+            svec!["Jose"],
+            svec!["jose"],
+            svec!["José"],
+        ],
+    );
+
+    let mut cmd = wrk.command("dedup");
+    cmd.arg("--accent-insensitive").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // accents are disregarded, but case is not
+    let expected = vec![svec!["name"], svec!["jose"], svec!["José"], svec!["Zoe"]];
+    assert_eq!(got, expected);
+
+    let got = wrk.output_stderr(&mut cmd);
+    assert_eq!(got, "1\n");
+}
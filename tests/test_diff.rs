@@ -328,10 +328,12 @@ fn diff_key_sort() {
     ]);
 
     let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // the key values differ, so the second rows are a deleted and an added row,
+    // sorted by h1, then h3
     let expected: Vec<Vec<String>> = vec![
         svec!["diffresult", "h1", "h2", "h3"],
-        svec!["+", "2", "booz", "fart"],
         svec!["-", "2", "fooz", "bart"],
+        svec!["+", "2", "booz", "fart"],
     ];
 
     assert_eq!(got, expected);
@@ -689,6 +691,298 @@ diffresult,h1,h2,h3,h4
     assert_eq!(got.as_str(), expected);
 }
 
+#[test]
+fn diff_key_name_different_position() {
+    let wrk = Workdir::new("diff_key_name_different_position");
+
+    wrk.create(
+        "left.csv",
+        vec![svec!["id", "name"], svec!["1", "foo"], svec!["2", "bar"]],
+    );
+    wrk.create(
+        "right.csv",
+        vec![svec!["name", "id"], svec!["foo", "1"], svec!["baz", "2"]],
+    );
+
+    let mut cmd = wrk.command("diff");
+    cmd.args(["left.csv", "right.csv", "--key", "id"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn diff_tolerance_ignore_case() {
+    let wrk = Workdir::new("diff_tolerance_ignore_case");
+
+    wrk.create(
+        "left.csv",
+        vec![
+            svec!["name", "id", "price"],
+            svec!["Foo", "1", "10.00"],
+            svec!["Bar", "2", "5"],
+        ],
+    );
+    wrk.create(
+        "right.csv",
+        vec![
+            svec!["name", "id", "price"],
+            svec!["foo", "1", "10.004"],
+            svec!["Bar", "2", "6"],
+        ],
+    );
+
+    let mut cmd = wrk.command("diff");
+    cmd.args([
+        "left.csv",
+        "right.csv",
+        "--key",
+        "id",
+        "--tolerance",
+        "price=0.01",
+        "--ignore-case",
+        "name",
+    ]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["diffresult", "name", "id", "price"],
+        svec!["-", "Bar", "2", "5"],
+        svec!["+", "Bar", "2", "6"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn diff_relative_tolerance_ignore_whitespace_ignore_columns() {
+    let wrk = Workdir::new("diff_relative_tolerance_ignore_whitespace_ignore_columns");
+
+    wrk.create(
+        "left.csv",
+        vec![
+            svec!["id", "qty", "note", "ts"],
+            svec!["1", "100", "a  b", "t1"],
+            svec!["2", "200", "x", "t1"],
+        ],
+    );
+    wrk.create(
+        "right.csv",
+        vec![
+            svec!["id", "qty", "note", "ts"],
+            svec!["1", "101", "a b ", "t2"],
+            svec!["2", "210", "x", "t2"],
+        ],
+    );
+
+    let mut cmd = wrk.command("diff");
+    cmd.args([
+        "left.csv",
+        "right.csv",
+        "--tolerance",
+        "qty=1%",
+        "--ignore-whitespace",
+        "note",
+        "--ignore-columns",
+        "ts",
+        "--drop-equal-fields",
+    ]);
+
+    // ts is ignored, so it's dropped as an equal field
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["diffresult", "id", "qty", "note", "ts"],
+        svec!["-", "2", "200", "", ""],
+        svec!["+", "2", "210", "", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn diff_summary() {
+    let wrk = Workdir::new("diff_summary");
+
+    wrk.create(
+        "left.csv",
+        vec![
+            svec!["h1", "h2", "h3"],
+            svec!["1", "a", "x"],
+            svec!["2", "b", "y"],
+            svec!["3", "c", "z"],
+        ],
+    );
+    wrk.create(
+        "right.csv",
+        vec![
+            svec!["h1", "h2", "h3"],
+            svec!["2", "B", "y"],
+            svec!["3", "cc", "zz"],
+            svec!["4", "d", "w"],
+        ],
+    );
+
+    let mut cmd = wrk.command("diff");
+    cmd.args(["left.csv", "right.csv", "--summary"]);
+
+    let got: serde_json::Value = serde_json::from_str(&wrk.stdout::<String>(&mut cmd)).unwrap();
+    let expected = serde_json::json!({
+        "added": 1,
+        "deleted": 1,
+        "modified": 2,
+        "modified_columns": {"h2": 2, "h3": 1}
+    });
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("diff");
+    cmd.args(["left.csv", "right.csv", "--summary", "--ignore-case", "*"]);
+
+    let got: serde_json::Value = serde_json::from_str(&wrk.stdout::<String>(&mut cmd)).unwrap();
+    let expected = serde_json::json!({
+        "added": 1,
+        "deleted": 1,
+        "modified": 1,
+        "modified_columns": {"h2": 1, "h3": 1}
+    });
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn diff_invalid_tolerance() {
+    let wrk = Workdir::new("diff_invalid_tolerance");
+
+    wrk.create("left.csv", vec![svec!["h1", "h2"], svec!["1", "2"]]);
+    wrk.create("right.csv", vec![svec!["h1", "h2"], svec!["1", "3"]]);
+
+    let mut cmd = wrk.command("diff");
+    cmd.args(["left.csv", "right.csv", "--tolerance", "h2"]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("diff");
+    cmd.args(["left.csv", "right.csv", "--tolerance", "h2=-1"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn diff_sorted() {
+    let wrk = Workdir::new("diff_sorted");
+
+    wrk.create(
+        "left.csv",
+        vec![
+            svec!["id", "val"],
+            svec!["1", "a"],
+            svec!["2", "b"],
+            svec!["3", "c"],
+        ],
+    );
+    wrk.create(
+        "right.csv",
+        vec![
+            svec!["id", "val"],
+            svec!["2", "B"],
+            svec!["3", "c"],
+            svec!["4", "d"],
+        ],
+    );
+
+    let mut cmd = wrk.command("diff");
+    cmd.args(["left.csv", "right.csv", "--sorted"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["diffresult", "id", "val"],
+        svec!["-", "1", "a"],
+        svec!["-", "2", "b"],
+        svec!["+", "2", "B"],
+        svec!["+", "4", "d"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn diff_sorted_unsorted_input() {
+    let wrk = Workdir::new("diff_sorted_unsorted_input");
+
+    wrk.create(
+        "left.csv",
+        vec![svec!["id", "val"], svec!["2", "b"], svec!["1", "a"]],
+    );
+    wrk.create(
+        "right.csv",
+        vec![svec!["id", "val"], svec!["1", "a"], svec!["2", "b"]],
+    );
+
+    let mut cmd = wrk.command("diff");
+    cmd.args(["left.csv", "right.csv", "--sorted"]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("diff");
+    cmd.args(["left.csv", "right.csv", "--sorted", "--sort-columns", "1"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn diff_extsort() {
+    let wrk = Workdir::new("diff_extsort");
+
+    wrk.create(
+        "left.csv",
+        vec![
+            svec!["val", "id", "price"],
+            svec!["c", "3", "1.5"],
+            svec!["a", "1", "7"],
+            svec!["b", "2", "2"],
+        ],
+    );
+    wrk.create(
+        "right.csv",
+        vec![
+            svec!["val", "id", "price"],
+            svec!["d", "4", "1"],
+            svec!["b", "2", "2.001"],
+            svec!["c", "3", "1.6"],
+        ],
+    );
+
+    let mut cmd = wrk.command("diff");
+    cmd.args([
+        "left.csv",
+        "right.csv",
+        "--key",
+        "id",
+        "--extsort",
+        "--tolerance",
+        "price=0.01",
+        "--drop-equal-fields",
+    ]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["diffresult", "val", "id", "price"],
+        svec!["-", "a", "1", "7"],
+        svec!["-", "", "3", "1.5"],
+        svec!["+", "", "3", "1.6"],
+        svec!["+", "d", "4", "1"],
+    ];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("diff");
+    cmd.args([
+        "left.csv",
+        "right.csv",
+        "--key",
+        "id",
+        "--extsort",
+        "--summary",
+    ]);
+
+    let got: serde_json::Value = serde_json::from_str(&wrk.stdout::<String>(&mut cmd)).unwrap();
+    let expected = serde_json::json!({
+        "added": 1,
+        "deleted": 1,
+        "modified": 2,
+        "modified_columns": {"price": 2}
+    });
+    assert_eq!(got, expected);
+}
+
 fn create_file_with_delim(wrk: &Workdir, file_path_new: &str, file_path: &str, delimiter: u8) {
     let mut select_cmd = wrk.command("select");
     select_cmd.args(["1-", file_path]);
@@ -114,3 +114,108 @@ b,2"
     .to_string();
     assert_eq!(got, expected);
 }
+
+#[test]
+fn edit_corrections_by_row_audit() {
+    let wrk = Workdir::new("edit_corrections_by_row_audit");
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["letter", "number"],
+            svec!["a", "1"],
+            svec!["b", "2"],
+            svec!["c", "3"],
+        ],
+    );
+    wrk.create(
+        "corrections.csv",
+        vec![
+            svec!["row", "column", "value"],
+            svec!["2", "letter", "x"],
+            svec!["0", "1", "10"],
+            svec!["2", "_", "30"],
+        ],
+    );
+
+    let mut cmd = wrk.command("edit");
+    cmd.args(["--corrections", "corrections.csv", "--audit", "audit.csv"])
+        .arg("data.csv");
+
+    let got: String = wrk.stdout(&mut cmd);
+    let expected = "letter,number
+a,10
+b,2
+x,30"
+        .to_string();
+    assert_eq!(got, expected);
+
+    let audit = wrk.read_to_string("audit.csv");
+    assert_eq!(
+        audit,
+        "row,column,old_value,new_value\n0,number,1,10\n2,letter,c,x\n2,number,3,30\n"
+    );
+}
+
+#[test]
+fn edit_corrections_by_key() {
+    let wrk = Workdir::new("edit_corrections_by_key");
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["id", "year", "status"],
+            svec!["A", "2023", "open"],
+            svec!["B", "2023", "open"],
+            svec!["A", "2024", "open"],
+            svec!["A", "2023", "open"],
+        ],
+    );
+    wrk.create(
+        "corrections.csv",
+        vec![
+            svec!["id", "year", "column", "value"],
+            svec!["A", "2023", "status", "closed"],
+            svec!["B", "2023", "year", "2022"],
+        ],
+    );
+
+    let mut cmd = wrk.command("edit");
+    cmd.args(["--corrections", "corrections.csv", "--key", "id,year"])
+        .arg("data.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "year", "status"],
+        svec!["A", "2023", "closed"],
+        svec!["B", "2022", "open"],
+        svec!["A", "2024", "open"],
+        svec!["A", "2023", "closed"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn edit_corrections_unmatched() {
+    let wrk = Workdir::new("edit_corrections_unmatched");
+    wrk.create(
+        "data.csv",
+        vec![svec!["letter", "number"], svec!["a", "1"], svec!["b", "2"]],
+    );
+    wrk.create(
+        "corrections.csv",
+        vec![
+            svec!["row", "column", "value"],
+            svec!["1", "number", "20"],
+            svec!["5", "number", "60"],
+            svec!["0", "color", "red"],
+        ],
+    );
+
+    // the matched corrections are still applied, then the unmatched ones are reported
+    let mut cmd = wrk.command("edit");
+    cmd.args(["--corrections", "corrections.csv", "--output", "out.csv"])
+        .arg("data.csv");
+    wrk.assert_err(&mut cmd);
+
+    let got = wrk.read_to_string("out.csv");
+    assert_eq!(got, "letter,number\na,1\nb,20\n");
+}
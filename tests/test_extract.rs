@@ -0,0 +1,87 @@
+use crate::workdir::Workdir;
+
+fn setup(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["id", "notes"],
+            svec!["1", "see ABC-12 and XYZ-7"],
+            svec!["2", "no code"],
+            svec!["3", "DEF-345"],
+        ],
+    );
+    wrk
+}
+
+#[test]
+fn extract_first_match() {
+    let wrk = setup("extract_first_match");
+
+    let mut cmd = wrk.command("extract");
+    cmd.args([r"(?P<code>[A-Z]{3})-(?P<num>\d+)", "notes", "data.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "notes", "code", "num"],
+        svec!["1", "see ABC-12 and XYZ-7", "ABC", "12"],
+        svec!["2", "no code", "", ""],
+        svec!["3", "DEF-345", "DEF", "345"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn extract_all_matches_only() {
+    let wrk = setup("extract_all_matches_only");
+
+    let mut cmd = wrk.command("extract");
+    cmd.args([r"(?P<code>[A-Z]{3})-(?P<num>\d+)", "notes", "data.csv"])
+        .args(["--all", "--matches-only"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "notes", "code", "num"],
+        svec!["1", "see ABC-12 and XYZ-7", "ABC", "12"],
+        svec!["1", "see ABC-12 and XYZ-7", "XYZ", "7"],
+        svec!["3", "DEF-345", "DEF", "345"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn extract_optional_group_prefix() {
+    let wrk = setup("extract_optional_group_prefix");
+
+    // the unnamed group is not extracted, the optional one is empty when it doesn't match
+    let mut cmd = wrk.command("extract");
+    cmd.args([r"(?P<id>[a-z]{3})-(\d)(?P<rest>\d+)?", "notes", "data.csv"])
+        .args(["--ignore-case", "--prefix", "x_"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "notes", "x_id", "x_rest"],
+        svec!["1", "see ABC-12 and XYZ-7", "ABC", "2"],
+        svec!["2", "no code", "", ""],
+        svec!["3", "DEF-345", "DEF", "45"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn extract_column_clash() {
+    let wrk = setup("extract_column_clash");
+
+    let mut cmd = wrk.command("extract");
+    cmd.args([r"(?P<id>[A-Z]{3})", "notes", "data.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn extract_no_named_groups() {
+    let wrk = setup("extract_no_named_groups");
+
+    let mut cmd = wrk.command("extract");
+    cmd.args([r"([A-Z]{3})-(\d+)", "notes", "data.csv"]);
+    wrk.assert_err(&mut cmd);
+}
@@ -53,3 +53,141 @@ fn extsort_csvmode() {
 
     assert_eq!(dos2unix(&sorted_output), dos2unix(&expected_csv));
 }
+
+#[test]
+fn extsort_csvmode_keys() {
+    let wrk = Workdir::new("extsort_csvmode_keys");
+    wrk.create_indexed(
+        "in.csv",
+        vec![
+            svec!["date", "amount", "name"],
+            svec!["2024-01-02", "10", "file10"],
+            svec!["2024-01-02", "9", "file2"],
+            svec!["2023-12-31", "5", "file1"],
+            svec!["", "7", "x"],
+            svec!["2024-01-02", "10", "file9"],
+        ],
+    );
+
+    let mut cmd = wrk.command("extsort");
+    cmd.arg("in.csv")
+        .args(["-k", "date:desc:date,amount:asc:num,name:asc:natural"])
+        .arg("out.csv");
+    wrk.assert_success(&mut cmd);
+
+    let got = wrk.read_to_string("out.csv");
+    let expected = "date,amount,name\n2024-01-02,9,file2\n2024-01-02,10,file9\n2024-01-02,10,\
+                    file10\n2023-12-31,5,file1\n,7,x\n";
+    assert_eq!(dos2unix(&got), expected);
+}
+
+#[test]
+fn extsort_linemode_collate() {
+    let wrk = Workdir::new("extsort_linemode_collate");
+    wrk.create_from_string("in.txt", "Zürich\nÄngelholm\nBerlin\napple\n");
+
+    let mut cmd = wrk.command("extsort");
+    cmd.args(["--collate", "en"])
+        .arg("--no-headers")
+        .arg("in.txt")
+        .arg("out.txt");
+    wrk.assert_success(&mut cmd);
+
+    let got = wrk.read_to_string("out.txt");
+    assert_eq!(dos2unix(&got), "Ängelholm\napple\nBerlin\nZürich\n");
+}
+
+#[test]
+fn extsort_csvmode_numeric_unique() {
+    let wrk = Workdir::new("extsort_csvmode_numeric_unique");
+    wrk.create_indexed(
+        "in.csv",
+        vec![
+            svec!["n", "s"],
+            svec!["10", "a"],
+            svec!["9", "b"],
+            svec!["10", "c"],
+            svec!["", "d"],
+            svec!["1.5", "e"],
+        ],
+    );
+
+    let mut cmd = wrk.command("extsort");
+    cmd.arg("in.csv")
+        .args(["--select", "n"])
+        .arg("--numeric")
+        .arg("--unique")
+        .arg("out.csv");
+    wrk.assert_success(&mut cmd);
+
+    let got = wrk.read_to_string("out.csv");
+    assert_eq!(dos2unix(&got), "n,s\n,d\n1.5,e\n9,b\n10,a\n");
+
+    // reversing keeps the sort stable, so the first "10" is still kept
+    let mut cmd = wrk.command("extsort");
+    cmd.arg("in.csv")
+        .args(["--select", "n"])
+        .arg("--numeric")
+        .arg("--reverse")
+        .arg("--unique")
+        .arg("out_rev.csv");
+    wrk.assert_success(&mut cmd);
+
+    let got = wrk.read_to_string("out_rev.csv");
+    assert_eq!(dos2unix(&got), "n,s\n10,a\n9,b\n1.5,e\n,d\n");
+}
+
+#[test]
+fn extsort_csvmode_numeric_large_ints() {
+    let wrk = Workdir::new("extsort_csvmode_numeric_large_ints");
+    wrk.create_indexed(
+        "in.csv",
+        vec![
+            svec!["id"],
+            svec!["9223372036854775807"],
+            svec!["9223372036854775805"],
+            svec!["9223372036854775806"],
+        ],
+    );
+
+    let mut cmd = wrk.command("extsort");
+    cmd.arg("in.csv")
+        .args(["--select", "id"])
+        .arg("--numeric")
+        .arg("--unique")
+        .arg("out.csv");
+    wrk.assert_success(&mut cmd);
+
+    let got = wrk.read_to_string("out.csv");
+    assert_eq!(
+        dos2unix(&got),
+        "id\n9223372036854775805\n9223372036854775806\n9223372036854775807\n"
+    );
+}
+
+#[test]
+fn extsort_csvmode_parallel_partitions() {
+    let wrk = Workdir::new("extsort_csvmode_parallel_partitions");
+    // enough records to be split into several partitions that are sorted in parallel
+    let mut rows = vec![svec!["id", "group"]];
+    for i in (1..=5000).rev() {
+        rows.push(vec![i.to_string(), (i % 7).to_string()]);
+    }
+    wrk.create_indexed("in.csv", rows);
+
+    let mut cmd = wrk.command("extsort");
+    cmd.arg("in.csv")
+        .args(["-k", "group:num,id:desc:num"])
+        .args(["--jobs", "4"])
+        .arg("out.csv");
+    wrk.assert_success(&mut cmd);
+
+    let mut expected = vec!["id,group".to_string()];
+    let mut ids: Vec<u32> = (1..=5000).collect();
+    ids.sort_by(|a, b| (a % 7).cmp(&(b % 7)).then(b.cmp(a)));
+    for id in ids {
+        expected.push(format!("{id},{}", id % 7));
+    }
+    let got = wrk.read_to_string("out.csv");
+    assert_eq!(dos2unix(&got), expected.join("\n") + "\n");
+}
@@ -626,6 +626,81 @@ fn frequency_issue1962() {
     assert_eq!(got, expected);
 }
 
+#[test]
+fn frequency_approx() {
+    let wrk = Workdir::new("frequency_approx");
+    let testdata = wrk.load_test_file("data1962.csv");
+    let mut cmd = wrk.command("frequency");
+    cmd.arg("--approx").args(["--limit", "5"]).arg(testdata);
+
+    // with the default number of counters, the approximation is exact
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count", "percentage", "error"],
+        svec!["year", "2024", "24", "8", "0"],
+        svec!["year", "2023", "23", "7.66667", "0"],
+        svec!["year", "2022", "22", "7.33333", "0"],
+        svec!["year", "2021", "21", "7", "0"],
+        svec!["year", "2020", "20", "6.66667", "0"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_approx_small_size() {
+    let wrk = Workdir::new("frequency_approx_small_size");
+    let testdata = wrk.load_test_file("data1962.csv");
+    let mut cmd = wrk.command("frequency");
+    cmd.arg("--approx")
+        .args(["--approx-size", "10"])
+        .args(["--limit", "5"])
+        .arg(testdata);
+
+    // the true counts of 2024..2020 are 24..20, and are all within [count - error, count]
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["field", "value", "count", "percentage", "error"],
+        svec!["year", "2024", "42", "14", "18"],
+        svec!["year", "2023", "39", "13", "16"],
+        svec!["year", "2022", "36", "12", "14"],
+        svec!["year", "2021", "33", "11", "12"],
+        svec!["year", "2020", "30", "10", "10"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn frequency_approx_indexed() {
+    let wrk = Workdir::new("frequency_approx_indexed");
+    let rows: Vec<Vec<String>> = std::iter::once(svec!["year"])
+        .chain(
+            (2001..=2024)
+                .flat_map(|year: u32| (0..(year - 2000)).map(move |_| vec![year.to_string()])),
+        )
+        .collect();
+    wrk.create_indexed("in.csv", rows);
+
+    let mut cmd = wrk.command("frequency");
+    cmd.arg("--approx")
+        .args(["--approx-size", "12"])
+        .args(["--limit", "3"])
+        .args(["--jobs", "4"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 4);
+    assert_eq!(
+        got[0],
+        svec!["field", "value", "count", "percentage", "error"]
+    );
+    for row in got.iter().skip(1) {
+        let true_count = row[1].parse::<u64>().unwrap() - 2000;
+        let count = row[2].parse::<u64>().unwrap();
+        let error = row[4].parse::<u64>().unwrap();
+        assert!(count - error <= true_count && true_count <= count);
+    }
+}
+
 // This tests that a frequency table computed by `qsv` is always the same
 // as the frequency table computed in memory.
 #[test]
@@ -0,0 +1,209 @@
+use crate::workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["region", "customer", "amount", "date"],
+        svec!["east", "alice", "10.5", "2024-01-01"],
+        svec!["west", "bob", "0.1", "2024-01-02"],
+        svec!["east", "carol", "", "2024-01-03"],
+        svec!["west", "bob", "0.2", "2024-01-04"],
+        svec!["east", "alice", "abc", "2024-01-05"],
+        svec!["north", "dave", "7", "2024-01-06"],
+    ]
+}
+
+const ALL_AGGS: &str = "count,count:amount,count_distinct:customer,sum:amount,mean:amount,min:\
+                        amount,max:amount,first:date,last:date";
+
+fn all_aggs_expected() -> Vec<Vec<String>> {
+    vec![
+        svec![
+            "region",
+            "count",
+            "count_amount",
+            "count_distinct_customer",
+            "sum_amount",
+            "mean_amount",
+            "min_amount",
+            "max_amount",
+            "first_date",
+            "last_date"
+        ],
+        svec![
+            "east",
+            "3",
+            "2",
+            "2",
+            "10.5",
+            "10.5",
+            "10.5",
+            "abc",
+            "2024-01-01",
+            "2024-01-05"
+        ],
+        svec![
+            "north",
+            "1",
+            "1",
+            "1",
+            "7",
+            "7",
+            "7",
+            "7",
+            "2024-01-06",
+            "2024-01-06"
+        ],
+        svec![
+            "west",
+            "2",
+            "2",
+            "1",
+            "0.3",
+            "0.15",
+            "0.1",
+            "0.2",
+            "2024-01-02",
+            "2024-01-04"
+        ],
+    ]
+}
+
+#[test]
+fn groupby_count() {
+    let wrk = Workdir::new("groupby_count");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("groupby");
+    cmd.arg("region").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["region", "count"],
+        svec!["east", "3"],
+        svec!["north", "1"],
+        svec!["west", "2"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn groupby_all_aggs() {
+    let wrk = Workdir::new("groupby_all_aggs");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("groupby");
+    cmd.arg("region").args(["--agg", ALL_AGGS]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, all_aggs_expected());
+}
+
+#[test]
+fn groupby_spill() {
+    let wrk = Workdir::new("groupby_spill");
+    wrk.create("in.csv", data());
+
+    // force spilling partial aggregates to disk for every new group
+    let mut cmd = wrk.command("groupby");
+    cmd.arg("region")
+        .args(["--agg", ALL_AGGS])
+        .args(["--max-groups", "1"])
+        .args(["--tmp-dir", &wrk.path(".").to_string_lossy()])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, all_aggs_expected());
+}
+
+#[test]
+fn groupby_indexed() {
+    let wrk = Workdir::new("groupby_indexed");
+    wrk.create_indexed("in.csv", data());
+
+    let mut cmd = wrk.command("groupby");
+    cmd.arg("region")
+        .args(["--agg", ALL_AGGS])
+        .args(["--jobs", "3"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, all_aggs_expected());
+}
+
+#[test]
+fn groupby_multiple_keys() {
+    let wrk = Workdir::new("groupby_multiple_keys");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("groupby");
+    cmd.arg("region,customer")
+        .args(["--agg", "count,sum:amount"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["region", "customer", "count", "sum_amount"],
+        svec!["east", "alice", "2", "10.5"],
+        svec!["east", "carol", "1", ""],
+        svec!["north", "dave", "1", "7"],
+        svec!["west", "bob", "2", "0.3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn groupby_no_headers() {
+    let wrk = Workdir::new("groupby_no_headers");
+    wrk.create("in.csv", data().into_iter().skip(1).collect::<Vec<_>>());
+
+    let mut cmd = wrk.command("groupby");
+    cmd.arg("1")
+        .args(["--agg", "sum:3"])
+        .arg("--no-headers")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["east", "10.5"],
+        svec!["north", "7"],
+        svec!["west", "0.3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn groupby_mean_round() {
+    let wrk = Workdir::new("groupby_mean_round");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["k", "v"],
+            svec!["a", "1"],
+            svec!["a", "1"],
+            svec!["a", "2"],
+        ],
+    );
+
+    let mut cmd = wrk.command("groupby");
+    cmd.arg("k")
+        .args(["--agg", "mean:v"])
+        .args(["--round", "2"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["k", "mean_v"], svec!["a", "1.33"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn groupby_invalid_agg() {
+    let wrk = Workdir::new("groupby_invalid_agg");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("groupby");
+    cmd.arg("region")
+        .args(["--agg", "median:amount"])
+        .arg("in.csv");
+
+    wrk.assert_err(&mut cmd);
+}
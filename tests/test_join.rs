@@ -301,3 +301,254 @@ fn join_cross_no_headers() {
     ];
     assert_eq!(got, expected);
 }
+
+fn setup_fuzzy(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create(
+        "customers.csv",
+        vec![
+            svec!["company", "country"],
+            svec!["Acme Corp", "US"],
+            svec!["Globex", "US"],
+            svec!["Initech", "US"],
+            svec!["Acme Corp", "DE"],
+        ],
+    );
+    wrk.create(
+        "companies.csv",
+        vec![
+            svec!["name", "country", "id"],
+            svec!["Acme Corp.", "US", "1"],
+            svec!["Acme Co", "US", "2"],
+            svec!["Globex Inc", "US", "3"],
+            svec!["Initech", "DE", "4"],
+            svec!["Acme Corp", "DE", "5"],
+        ],
+    );
+    wrk
+}
+
+#[test]
+fn join_fuzzy_block() {
+    let wrk = setup_fuzzy("join_fuzzy_block");
+
+    let mut cmd = wrk.command("join");
+    cmd.args(["--fuzzy", "levenshtein", "--threshold", "0.8"])
+        .args(["--block", "country"])
+        .args(["company", "customers.csv", "name", "companies.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["company", "country", "name", "country", "id", "fuzzy_score"],
+        svec!["Acme Corp", "US", "Acme Corp.", "US", "1", "0.9"],
+        svec!["Acme Corp", "DE", "Acme Corp", "DE", "5", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_fuzzy_left() {
+    let wrk = setup_fuzzy("join_fuzzy_left");
+
+    let mut cmd = wrk.command("join");
+    cmd.args(["--fuzzy", "levenshtein", "--threshold", "0.8", "--left"])
+        .args(["--block", "country"])
+        .args(["company", "customers.csv", "name", "companies.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["company", "country", "name", "country", "id", "fuzzy_score"],
+        svec!["Acme Corp", "US", "Acme Corp.", "US", "1", "0.9"],
+        svec!["Globex", "US", "", "", "", ""],
+        svec!["Initech", "US", "", "", "", ""],
+        svec!["Acme Corp", "DE", "Acme Corp", "DE", "5", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_fuzzy_top_k() {
+    let wrk = setup_fuzzy("join_fuzzy_top_k");
+    wrk.create("acme.csv", vec![svec!["company"], svec!["Acme Corp"]]);
+
+    let mut cmd = wrk.command("join");
+    cmd.args(["--fuzzy", "levenshtein", "--threshold", "0.7"])
+        .args(["--top-k", "3", "--score-col", "score"])
+        .args(["company", "acme.csv", "name", "companies.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["company", "name", "country", "id", "score"],
+        svec!["Acme Corp", "Acme Corp", "DE", "5", "1"],
+        svec!["Acme Corp", "Acme Corp.", "US", "1", "0.9"],
+        svec!["Acme Corp", "Acme Co", "US", "2", "0.7778"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_fuzzy_errors() {
+    let wrk = setup_fuzzy("join_fuzzy_errors");
+
+    let mut cmd = wrk.command("join");
+    cmd.args(["--fuzzy", "levenshtein", "--right"]).args([
+        "company",
+        "customers.csv",
+        "name",
+        "companies.csv",
+    ]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("join");
+    cmd.args(["--fuzzy", "soundex"])
+        .args(["company", "customers.csv", "name", "companies.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+fn sort_merge_join(name: &str, join: Option<&str>) -> Vec<Vec<String>> {
+    let wrk = setup(name, true);
+    let mut cmd = wrk.command("join");
+    cmd.arg("--sort-merge");
+    if let Some(join) = join {
+        cmd.arg(join);
+    }
+    cmd.args(["city", "cities.csv", "city", "places.csv"]);
+    wrk.read_stdout(&mut cmd)
+}
+
+#[test]
+fn join_sort_merge_inner() {
+    let got = sort_merge_join("join_sort_merge_inner", None);
+    let expected = make_rows(
+        true,
+        false,
+        vec![
+            svec!["Boston", "MA", "Boston", "Boston Garden"],
+            svec!["Boston", "MA", "Boston", "Logan Airport"],
+            svec!["Buffalo", "NY", "Buffalo", "Ralph Wilson Stadium"],
+        ],
+    );
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_sort_merge_full() {
+    let got = sort_merge_join("join_sort_merge_full", Some("--full"));
+    let expected = make_rows(
+        true,
+        false,
+        vec![
+            svec!["", "", "BOSTON", "BOSTON COMMON"],
+            svec!["Boston", "MA", "Boston", "Boston Garden"],
+            svec!["Boston", "MA", "Boston", "Logan Airport"],
+            svec!["Buffalo", "NY", "Buffalo", "Ralph Wilson Stadium"],
+            svec!["New York", "NY", "", ""],
+            svec!["", "", "Orlando", "Disney World"],
+            svec!["San Francisco", "CA", "", ""],
+        ],
+    );
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_sort_merge_left_right() {
+    let got = sort_merge_join("join_sort_merge_left", Some("--left"));
+    let expected = make_rows(
+        true,
+        false,
+        vec![
+            svec!["Boston", "MA", "Boston", "Boston Garden"],
+            svec!["Boston", "MA", "Boston", "Logan Airport"],
+            svec!["Buffalo", "NY", "Buffalo", "Ralph Wilson Stadium"],
+            svec!["New York", "NY", "", ""],
+            svec!["San Francisco", "CA", "", ""],
+        ],
+    );
+    assert_eq!(got, expected);
+
+    let got = sort_merge_join("join_sort_merge_right", Some("--right"));
+    let expected = make_rows(
+        true,
+        false,
+        vec![
+            svec!["", "", "BOSTON", "BOSTON COMMON"],
+            svec!["Boston", "MA", "Boston", "Boston Garden"],
+            svec!["Boston", "MA", "Boston", "Logan Airport"],
+            svec!["Buffalo", "NY", "Buffalo", "Ralph Wilson Stadium"],
+            svec!["", "", "Orlando", "Disney World"],
+        ],
+    );
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_sort_merge_anti_semi() {
+    let got = sort_merge_join("join_sort_merge_anti", Some("--left-anti"));
+    let expected = make_rows(
+        true,
+        true,
+        vec![svec!["New York", "NY"], svec!["San Francisco", "CA"]],
+    );
+    assert_eq!(got, expected);
+
+    let got = sort_merge_join("join_sort_merge_semi", Some("--left-semi"));
+    let expected = make_rows(
+        true,
+        true,
+        vec![svec!["Boston", "MA"], svec!["Buffalo", "NY"]],
+    );
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn join_sort_merge_cross_error() {
+    let wrk = setup("join_sort_merge_cross_error", true);
+    let mut cmd = wrk.command("join");
+    cmd.args(["--sort-merge", "--cross"])
+        .args(["city", "cities.csv", "city", "places.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn join_accent_insensitive() {
+    let wrk = Workdir::new("join_accent_insensitive");
+    wrk.create(
+        "cities.csv",
+        vec![
+            svec!["city"],
+            svec!["Zürich"],
+            svec!["malmo"],
+            svec!["Paris"],
+        ],
+    );
+    wrk.create(
+        "ids.csv",
+        vec![
+            svec!["name", "id"],
+            svec!["Zurich", "1"],
+            svec!["Malmö", "2"],
+        ],
+    );
+
+    let mut cmd = wrk.command("join");
+    cmd.arg("--accent-insensitive")
+        .args(["city", "cities.csv", "name", "ids.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["city", "name", "id"], svec!["Zürich", "Zurich", "1"]];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("join");
+    cmd.arg("--accent-insensitive").arg("--ignore-case").args([
+        "city",
+        "cities.csv",
+        "name",
+        "ids.csv",
+    ]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["city", "name", "id"],
+        svec!["Zürich", "Zurich", "1"],
+        svec!["malmo", "Malmö", "2"],
+    ];
+    assert_eq!(got, expected);
+}
@@ -1285,3 +1285,170 @@ fn joinp_ignore_case() {
     ];
     assert_eq!(got, expected);
 }
+
+fn setup_range(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create(
+        "events.csv",
+        vec![
+            svec!["event", "ts"],
+            svec!["a", "5"],
+            svec!["b", "10"],
+            svec!["c", "15"],
+            svec!["d", "25"],
+        ],
+    );
+    wrk.create(
+        "bands.csv",
+        vec![
+            svec!["band", "lo", "hi"],
+            svec!["low", "0", "10"],
+            svec!["mid", "10", "20"],
+        ],
+    );
+    wrk
+}
+
+#[test]
+fn joinp_range() {
+    let wrk = setup_range("joinp_range");
+
+    let mut cmd = wrk.command("joinp");
+    cmd.args(["--range", "ts,lo,hi"])
+        .args([
+            "--sql-filter",
+            "select * from join_result order by event, band",
+        ])
+        .args(["events.csv", "bands.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["event", "ts", "band", "lo", "hi"],
+        svec!["a", "5", "low", "0", "10"],
+        svec!["b", "10", "low", "0", "10"],
+        svec!["b", "10", "mid", "10", "20"],
+        svec!["c", "15", "mid", "10", "20"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn joinp_range_closed_left() {
+    let wrk = setup_range("joinp_range_closed_left");
+
+    let mut cmd = wrk.command("joinp");
+    cmd.args(["--range", "ts,lo,hi", "--range-closed", "left"])
+        .args([
+            "--sql-filter",
+            "select * from join_result order by event, band",
+        ])
+        .args(["events.csv", "bands.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["event", "ts", "band", "lo", "hi"],
+        svec!["a", "5", "low", "0", "10"],
+        svec!["b", "10", "mid", "10", "20"],
+        svec!["c", "15", "mid", "10", "20"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn joinp_range_with_keys() {
+    let wrk = Workdir::new("joinp_range_with_keys");
+    wrk.create(
+        "events.csv",
+        vec![
+            svec!["event", "region", "ts"],
+            svec!["x", "east", "5"],
+            svec!["y", "west", "5"],
+            svec!["z", "east", "50"],
+        ],
+    );
+    wrk.create(
+        "rates.csv",
+        vec![
+            svec!["region", "lo", "hi", "rate"],
+            svec!["east", "0", "10", "1"],
+            svec!["west", "10", "20", "2"],
+            svec!["east", "40", "60", "3"],
+        ],
+    );
+
+    let mut cmd = wrk.command("joinp");
+    cmd.args(["--range", "ts,lo,hi"])
+        .args(["--sql-filter", "select * from join_result order by event"])
+        .args(["region", "events.csv", "region", "rates.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["event", "region", "ts", "lo", "hi", "rate"],
+        svec!["x", "east", "5", "0", "10", "1"],
+        svec!["z", "east", "50", "40", "60", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn joinp_range_with_keys_bound_name_clash() {
+    let wrk = Workdir::new("joinp_range_with_keys_bound_name_clash");
+    // the left "lo" column must not be mistaken for the lower bound
+    wrk.create(
+        "events.csv",
+        vec![
+            svec!["event", "region", "ts", "lo"],
+            svec!["x", "east", "5", "100"],
+            svec!["y", "west", "5", "100"],
+            svec!["z", "east", "50", "100"],
+        ],
+    );
+    wrk.create(
+        "rates.csv",
+        vec![
+            svec!["region", "lo", "hi", "rate"],
+            svec!["east", "0", "10", "1"],
+            svec!["west", "10", "20", "2"],
+            svec!["east", "40", "60", "3"],
+        ],
+    );
+
+    let mut cmd = wrk.command("joinp");
+    cmd.args(["--range", "ts,lo,hi"])
+        .args(["--sql-filter", "select * from join_result order by event"])
+        .args(["region", "events.csv", "region", "rates.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["event", "region", "ts", "lo", "lo_right", "hi", "rate"],
+        svec!["x", "east", "5", "100", "0", "10", "1"],
+        svec!["z", "east", "50", "100", "40", "60", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn joinp_range_errors() {
+    let wrk = setup_range("joinp_range_errors");
+
+    let mut cmd = wrk.command("joinp");
+    cmd.args(["--range", "ts,lo,hi", "--left"])
+        .args(["events.csv", "bands.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("joinp");
+    cmd.args(["--range", "ts,lo"])
+        .args(["events.csv", "bands.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    // without join keys, there are no keys to coalesce or validate
+    let mut cmd = wrk.command("joinp");
+    cmd.args(["--range", "ts,lo,hi", "--coalesce"])
+        .args(["events.csv", "bands.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("joinp");
+    cmd.args(["--range", "ts,lo,hi", "--validate", "onetoone"])
+        .args(["events.csv", "bands.csv"]);
+    wrk.assert_err(&mut cmd);
+}
@@ -0,0 +1,201 @@
+use crate::workdir::Workdir;
+
+fn setup(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create(
+        "base.csv",
+        vec![
+            svec!["id", "name", "qty"],
+            svec!["1", "a", "10"],
+            svec!["2", "b", "20"],
+        ],
+    );
+    wrk.create(
+        "changes1.csv",
+        vec![
+            svec!["id", "name", "qty"],
+            svec!["2", "B", "21"],
+            svec!["3", "c", "30"],
+        ],
+    );
+    // a partial update, with the columns in a different order
+    wrk.create("changes2.csv", vec![svec!["qty", "id"], svec!["11", "1"]]);
+    wrk
+}
+
+#[test]
+fn merge_upsert() {
+    let wrk = setup("merge_upsert");
+
+    let mut cmd = wrk.command("merge");
+    cmd.args(["id", "base.csv", "changes1.csv", "changes2.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name", "qty"],
+        svec!["1", "a", "11"],
+        svec!["2", "B", "21"],
+        svec!["3", "c", "30"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn merge_update_only() {
+    let wrk = setup("merge_update_only");
+
+    let mut cmd = wrk.command("merge");
+    cmd.args(["id", "base.csv", "changes1.csv", "changes2.csv"])
+        .args(["--strategy", "update-only"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name", "qty"],
+        svec!["1", "a", "11"],
+        svec!["2", "B", "21"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn merge_insert_only() {
+    let wrk = setup("merge_insert_only");
+
+    let mut cmd = wrk.command("merge");
+    cmd.args(["id", "base.csv", "changes1.csv", "changes2.csv"])
+        .args(["--strategy", "insert-only"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name", "qty"],
+        svec!["1", "a", "10"],
+        svec!["2", "b", "20"],
+        svec!["3", "c", "30"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn merge_last_write_wins_with_deletes() {
+    let wrk = Workdir::new("merge_last_write_wins_with_deletes");
+    wrk.create(
+        "base.csv",
+        vec![
+            svec!["id", "val", "ts"],
+            svec!["1", "a", "2024-01-02"],
+            svec!["2", "b", "2024-01-01"],
+        ],
+    );
+    wrk.create(
+        "changes.csv",
+        vec![
+            svec!["id", "val", "ts", "op"],
+            svec!["1", "old", "2024-01-01", "U"],
+            svec!["2", "new", "2024-01-03", "U"],
+            svec!["1", "", "2024-01-05", "D"],
+            svec!["1", "zombie", "2024-01-04", "I"],
+            svec!["3", "c", "2024-01-01", "I"],
+        ],
+    );
+
+    let mut cmd = wrk.command("merge");
+    cmd.args(["id", "base.csv", "changes.csv"])
+        .args(["--timestamp", "ts"])
+        .args(["--delete-col", "op"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "val", "ts"],
+        svec!["2", "new", "2024-01-03"],
+        svec!["3", "c", "2024-01-01"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn merge_scd2() {
+    let wrk = Workdir::new("merge_scd2");
+    wrk.create(
+        "base.csv",
+        vec![svec!["id", "val"], svec!["1", "a"], svec!["2", "b"]],
+    );
+    wrk.create(
+        "changes.csv",
+        vec![
+            svec!["id", "val"],
+            svec!["1", "a2"],
+            svec!["2", "b"],
+            svec!["3", "c"],
+        ],
+    );
+
+    let mut cmd = wrk.command("merge");
+    cmd.args(["id", "base.csv", "changes.csv"])
+        .arg("--scd2")
+        .args(["--as-of", "2024-02-01"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "val", "valid_from", "valid_to", "is_current"],
+        svec!["1", "a", "", "2024-02-01", "false"],
+        svec!["2", "b", "", "", "true"],
+        svec!["1", "a2", "2024-02-01", "", "true"],
+        svec!["3", "c", "2024-02-01", "", "true"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn merge_scd2_existing_history() {
+    let wrk = Workdir::new("merge_scd2_existing_history");
+    wrk.create(
+        "base.csv",
+        vec![
+            svec!["id", "val", "valid_from", "valid_to", "is_current"],
+            svec!["1", "a", "", "2024-02-01", "false"],
+            svec!["2", "b", "", "", "true"],
+            svec!["1", "a2", "2024-02-01", "", "true"],
+            svec!["3", "c", "2024-02-01", "", "true"],
+        ],
+    );
+    wrk.create(
+        "changes.csv",
+        vec![
+            svec!["id", "val", "op"],
+            svec!["2", "b2", "U"],
+            svec!["1", "", "D"],
+        ],
+    );
+
+    let mut cmd = wrk.command("merge");
+    cmd.args(["id", "base.csv", "changes.csv"])
+        .arg("--scd2")
+        .args(["--as-of", "2024-03-01"])
+        .args(["--delete-col", "op"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "val", "valid_from", "valid_to", "is_current"],
+        svec!["1", "a", "", "2024-02-01", "false"],
+        svec!["2", "b", "", "2024-03-01", "false"],
+        svec!["1", "a2", "2024-02-01", "2024-03-01", "false"],
+        svec!["3", "c", "2024-02-01", "", "true"],
+        svec!["2", "b2", "2024-03-01", "", "true"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn merge_errors() {
+    let wrk = setup("merge_errors");
+
+    let mut cmd = wrk.command("merge");
+    cmd.args(["id", "base.csv", "changes1.csv"])
+        .args(["--strategy", "replace"]);
+    wrk.assert_err(&mut cmd);
+
+    // the change file doesn't have the name column
+    let mut cmd = wrk.command("merge");
+    cmd.args(["name", "base.csv", "changes2.csv"]);
+    wrk.assert_err(&mut cmd);
+}
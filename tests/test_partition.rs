@@ -366,3 +366,202 @@ CO,Denver
 "
     );
 }
+
+#[test]
+fn partition_hive_multiple_columns() {
+    let wrk = Workdir::new("partition_hive_multiple_columns");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.arg("--hive")
+        .arg("state,city")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    part_eq!(
+        wrk,
+        "state=NY/city=Buffalo/part-0.csv",
+        "\
+state,city
+NY,Buffalo
+"
+    );
+    part_eq!(
+        wrk,
+        "state=CA/city=San%20Francisco/part-0.csv",
+        "\
+state,city
+CA,San Francisco
+"
+    );
+    part_eq!(
+        wrk,
+        "state=TX/city=Fort%20Worth/part-0.csv",
+        "\
+state,city
+TX,Fort Worth
+"
+    );
+}
+
+#[test]
+fn partition_hive_drop() {
+    let wrk = Workdir::new("partition_hive_drop");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.arg("--hive")
+        .arg("--drop")
+        .arg("state")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    part_eq!(
+        wrk,
+        "state=NY/part-0.csv",
+        "\
+city
+Manhatten
+Buffalo
+"
+    );
+    part_eq!(
+        wrk,
+        "state=TX/part-0.csv",
+        "\
+city
+Dallas
+Fort Worth
+"
+    );
+}
+
+#[test]
+fn partition_multiple_columns_requires_hive() {
+    let wrk = Workdir::new("partition_multiple_columns_requires_hive");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.arg("state,city").arg(&wrk.path(".")).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn partition_max_rows() {
+    let wrk = Workdir::new("partition_max_rows");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.args(["--max-rows", "1"])
+        .arg("state")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    part_eq!(
+        wrk,
+        "NY.csv",
+        "\
+state,city
+NY,Manhatten
+"
+    );
+    part_eq!(
+        wrk,
+        "NY-1.csv",
+        "\
+state,city
+NY,Buffalo
+"
+    );
+    part_eq!(
+        wrk,
+        "CA.csv",
+        "\
+state,city
+CA,San Francisco
+"
+    );
+}
+
+#[test]
+fn partition_hive_buckets() {
+    let wrk = Workdir::new("partition_hive_buckets");
+    wrk.create("in.csv", data(true));
+
+    let mut cmd = wrk.command("partition");
+    cmd.arg("--hive")
+        .args(["--buckets", "1"])
+        .args(["--max-rows", "3"])
+        .arg("state,city")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    part_eq!(
+        wrk,
+        "bucket=0/part-0.csv",
+        "\
+state,city
+NY,Manhatten
+CA,San Francisco
+TX,Dallas
+"
+    );
+    part_eq!(
+        wrk,
+        "bucket=0/part-1.csv",
+        "\
+state,city
+NY,Buffalo
+TX,Fort Worth
+"
+    );
+
+    let mut cmd = wrk.command("partition");
+    cmd.args(["--buckets", "0"])
+        .arg("state")
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+#[cfg(feature = "polars")]
+fn partition_parquet_tsv_input() {
+    let wrk = Workdir::new("partition_parquet_tsv_input");
+    // the delimiter of the input is detected from its extension
+    wrk.create_with_delim(
+        "in.tsv",
+        vec![
+            svec!["state", "city", "pop"],
+            svec!["NY", "Buffalo", "276807"],
+            svec!["TX", "Dallas", "1304379"],
+            svec!["NY", "Albany", "99224"],
+        ],
+        b'\t',
+    );
+
+    let mut cmd = wrk.command("partition");
+    cmd.args(["--format", "parquet"])
+        .arg("state")
+        .arg(&wrk.path("."))
+        .arg("in.tsv");
+    wrk.assert_success(&mut cmd);
+
+    let mut cmd = wrk.command("sqlp");
+    cmd.arg("SKIP_INPUT").arg(format!(
+        "select city, pop + 1 as pop from read_parquet('{}') order by city",
+        wrk.path("NY.parquet").display()
+    ));
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["city", "pop"],
+        svec!["Albany", "99225"],
+        svec!["Buffalo", "276808"],
+    ];
+    assert_eq!(got, expected);
+}
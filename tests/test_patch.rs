@@ -0,0 +1,143 @@
+use crate::workdir::Workdir;
+
+fn base() -> Vec<Vec<String>> {
+    vec![
+        svec!["id", "name", "qty"],
+        svec!["1", "a", "10"],
+        svec!["2", "b", "20"],
+        svec!["3", "c", "30"],
+    ]
+}
+
+fn patch() -> Vec<Vec<String>> {
+    vec![
+        svec!["diffresult", "id", "name", "qty"],
+        svec!["-", "2", "b", "20"],
+        svec!["+", "2", "b", "25"],
+        svec!["-", "3", "c", "30"],
+        svec!["+", "4", "d", "40"],
+    ]
+}
+
+#[test]
+fn patch_apply() {
+    let wrk = Workdir::new("patch_apply");
+    wrk.create("base.csv", base());
+    wrk.create("patch.csv", patch());
+
+    let mut cmd = wrk.command("patch");
+    cmd.args(["-k", "id", "base.csv", "patch.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name", "qty"],
+        svec!["1", "a", "10"],
+        svec!["2", "b", "25"],
+        svec!["4", "d", "40"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn patch_diff_roundtrip() {
+    let wrk = Workdir::new("patch_diff_roundtrip");
+    wrk.create("base.csv", base());
+    let updated = vec![
+        svec!["id", "name", "qty"],
+        svec!["1", "a", "10"],
+        svec!["2", "b", "25"],
+        svec!["4", "d", "40"],
+    ];
+    wrk.create("updated.csv", updated.clone());
+
+    for drop_equal_fields in [false, true] {
+        let mut cmd = wrk.command("diff");
+        cmd.args(["base.csv", "updated.csv", "-o", "patch.csv"]);
+        if drop_equal_fields {
+            cmd.arg("--drop-equal-fields");
+        }
+        wrk.run(&mut cmd);
+
+        let mut cmd = wrk.command("patch");
+        cmd.args(["base.csv", "patch.csv"]);
+
+        let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+        assert_eq!(got, updated);
+    }
+}
+
+#[test]
+fn patch_conflicts() {
+    let wrk = Workdir::new("patch_conflicts");
+    wrk.create(
+        "base.csv",
+        vec![
+            svec!["id", "name", "qty"],
+            svec!["1", "a", "10"],
+            svec!["2", "x", "20"],
+            svec!["3", "c", "31"],
+        ],
+    );
+    wrk.create("patch.csv", patch());
+
+    // only the modified fields are patched, and rows are deleted by key
+    let mut cmd = wrk.command("patch");
+    cmd.args(["base.csv", "patch.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name", "qty"],
+        svec!["1", "a", "10"],
+        svec!["2", "x", "25"],
+        svec!["4", "d", "40"],
+    ];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("patch");
+    cmd.args(["--strict", "base.csv", "patch.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn patch_report() {
+    let wrk = Workdir::new("patch_report");
+    let mut base = base();
+    base.push(svec!["4", "z", "0"]);
+    wrk.create("base.csv", base);
+    wrk.create("patch.csv", patch());
+
+    let mut cmd = wrk.command("patch");
+    cmd.args(["--report", "report.csv", "base.csv", "patch.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name", "qty"],
+        svec!["1", "a", "10"],
+        svec!["2", "b", "25"],
+        svec!["4", "z", "0"],
+    ];
+    assert_eq!(got, expected);
+
+    let got: String = wrk.read_to_string("report.csv");
+    let expected = "\
+row,op,key,status,reason
+1,modify,2,applied,
+3,delete,3,applied,
+4,add,4,failed,key already exists
+";
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("patch");
+    cmd.args(["--strict", "base.csv", "patch.csv"]);
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn patch_not_a_diff() {
+    let wrk = Workdir::new("patch_not_a_diff");
+    wrk.create("base.csv", base());
+
+    let mut cmd = wrk.command("patch");
+    cmd.args(["base.csv", "base.csv"]);
+    wrk.assert_err(&mut cmd);
+}
@@ -0,0 +1,67 @@
+use crate::workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["date", "sensor", "reading"],
+        svec!["2024-01-01", "s1", "1"],
+        svec!["2024-01-01", "s2", "2"],
+        svec!["2024-01-02", "s1", "3"],
+        svec!["2024-01-02", "s1", "4"],
+        svec!["2024-01-02", "s2", "5"],
+    ]
+}
+
+#[test]
+fn pivotp_sum() {
+    let wrk = Workdir::new("pivotp_sum");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("pivotp");
+    cmd.arg("sensor")
+        .args(["--index", "date"])
+        .args(["--values", "reading"])
+        .args(["--agg", "sum"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["date", "s1", "s2"],
+        svec!["2024-01-01", "1", "2"],
+        svec!["2024-01-02", "7", "5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn pivotp_derived_index() {
+    let wrk = Workdir::new("pivotp_derived_index");
+    wrk.create("in.csv", data());
+
+    // date is used as the index as it's the only column left
+    let mut cmd = wrk.command("pivotp");
+    cmd.arg("sensor")
+        .args(["--values", "reading"])
+        .args(["--agg", "first"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["date", "s1", "s2"],
+        svec!["2024-01-01", "1", "2"],
+        svec!["2024-01-02", "3", "5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn pivotp_invalid_column() {
+    let wrk = Workdir::new("pivotp_invalid_column");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("pivotp");
+    cmd.arg("nonexistent")
+        .args(["--index", "date"])
+        .arg("in.csv");
+
+    wrk.assert_err(&mut cmd);
+}
@@ -292,3 +292,26 @@ fn replace_groups() {
     ];
     assert_eq!(got, expected);
 }
+
+#[test]
+fn replace_parallel() {
+    let wrk = Workdir::new("replace_parallel");
+    let mut rows = vec![svec!["id", "color"]];
+    for i in 0..1000 {
+        let color = if i % 3 == 0 { "yellow" } else { "blue" };
+        rows.push(vec![i.to_string(), color.to_string()]);
+    }
+    wrk.create_indexed("data.csv", rows);
+
+    let mut cmd = wrk.command("replace");
+    cmd.args(["yellow", "red", "--jobs", "4", "data.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 1001);
+    for (i, row) in got[1..].iter().enumerate() {
+        let color = if i % 3 == 0 { "red" } else { "blue" };
+        assert_eq!(row, &svec![i.to_string(), color]);
+    }
+
+    let got_err = wrk.output_stderr(&mut cmd);
+    assert_eq!(got_err, "334\n");
+}
@@ -0,0 +1,153 @@
+use crate::workdir::Workdir;
+
+fn readings() -> Vec<Vec<String>> {
+    vec![
+        svec!["sensor", "ts", "temp"],
+        svec!["a", "2024-01-01 00:05:00", "10"],
+        svec!["b", "2024-01-01 00:10:00", "5"],
+        svec!["a", "2024-01-01 00:20:00", "14"],
+        svec!["a", "2024-01-01 00:25:00", "15"],
+        svec!["a", "not a date", "99"],
+        svec!["a", "2024-01-01 00:55:00", "20"],
+    ]
+}
+
+#[test]
+fn resample_group_fill_null() {
+    let wrk = Workdir::new("resample_group_fill_null");
+    wrk.create("in.csv", readings());
+
+    let mut cmd = wrk.command("resample");
+    cmd.arg("ts")
+        .arg("15m")
+        .args(["--group", "sensor"])
+        .args(["--agg", "count,mean:temp"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["sensor", "ts", "count", "mean_temp"],
+        svec!["a", "2024-01-01T00:00:00Z", "1", "10"],
+        svec!["a", "2024-01-01T00:15:00Z", "2", "14.5"],
+        svec!["a", "2024-01-01T00:30:00Z", "0", ""],
+        svec!["a", "2024-01-01T00:45:00Z", "1", "20"],
+        svec!["b", "2024-01-01T00:00:00Z", "1", "5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+fn hourly() -> Vec<Vec<String>> {
+    vec![
+        svec!["ts", "value", "status"],
+        svec!["2024-01-01T00:10:00Z", "10", "ok"],
+        svec!["2024-01-01T03:20:00Z", "16", "ok"],
+        svec!["2024-01-01T04:00:00Z", "17", "fail"],
+    ]
+}
+
+#[test]
+fn resample_fill_previous() {
+    let wrk = Workdir::new("resample_fill_previous");
+    wrk.create("in.csv", hourly());
+
+    let mut cmd = wrk.command("resample");
+    cmd.arg("ts")
+        .arg("1h")
+        .args(["-a", "count,last:value,last:status"])
+        .args(["--fill", "previous"])
+        .args(["--formatstr", "%H:%M"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["ts", "count", "last_value", "last_status"],
+        svec!["00:00", "1", "10", "ok"],
+        svec!["01:00", "0", "10", "ok"],
+        svec!["02:00", "0", "10", "ok"],
+        svec!["03:00", "1", "16", "ok"],
+        svec!["04:00", "1", "17", "fail"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn resample_fill_linear() {
+    let wrk = Workdir::new("resample_fill_linear");
+    wrk.create("in.csv", hourly());
+
+    let mut cmd = wrk.command("resample");
+    cmd.arg("ts")
+        .arg("1h")
+        .args(["-a", "sum:value,first:status"])
+        .args(["--fill", "linear"])
+        .args(["--formatstr", "%H:%M"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["ts", "sum_value", "first_status"],
+        svec!["00:00", "10", "ok"],
+        svec!["01:00", "12", ""],
+        svec!["02:00", "14", ""],
+        svec!["03:00", "16", "ok"],
+        svec!["04:00", "17", "fail"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn resample_daily() {
+    let wrk = Workdir::new("resample_daily");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["date", "amount"],
+            svec!["2024-03-03", "1.5"],
+            svec!["2024-03-01 23:59:59", "1"],
+            svec!["2024-03-01", "2"],
+        ],
+    );
+
+    let mut cmd = wrk.command("resample");
+    cmd.arg("date")
+        .arg("1d")
+        .args(["-a", "sum:amount"])
+        .args(["--formatstr", "%Y-%m-%d"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["date", "sum_amount"],
+        svec!["2024-03-01", "3"],
+        svec!["2024-03-02", ""],
+        svec!["2024-03-03", "1.5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn resample_invalid_interval() {
+    let wrk = Workdir::new("resample_invalid_interval");
+    wrk.create("in.csv", hourly());
+
+    let mut cmd = wrk.command("resample");
+    cmd.arg("ts").arg("15x").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("resample");
+    cmd.arg("ts").arg("0h").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn resample_invalid_fill() {
+    let wrk = Workdir::new("resample_invalid_fill");
+    wrk.create("in.csv", hourly());
+
+    let mut cmd = wrk.command("resample");
+    cmd.arg("ts")
+        .arg("1h")
+        .args(["--fill", "spline"])
+        .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
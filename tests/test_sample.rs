@@ -483,3 +483,170 @@ fn sample_percentage_negative_sample_size_error() {
 
     wrk.assert_err(&mut cmd);
 }
+
+fn strata_data() -> Vec<Vec<String>> {
+    let mut rows = vec![svec!["id", "group", "weight"]];
+    for i in 1..=30 {
+        let group = match i % 3 {
+            0 => "a",
+            1 => "b",
+            _ => "c",
+        };
+        // only the even ids have a positive weight
+        let weight = if i % 2 == 0 { "1.5" } else { "0" };
+        rows.push(vec![i.to_string(), group.to_string(), weight.to_string()]);
+    }
+    rows
+}
+
+fn ids(rows: &[Vec<String>]) -> Vec<u32> {
+    rows[1..].iter().map(|r| r[0].parse().unwrap()).collect()
+}
+
+#[test]
+fn sample_stratified() {
+    let wrk = Workdir::new("sample_stratified");
+    wrk.create("in.csv", strata_data());
+
+    let mut cmd = wrk.command("sample");
+    cmd.args(["--stratified", "group"])
+        .args(["--seed", "42"])
+        .arg("3")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got[0], svec!["id", "group", "weight"]);
+    assert_eq!(got.len(), 10);
+    for group in ["a", "b", "c"] {
+        assert_eq!(got[1..].iter().filter(|r| r[1] == group).count(), 3);
+    }
+    // sampled rows are in input order
+    let ids = ids(&got);
+    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+    // the same seed gives the same sample
+    let mut cmd = wrk.command("sample");
+    cmd.args(["--stratified", "group"])
+        .args(["--seed", "42"])
+        .arg("3")
+        .arg("in.csv");
+    let got2: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, got2);
+}
+
+#[test]
+fn sample_stratified_percentage() {
+    let wrk = Workdir::new("sample_stratified_percentage");
+    wrk.create("in.csv", strata_data());
+
+    let mut cmd = wrk.command("sample");
+    cmd.args(["--stratified", "group"])
+        .args(["--seed", "42"])
+        .args(["--rng", "faster"])
+        .arg("0.5")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    for group in ["a", "b", "c"] {
+        assert_eq!(got[1..].iter().filter(|r| r[1] == group).count(), 5);
+    }
+}
+
+#[test]
+fn sample_weighted() {
+    let wrk = Workdir::new("sample_weighted");
+    wrk.create("in.csv", strata_data());
+
+    let mut cmd = wrk.command("sample");
+    cmd.args(["--weighted", "weight"])
+        .args(["--seed", "42"])
+        .arg("5")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 6);
+    // records with a zero weight are never sampled
+    assert!(ids(&got).iter().all(|id| id % 2 == 0));
+
+    // there are only 15 records with a positive weight
+    let mut cmd = wrk.command("sample");
+    cmd.args(["--weighted", "weight"])
+        .args(["--rng", "cryptosecure"])
+        .arg("20")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(ids(&got), (1..=15).map(|i| i * 2).collect::<Vec<u32>>());
+}
+
+#[test]
+fn sample_systematic() {
+    let wrk = Workdir::new("sample_systematic");
+    wrk.create("in.csv", strata_data());
+
+    let mut cmd = wrk.command("sample");
+    cmd.arg("--systematic")
+        .args(["--seed", "42"])
+        .arg("6")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let ids = ids(&got);
+    assert_eq!(ids.len(), 6);
+    // every 5th record, starting from one of the first 5 records
+    assert!(ids[0] <= 5);
+    assert!(ids.windows(2).all(|w| w[1] - w[0] == 5));
+}
+
+#[test]
+fn sample_cluster() {
+    let wrk = Workdir::new("sample_cluster");
+    wrk.create("in.csv", strata_data());
+
+    let mut cmd = wrk.command("sample");
+    cmd.args(["--cluster", "group"])
+        .args(["--seed", "42"])
+        .arg("2")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // all the 10 records of each of the 2 sampled clusters
+    assert_eq!(got.len(), 21);
+    let mut groups: Vec<&str> = got[1..].iter().map(|r| r[1].as_str()).collect();
+    groups.sort_unstable();
+    groups.dedup();
+    assert_eq!(groups.len(), 2);
+}
+
+#[test]
+fn sample_bootstrap() {
+    let wrk = Workdir::new("sample_bootstrap");
+    wrk.create("in.csv", strata_data());
+
+    let mut cmd = wrk.command("sample");
+    cmd.arg("--bootstrap")
+        .args(["--seed", "42"])
+        .arg("100")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let ids = ids(&got);
+    // sampling with replacement can sample more records than the input has
+    assert_eq!(ids.len(), 100);
+    assert!(ids.windows(2).all(|w| w[0] <= w[1]));
+    assert!(ids.iter().all(|id| (1..=30).contains(id)));
+}
+
+#[test]
+fn sample_multiple_modes_error() {
+    let wrk = Workdir::new("sample_multiple_modes_error");
+    wrk.create("in.csv", strata_data());
+
+    let mut cmd = wrk.command("sample");
+    cmd.arg("--bootstrap")
+        .arg("--systematic")
+        .arg("5")
+        .arg("in.csv");
+
+    wrk.assert_err(&mut cmd);
+}
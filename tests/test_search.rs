@@ -652,3 +652,54 @@ fn search_literal() {
 
     wrk.assert_success(&mut cmd);
 }
+
+fn needles(wrk: &Workdir) {
+    let mut rows = vec![svec!["id", "text"]];
+    for i in 0..1000 {
+        let text = if i % 97 == 0 {
+            format!("needle {i}")
+        } else {
+            "hay".to_string()
+        };
+        rows.push(vec![i.to_string(), text]);
+    }
+    wrk.create_indexed("data.csv", rows);
+}
+
+#[test]
+fn search_parallel_flag_count() {
+    let wrk = Workdir::new("search_parallel_flag_count");
+    needles(&wrk);
+
+    let mut cmd = wrk.command("search");
+    cmd.args([
+        "needle", "--flag", "found", "--count", "--jobs", "1", "data.csv",
+    ]);
+    let expected: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(expected.len(), 1001);
+    assert_eq!(expected[99], svec!["97", "needle 97", "99"]);
+
+    let mut cmd = wrk.command("search");
+    cmd.args([
+        "needle", "--flag", "found", "--count", "--jobs", "4", "data.csv",
+    ]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, expected);
+
+    let got_err = wrk.output_stderr(&mut cmd);
+    assert_eq!(got_err, "11\n");
+}
+
+#[test]
+fn search_parallel_quick() {
+    let wrk = Workdir::new("search_parallel_quick");
+    needles(&wrk);
+
+    let mut cmd = wrk.command("search");
+    cmd.args(["needle [1-9]", "--quick", "--jobs", "4", "data.csv"]);
+
+    let got_err = wrk.output_stderr(&mut cmd);
+    assert_eq!(got_err, "98\n");
+    let got: String = wrk.stdout(&mut cmd);
+    assert_eq!(got, "");
+}
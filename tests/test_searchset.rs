@@ -467,3 +467,109 @@ fn searchset_literal() {
     assert_eq!(got, expected);
     wrk.assert_success(&mut cmd);
 }
+
+#[test]
+fn searchset_parallel_flag_unmatched_output() {
+    let wrk = Workdir::new("searchset_parallel_flag_unmatched_output");
+    let mut rows = vec![svec!["id", "text"]];
+    for i in 0..1000 {
+        let text = match i % 10 {
+            0 => "needle",
+            5 => "pin",
+            _ => "hay",
+        };
+        rows.push(vec![i.to_string(), text.to_string()]);
+    }
+    wrk.create_indexed("data.csv", rows);
+    wrk.create("regexset.txt", vec![svec!["needle"], svec!["pin"]]);
+
+    let mut cmd = wrk.command("searchset");
+    cmd.args(["regexset.txt", "data.csv", "--flag", "found"])
+        .args(["--flag-matches-only", "--unmatched-output", "unmatched.csv"])
+        .args(["--count", "--jobs", "4"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got.len(), 201);
+    assert_eq!(got[1], svec!["0", "needle", "2;1"]);
+    assert_eq!(got[2], svec!["5", "pin", "7;2"]);
+    assert_eq!(got[200], svec!["995", "pin", "997;2"]);
+
+    let unmatched: String = wrk.from_str(&wrk.path("unmatched.csv"));
+    let unmatched: Vec<&str> = unmatched.lines().collect();
+    assert_eq!(unmatched.len(), 800);
+    assert_eq!(unmatched[0], "1,hay");
+    assert_eq!(unmatched[799], "999,hay");
+
+    let got_err = wrk.output_stderr(&mut cmd);
+    assert_eq!(got_err, "200\n");
+}
+
+fn dictionary_setup(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["name", "desc"],
+            svec!["Acme Corp", "sells widgets"],
+            svec!["Foo", "bar"],
+            svec!["acme", "widget maker"],
+            svec!["x", "widgets"],
+            svec!["Café acme", ""],
+        ],
+    );
+    // the empty line & the duplicate term are ignored
+    wrk.create_from_string("dictionary.txt", "acme\nwidget\n\nacme\n");
+    wrk
+}
+
+#[test]
+fn searchset_dictionary_flag() {
+    let wrk = dictionary_setup("searchset_dictionary_flag");
+
+    let mut cmd = wrk.command("searchset");
+    cmd.args(["dictionary.txt", "data.csv", "--dictionary"])
+        .args(["--flag", "matched", "--count"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "desc", "matched"],
+        svec!["Acme Corp", "sells widgets", "2;widget@6"],
+        svec!["Foo", "bar", "0"],
+        svec!["acme", "widget maker", "4;acme@0"],
+        svec!["x", "widgets", "5;widget@0"],
+        svec!["Café acme", "", "6;acme@5"],
+    ];
+    assert_eq!(got, expected);
+
+    let got_err = wrk.output_stderr(&mut cmd);
+    assert_eq!(got_err, "4\n");
+}
+
+#[test]
+fn searchset_dictionary_ignore_case_whole_word() {
+    let wrk = dictionary_setup("searchset_dictionary_ignore_case_whole_word");
+
+    let mut cmd = wrk.command("searchset");
+    cmd.args(["dictionary.txt", "data.csv", "--dictionary"])
+        .args(["--ignore-case", "--whole-word", "--flag", "matched"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["name", "desc", "matched"],
+        svec!["Acme Corp", "sells widgets", "2;acme@0"],
+        svec!["Foo", "bar", "0"],
+        svec!["acme", "widget maker", "4;acme@0"],
+        svec!["x", "widgets", "0"],
+        svec!["Café acme", "", "6;acme@5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn searchset_whole_word_requires_dictionary() {
+    let wrk = dictionary_setup("searchset_whole_word_requires_dictionary");
+
+    let mut cmd = wrk.command("searchset");
+    cmd.args(["dictionary.txt", "data.csv", "--whole-word"]);
+    wrk.assert_err(&mut cmd);
+}
@@ -516,6 +516,251 @@ fn sort_random_secure() {
     assert_eq!(got, expected);
 }
 
+#[test]
+fn sort_keys_typed() {
+    let wrk = Workdir::new("sort_keys_typed");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["date", "amount", "name"],
+            svec!["2024-01-02", "10", "file10"],
+            svec!["2024-01-02", "9", "file2"],
+            svec!["2023-12-31", "5", "file1"],
+            svec!["", "7", "x"],
+            svec!["2024-01-02", "10", "file9"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["-k", "date:desc:date,amount:asc:num,name:asc:natural"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["date", "amount", "name"],
+        svec!["2024-01-02", "9", "file2"],
+        svec!["2024-01-02", "10", "file9"],
+        svec!["2024-01-02", "10", "file10"],
+        svec!["2023-12-31", "5", "file1"],
+        svec!["", "7", "x"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys_version_nulls_first() {
+    let wrk = Workdir::new("sort_keys_version_nulls_first");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["v"],
+            svec!["1.10.0"],
+            svec!["1.2.0"],
+            svec!["1.0.0"],
+            svec![""],
+            svec!["v1.9"],
+            svec!["1.0.0-rc1"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--keys", "1:desc:version:nulls-first"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["v"],
+        svec![""],
+        svec!["1.10.0"],
+        svec!["v1.9"],
+        svec!["1.2.0"],
+        svec!["1.0.0"],
+        svec!["1.0.0-rc1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys_unique_istr() {
+    let wrk = Workdir::new("sort_keys_unique_istr");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["name", "n"],
+            svec!["b", "1"],
+            svec!["B", "2"],
+            svec!["a", "3"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--keys", "name:istr"])
+        .arg("--unique")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["name", "n"], svec!["a", "3"], svec!["b", "1"]];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys_date_prefer_dmy() {
+    let wrk = Workdir::new("sort_keys_date_prefer_dmy");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["date"],
+            svec!["01/02/2024"],
+            svec!["15/01/2024"],
+            svec!["03/01/2024"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sort");
+    cmd.env("QSV_PREFER_DMY", "1")
+        .args(["--keys", "date:date"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["date"],
+        svec!["03/01/2024"],
+        svec!["15/01/2024"],
+        svec!["01/02/2024"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_keys_num_large_ints() {
+    let wrk = Workdir::new("sort_keys_num_large_ints");
+    // these ids round to the same f64, but must still be sorted exactly
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["id"],
+            svec!["9223372036854775807"],
+            svec!["9223372036854775805"],
+            svec!["9.3e18"],
+            svec!["9223372036854775806"],
+            svec!["-9223372036854775807"],
+            svec!["-9223372036854775808"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--keys", "id:num"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id"],
+        svec!["-9223372036854775808"],
+        svec!["-9223372036854775807"],
+        svec!["9223372036854775805"],
+        svec!["9223372036854775806"],
+        svec!["9223372036854775807"],
+        svec!["9.3e18"],
+    ];
+    assert_eq!(got, expected);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--keys", "id:desc:num"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let mut expected_desc = expected[1..].to_vec();
+    expected_desc.reverse();
+    expected_desc.insert(0, svec!["id"]);
+    assert_eq!(got, expected_desc);
+}
+
+#[test]
+fn sort_keys_errors() {
+    let wrk = Workdir::new("sort_keys_errors");
+    wrk.create("in.csv", vec![svec!["name", "n"], svec!["b", "1"]]);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--keys", "name:asc"])
+        .arg("--numeric")
+        .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--keys", "name:sideways"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--keys", "nosuchcol:desc"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn sort_collate() {
+    let wrk = Workdir::new("sort_collate");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["city"],
+            svec!["Zürich"],
+            svec!["Ängelholm"],
+            svec!["Berlin"],
+            svec!["apple"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--collate", "en"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["city"],
+        svec!["Ängelholm"],
+        svec!["apple"],
+        svec!["Berlin"],
+        svec!["Zürich"],
+    ];
+    assert_eq!(got, expected);
+
+    // in Swedish, Ä sorts after Z
+    let mut cmd = wrk.command("sort");
+    cmd.args(["--collate", "sv"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["city"],
+        svec!["apple"],
+        svec!["Berlin"],
+        svec!["Zürich"],
+        svec!["Ängelholm"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn sort_accent_insensitive_unique() {
+    let wrk = Workdir::new("sort_accent_insensitive_unique");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["word"],
+            svec!["resume"],
+            svec!["Résumé"],
+            svec!["cafe"],
+            svec!["résumé"],
+            svec!["café"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sort");
+    cmd.arg("--accent-insensitive")
+        .arg("--ignore-case")
+        .arg("--unique")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![svec!["word"], svec!["cafe"], svec!["resume"]];
+    assert_eq!(got, expected);
+}
+
 /// Order `a` and `b` lexicographically using `Ord`
 pub fn iter_cmp<A, L, R>(mut a: L, mut b: R) -> cmp::Ordering
 where
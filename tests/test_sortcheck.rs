@@ -226,3 +226,83 @@ fn sortcheck_simple_all_json_progressbar() {
     );
     wrk.assert_err(&mut cmd);
 }
+
+#[test]
+fn sortcheck_keys() {
+    let wrk = Workdir::new("sortcheck_keys");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["date", "amount", "name"],
+            svec!["2024-01-02", "9", "file2"],
+            svec!["2024-01-02", "10", "file9"],
+            svec!["2024-01-02", "10", "file10"],
+            svec!["2023-12-31", "5", "file1"],
+            svec!["", "7", "x"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sortcheck");
+    cmd.args(["-k", "date:desc:date,amount:asc:num,name:asc:natural"])
+        .arg("in.csv");
+    wrk.assert_success(&mut cmd);
+
+    // a plain lexicographic check on the same columns fails
+    let mut cmd = wrk.command("sortcheck");
+    cmd.args(["--select", "date,amount,name"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn sortcheck_keys_json() {
+    let wrk = Workdir::new("sortcheck_keys_json");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["name"],
+            svec!["file2"],
+            svec!["file10"],
+            svec!["file10"],
+            svec!["file9"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sortcheck");
+    cmd.args(["--keys", "name:natural"])
+        .arg("--json")
+        .arg("in.csv");
+
+    let output = cmd.output().unwrap();
+    let got_stdout = std::str::from_utf8(&output.stdout).unwrap_or_default();
+
+    assert_eq!(
+        got_stdout,
+        r#"{"sorted":false,"record_count":4,"unsorted_breaks":1,"dupe_count":-1}
+"#
+    );
+    wrk.assert_err(&mut cmd);
+}
+
+#[test]
+fn sortcheck_collate() {
+    let wrk = Workdir::new("sortcheck_collate");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["city"],
+            svec!["Ängelholm"],
+            svec!["apple"],
+            svec!["Berlin"],
+            svec!["Zürich"],
+        ],
+    );
+
+    let mut cmd = wrk.command("sortcheck");
+    cmd.args(["--collate", "en"]).arg("in.csv");
+    wrk.assert_success(&mut cmd);
+
+    // not sorted in byte order
+    let mut cmd = wrk.command("sortcheck");
+    cmd.arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
@@ -855,3 +855,142 @@ fn split_kbsize_boston_5k_no_headers() {
     assert!(wrk.path("84.csv").exists());
     assert!(wrk.path("93.csv").exists());
 }
+
+fn labeled_data() -> Vec<Vec<String>> {
+    let mut rows = vec![svec!["id", "label", "patient"]];
+    for i in 0..20 {
+        let label = if i < 10 { "a" } else { "b" };
+        rows.push(vec![
+            i.to_string(),
+            label.to_string(),
+            format!("p{}", i % 4),
+        ]);
+    }
+    rows
+}
+
+/// the records of a split file, without the header row
+fn split_rows(wrk: &Workdir, name: &str) -> Vec<Vec<String>> {
+    let contents = wrk.read_to_string(name);
+    let mut rdr = csv::Reader::from_reader(contents.as_bytes());
+    rdr.records()
+        .map(|r| r.unwrap().iter().map(ToOwned::to_owned).collect())
+        .collect()
+}
+
+#[test]
+fn split_ratios() {
+    let wrk = Workdir::new("split_ratios");
+    wrk.create("in.csv", labeled_data());
+
+    let mut cmd = wrk.command("split");
+    cmd.args(["--ratios", "70,15,15"])
+        .args(["--seed", "42"])
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    let train = split_rows(&wrk, "train.csv");
+    let test = split_rows(&wrk, "test.csv");
+    let validation = split_rows(&wrk, "validation.csv");
+    assert_eq!((train.len(), test.len(), validation.len()), (14, 3, 3));
+
+    // every record is in exactly one split, in input order
+    let mut ids: Vec<u32> = train
+        .iter()
+        .chain(&test)
+        .chain(&validation)
+        .map(|r| r[0].parse().unwrap())
+        .collect();
+    assert!(train
+        .windows(2)
+        .all(|w| w[0][0].parse::<u32>().unwrap() < w[1][0].parse::<u32>().unwrap()));
+    ids.sort_unstable();
+    assert_eq!(ids, (0..20).collect::<Vec<u32>>());
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&wrk.read_to_string("manifest.json")).unwrap();
+    assert_eq!(manifest["rows"], 20);
+    assert_eq!(manifest["splits"][0]["name"], "train");
+    assert_eq!(manifest["splits"][0]["file"], "train.csv");
+    assert_eq!(manifest["splits"][0]["rows"], 14);
+    assert_eq!(manifest["splits"][2]["rows"], 3);
+
+    // the same seed gives the same splits
+    let mut cmd = wrk.command("split");
+    cmd.args(["--ratios", "70,15,15"])
+        .args(["--seed", "42"])
+        .arg(&wrk.path("again"))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+    assert_eq!(train, split_rows(&wrk, "again/train.csv"));
+}
+
+#[test]
+fn split_ratios_stratified() {
+    let wrk = Workdir::new("split_ratios_stratified");
+    wrk.create("in.csv", labeled_data());
+
+    let mut cmd = wrk.command("split");
+    cmd.args(["--ratios", "0.6,0.4"])
+        .args(["--split-names", "fit,holdout"])
+        .args(["--stratify", "label"])
+        .args(["--rng", "faster"])
+        .args(["--seed", "7"])
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    for (name, expected) in [("fit.csv", 6), ("holdout.csv", 4)] {
+        let rows = split_rows(&wrk, name);
+        for label in ["a", "b"] {
+            assert_eq!(rows.iter().filter(|r| r[1] == label).count(), expected);
+        }
+    }
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&wrk.read_to_string("manifest.json")).unwrap();
+    assert_eq!(manifest["stratify"], "label");
+    assert_eq!(manifest["splits"][1]["labels"]["a"], 4);
+    assert_eq!(manifest["splits"][1]["labels"]["b"], 4);
+}
+
+#[test]
+fn split_ratios_grouped() {
+    let wrk = Workdir::new("split_ratios_grouped");
+    wrk.create("in.csv", labeled_data());
+
+    let mut cmd = wrk.command("split");
+    cmd.args(["--ratios", "50,50"])
+        .args(["--group", "patient"])
+        .args(["--filename", "set_{}.csv"])
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.run(&mut cmd);
+
+    let train = split_rows(&wrk, "set_train.csv");
+    let test = split_rows(&wrk, "set_test.csv");
+    // 2 of the 4 patients in each split, with all their 5 records
+    assert_eq!((train.len(), test.len()), (10, 10));
+    assert!(train.iter().all(|r| test.iter().all(|t| t[2] != r[2])));
+}
+
+#[test]
+fn split_ratios_invalid() {
+    let wrk = Workdir::new("split_ratios_invalid");
+    wrk.create("in.csv", labeled_data());
+
+    let mut cmd = wrk.command("split");
+    cmd.args(["--ratios", "70,-30"])
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    // more ratios than split names
+    let mut cmd = wrk.command("split");
+    cmd.args(["--ratios", "1,1,1"])
+        .args(["--split-names", "train,test"])
+        .arg(&wrk.path("."))
+        .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
@@ -0,0 +1,179 @@
+use crate::workdir::Workdir;
+
+fn setup(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create(
+        "txns.csv",
+        vec![
+            svec!["id", "customer", "amount"],
+            svec!["1", "a", "10"],
+            svec!["2", "b", "5"],
+            svec!["3", "a", "30"],
+            svec!["4", "a", "20"],
+            svec!["5", "b", "50"],
+            svec!["6", "a", "30"],
+            svec!["7", "b", "5"],
+        ],
+    );
+    wrk
+}
+
+#[test]
+fn topn_largest_per_group() {
+    let wrk = setup("topn_largest_per_group");
+
+    let mut cmd = wrk.command("topn");
+    cmd.args([
+        "amount:num",
+        "--group",
+        "customer",
+        "--top",
+        "2",
+        "txns.csv",
+    ]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "customer", "amount"],
+        svec!["3", "a", "30"],
+        svec!["6", "a", "30"],
+        svec!["5", "b", "50"],
+        svec!["2", "b", "5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn topn_ties_last() {
+    let wrk = setup("topn_ties_last");
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["amount:num", "-g", "customer", "--top", "2"])
+        .args(["--ties", "last", "txns.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "customer", "amount"],
+        svec!["6", "a", "30"],
+        svec!["3", "a", "30"],
+        svec!["5", "b", "50"],
+        svec!["7", "b", "5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn topn_ties_all_rank_col() {
+    let wrk = setup("topn_ties_all_rank_col");
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["amount:num", "-g", "customer", "--top", "2"])
+        .args(["--ties", "all", "--rank-col", "rank", "txns.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "customer", "amount", "rank"],
+        svec!["3", "a", "30", "1"],
+        svec!["6", "a", "30", "1"],
+        svec!["5", "b", "50", "1"],
+        svec!["2", "b", "5", "2"],
+        svec!["7", "b", "5", "2"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn topn_smallest() {
+    let wrk = setup("topn_smallest");
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["amount:num", "-g", "customer", "--top", "1"])
+        .args(["--smallest", "--ties", "all", "txns.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "customer", "amount"],
+        svec!["1", "a", "10"],
+        svec!["2", "b", "5"],
+        svec!["7", "b", "5"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn topn_date_vs_str() {
+    let wrk = Workdir::new("topn_date_vs_str");
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["day"],
+            svec!["10/1/2024"],
+            svec!["2/1/2024"],
+            svec!["9/15/2024"],
+        ],
+    );
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["day:date", "--top", "1", "data.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["day"], svec!["10/1/2024"]]);
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["day", "--top", "1", "data.csv"]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, vec![svec!["day"], svec!["9/15/2024"]]);
+}
+
+#[test]
+fn topn_parallel() {
+    let wrk = Workdir::new("topn_parallel");
+    let mut rows = vec![svec!["id", "group", "amount"]];
+    for i in 0..1000 {
+        rows.push(vec![
+            i.to_string(),
+            (i % 7).to_string(),
+            ((i * 37) % 101).to_string(),
+        ]);
+    }
+    wrk.create_indexed("data.csv", rows);
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["amount:num", "-g", "group", "--top", "5"]).args([
+        "--ties",
+        "all",
+        "--rank-col",
+        "rank",
+        "--jobs",
+        "1",
+        "data.csv",
+    ]);
+    let expected: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    // 7 groups, each with at least 5 records
+    assert!(expected.len() > 35);
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["amount:num", "-g", "group", "--top", "5"]).args([
+        "--ties",
+        "all",
+        "--rank-col",
+        "rank",
+        "--jobs",
+        "4",
+        "data.csv",
+    ]);
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn topn_invalid_args() {
+    let wrk = setup("topn_invalid_args");
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["amount:num", "--top", "0", "txns.csv"]);
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("topn");
+    cmd.args(["amount:num", "--ties", "some", "txns.csv"]);
+    wrk.assert_err(&mut cmd);
+}
@@ -0,0 +1,104 @@
+use crate::workdir::Workdir;
+
+fn setup(name: &str) -> Workdir {
+    let wrk = Workdir::new(name);
+    wrk.create(
+        "countries.csv",
+        vec![
+            svec!["code", "name", "region"],
+            svec!["FR", "France", "Europe"],
+            svec!["JP", "Japan", "Asia"],
+            svec!["BR", "Brazil", "Americas"],
+        ],
+    );
+    wrk.create(
+        "data.csv",
+        vec![
+            svec!["id", "country"],
+            svec!["1", "FR"],
+            svec!["2", "jp"],
+            svec!["3", "XX"],
+            svec!["4", "BR"],
+            svec!["5", "XX"],
+            svec!["6", ""],
+        ],
+    );
+    wrk
+}
+
+#[test]
+fn translate_replace() {
+    let wrk = setup("translate_replace");
+
+    let mut cmd = wrk.command("translate");
+    cmd.args(["country", "countries.csv", "data.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "country"],
+        svec!["1", "France"],
+        svec!["2", "jp"],
+        svec!["3", "XX"],
+        svec!["4", "Brazil"],
+        svec!["5", "XX"],
+        svec!["6", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn translate_ignore_case_default_unmatched() {
+    let wrk = setup("translate_ignore_case_default_unmatched");
+
+    let mut cmd = wrk.command("translate");
+    cmd.args(["country", "countries.csv", "data.csv"])
+        .args(["--ignore-case", "--default", "Unknown"])
+        .args(["--unmatched", "unmatched.csv"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "country"],
+        svec!["1", "France"],
+        svec!["2", "Japan"],
+        svec!["3", "Unknown"],
+        svec!["4", "Brazil"],
+        svec!["5", "Unknown"],
+        svec!["6", "Unknown"],
+    ];
+    assert_eq!(got, expected);
+
+    let unmatched = wrk.read_to_string("unmatched.csv");
+    assert_eq!(unmatched, "value,count\nXX,2\n,1\n");
+}
+
+#[test]
+fn translate_append() {
+    let wrk = setup("translate_append");
+
+    let mut cmd = wrk.command("translate");
+    cmd.args(["country", "countries.csv", "data.csv"])
+        .args(["--key-col", "code", "--value-col", "name,region"])
+        .args(["--append", "--prefix", "country_"]);
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "country", "country_name", "country_region"],
+        svec!["1", "FR", "France", "Europe"],
+        svec!["2", "jp", "", ""],
+        svec!["3", "XX", "", ""],
+        svec!["4", "BR", "Brazil", "Americas"],
+        svec!["5", "XX", "", ""],
+        svec!["6", "", "", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn translate_multiple_values_without_append() {
+    let wrk = setup("translate_multiple_values_without_append");
+
+    let mut cmd = wrk.command("translate");
+    cmd.args(["country", "countries.csv", "data.csv"])
+        .args(["--value-col", "name,region"]);
+    wrk.assert_err(&mut cmd);
+}
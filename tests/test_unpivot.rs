@@ -0,0 +1,91 @@
+use crate::workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["id", "name", "2022", "2023"],
+        svec!["1", "a", "10", "20"],
+        svec!["2", "b", "", "40"],
+    ]
+}
+
+#[test]
+fn unpivot_index() {
+    let wrk = Workdir::new("unpivot_index");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("unpivot");
+    cmd.args(["--index", "id,name"]).arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "name", "variable", "value"],
+        svec!["1", "a", "2022", "10"],
+        svec!["1", "a", "2023", "20"],
+        svec!["2", "b", "2022", ""],
+        svec!["2", "b", "2023", "40"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn unpivot_on_names_drop_empty() {
+    let wrk = Workdir::new("unpivot_on_names_drop_empty");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("unpivot");
+    cmd.args(["--index", "id"])
+        .args(["--on", "2022,2023"])
+        .args(["--variable-name", "year"])
+        .args(["--value-name", "sales"])
+        .arg("--drop-empty")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "year", "sales"],
+        svec!["1", "2022", "10"],
+        svec!["1", "2023", "20"],
+        svec!["2", "2023", "40"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn unpivot_melt_alias() {
+    let wrk = Workdir::new("unpivot_melt_alias");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("melt");
+    cmd.args(["--index", "id"])
+        .args(["--on", "name"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["id", "variable", "value"],
+        svec!["1", "name", "a"],
+        svec!["2", "name", "b"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn unpivot_no_headers() {
+    let wrk = Workdir::new("unpivot_no_headers");
+    wrk.create("in.csv", data().into_iter().skip(1).collect::<Vec<_>>());
+
+    let mut cmd = wrk.command("unpivot");
+    cmd.args(["--index", "1"])
+        .args(["--on", "3-4"])
+        .arg("--no-headers")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["1", "3", "10"],
+        svec!["1", "4", "20"],
+        svec!["2", "3", ""],
+        svec!["2", "4", "40"],
+    ];
+    assert_eq!(got, expected);
+}
@@ -0,0 +1,204 @@
+use crate::workdir::Workdir;
+
+fn data() -> Vec<Vec<String>> {
+    vec![
+        svec!["ticker", "date", "close"],
+        svec!["a", "1", "10"],
+        svec!["a", "2", "12"],
+        svec!["a", "3", ""],
+        svec!["a", "4", "20"],
+        svec!["b", "1", "5"],
+        svec!["b", "2", "7"],
+    ]
+}
+
+#[test]
+fn window_lag_lead_cumsum_rolling_mean() {
+    let wrk = Workdir::new("window_lag_lead_cumsum_rolling_mean");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("window");
+    cmd.arg("row_number,lag:close,lead:close,cumsum:close,rolling_mean:close:2")
+        .args(["--partition", "ticker"])
+        .args(["--order-by", "date"])
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec![
+            "ticker",
+            "date",
+            "close",
+            "row_number",
+            "lag_close",
+            "lead_close",
+            "cumsum_close",
+            "rolling_mean2_close"
+        ],
+        svec!["a", "1", "10", "1", "", "12", "10", "10"],
+        svec!["a", "2", "12", "2", "10", "", "22", "11"],
+        svec!["a", "3", "", "3", "12", "20", "22", "12"],
+        svec!["a", "4", "20", "4", "", "", "42", "20"],
+        svec!["b", "1", "5", "1", "", "7", "5", "5"],
+        svec!["b", "2", "7", "2", "5", "", "12", "6"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn window_offsets_cumcount_rolling() {
+    let wrk = Workdir::new("window_offsets_cumcount_rolling");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("window");
+    cmd.arg(
+        "lag:close:2,lead:close:2,cumcount:close,rolling_min:close:3,rolling_max:close:3,\
+         rolling_sum:close:3",
+    )
+    .args(["-p", "ticker"])
+    .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec![
+            "ticker",
+            "date",
+            "close",
+            "lag2_close",
+            "lead2_close",
+            "cumcount_close",
+            "rolling_min3_close",
+            "rolling_max3_close",
+            "rolling_sum3_close"
+        ],
+        svec!["a", "1", "10", "", "", "1", "10", "10", "10"],
+        svec!["a", "2", "12", "", "20", "2", "10", "12", "22"],
+        svec!["a", "3", "", "10", "", "2", "10", "12", "22"],
+        svec!["a", "4", "20", "12", "", "3", "12", "20", "32"],
+        svec!["b", "1", "5", "", "", "1", "5", "5", "5"],
+        svec!["b", "2", "7", "", "", "2", "5", "7", "12"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn window_no_partition() {
+    let wrk = Workdir::new("window_no_partition");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("window");
+    cmd.arg("cumcount,lead:ticker").arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["ticker", "date", "close", "cumcount", "lead_ticker"],
+        svec!["a", "1", "10", "1", "a"],
+        svec!["a", "2", "12", "2", "a"],
+        svec!["a", "3", "", "3", "a"],
+        svec!["a", "4", "20", "4", "b"],
+        svec!["b", "1", "5", "5", "b"],
+        svec!["b", "2", "7", "6", ""],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn window_unsorted() {
+    let wrk = Workdir::new("window_unsorted");
+    wrk.create(
+        "in.csv",
+        vec![
+            svec!["ticker", "date", "close"],
+            svec!["b", "1", "5"],
+            svec!["a", "1", "10"],
+        ],
+    );
+
+    let mut cmd = wrk.command("window");
+    cmd.arg("row_number")
+        .args(["-p", "ticker"])
+        .args(["-s", "date"])
+        .arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    // without the sort check, each run of the same ticker is a partition
+    let mut cmd = wrk.command("window");
+    cmd.arg("row_number")
+        .args(["-p", "ticker"])
+        .arg("--no-check")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["ticker", "date", "close", "row_number"],
+        svec!["b", "1", "5", "1"],
+        svec!["a", "1", "10", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn window_numeric_order() {
+    let wrk = Workdir::new("window_numeric_order");
+    wrk.create(
+        "in.csv",
+        vec![svec!["day", "qty"], svec!["2", "1"], svec!["10", "2"]],
+    );
+
+    // 10 sorts before 2 lexicographically
+    let mut cmd = wrk.command("window");
+    cmd.arg("cumsum:qty").args(["-s", "day"]).arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    let mut cmd = wrk.command("window");
+    cmd.arg("cumsum:qty")
+        .args(["-s", "day"])
+        .arg("--numeric")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["day", "qty", "cumsum_qty"],
+        svec!["2", "1", "1"],
+        svec!["10", "2", "3"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn window_no_headers() {
+    let wrk = Workdir::new("window_no_headers");
+    wrk.create(
+        "in.csv",
+        vec![svec!["x", "1"], svec!["x", "2"], svec!["y", "3"]],
+    );
+
+    let mut cmd = wrk.command("window");
+    cmd.arg("lag:2,row_number")
+        .args(["-p", "1"])
+        .arg("--no-headers")
+        .arg("in.csv");
+
+    let got: Vec<Vec<String>> = wrk.read_stdout(&mut cmd);
+    let expected = vec![
+        svec!["x", "1", "", "1"],
+        svec!["x", "2", "1", "2"],
+        svec!["y", "3", "", "1"],
+    ];
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn window_invalid_function() {
+    let wrk = Workdir::new("window_invalid_function");
+    wrk.create("in.csv", data());
+
+    let mut cmd = wrk.command("window");
+    cmd.arg("median:close").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+
+    // rolling functions require a window size
+    let mut cmd = wrk.command("window");
+    cmd.arg("rolling_mean:close").arg("in.csv");
+    wrk.assert_err(&mut cmd);
+}
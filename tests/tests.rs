@@ -62,6 +62,8 @@ mod test_exclude;
 mod test_explode;
 mod test_extdedup;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
+mod test_extract;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
 mod test_extsort;
 #[cfg(feature = "fetch")]
 mod test_fetch;
@@ -78,6 +80,8 @@ mod test_foreach;
 mod test_frequency;
 #[cfg(all(feature = "feature_capable", feature = "geocode"))]
 mod test_geocode;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+mod test_groupby;
 mod test_headers;
 mod test_index;
 mod test_input;
@@ -92,7 +96,13 @@ mod test_jsonl;
 #[cfg(feature = "luau")]
 mod test_luau;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
+mod test_merge;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
 mod test_partition;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+mod test_patch;
+#[cfg(feature = "polars")]
+mod test_pivotp;
 #[cfg(feature = "prompt")]
 mod test_prompt;
 mod test_pseudo;
@@ -100,6 +110,8 @@ mod test_pseudo;
 mod test_py;
 mod test_rename;
 mod test_replace;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+mod test_resample;
 mod test_reverse;
 mod test_safenames;
 mod test_sample;
@@ -127,8 +139,16 @@ mod test_to;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
 mod test_tojsonl;
 #[cfg(any(feature = "feature_capable", feature = "lite"))]
+mod test_topn;
+#[cfg(feature = "feature_capable")]
+mod test_translate;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
 mod test_transpose;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+mod test_unpivot;
 mod test_validate;
+#[cfg(any(feature = "feature_capable", feature = "lite"))]
+mod test_window;
 
 fn qcheck<T: Testable>(p: T) {
     env::set_var("QSV_SKIPUTF8_CHECK", "1");